@@ -0,0 +1,92 @@
+//! Cost of listing a directory with 500k children for the children table:
+//! building the filtered, sorted listing versus reading it from the cache the
+//! UI consults several times per frame. Run with `cargo bench --bench listing`.
+
+use std::hint::black_box;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant, SystemTime};
+
+use storagescope::listing::{ListingCache, ListingSettings, list_children};
+use storagescope::model::{AgeSummary, FsEntryKind, NodeSummary, SizeMetric, SortMode};
+use storagescope::store::NodeStore;
+
+const DIR: &str = "/srv/data/inbox";
+const CHILDREN: u64 = 500_000;
+const ROUNDS: usize = 3;
+const CACHED_LOOKUPS: u32 = 100_000;
+
+fn node(path: PathBuf, bytes: u64) -> NodeSummary {
+    NodeSummary {
+        path,
+        kind: FsEntryKind::File,
+        apparent_bytes: bytes,
+        allocated_bytes: bytes,
+        naive_apparent_bytes: bytes,
+        naive_allocated_bytes: bytes,
+        children_count: 0,
+        excluded_entries: 0,
+        age: AgeSummary::default(),
+        owner: None,
+        is_complete: true,
+        last_updated: SystemTime::now(),
+    }
+}
+
+fn settings(sort_mode: SortMode, filter: &str) -> ListingSettings {
+    ListingSettings {
+        sort_mode,
+        metric: SizeMetric::Apparent,
+        filter: filter.to_string(),
+        older_than: None,
+        owner_filter: None,
+        owners_generation: 0,
+    }
+}
+
+fn best_of(mut run: impl FnMut()) -> Duration {
+    (0..ROUNDS)
+        .map(|_| {
+            let started = Instant::now();
+            run();
+            started.elapsed()
+        })
+        .min()
+        .expect("at least one round")
+}
+
+fn main() {
+    let dir = Path::new(DIR);
+    let mut nodes = NodeStore::new();
+    nodes.upsert(node(dir.to_path_buf(), 0));
+    for index in 0..CHILDREN {
+        // Scattered sizes so the size sorts do real work.
+        let bytes = index.wrapping_mul(2_654_435_761) % 1_000_000_007;
+        nodes.upsert(node(dir.join(format!("message-{index:07}.eml")), bytes));
+    }
+
+    for (name, settings) in [
+        ("size-desc", settings(SortMode::SizeDesc, "")),
+        ("name", settings(SortMode::Name, "")),
+        ("size-desc filtered", settings(SortMode::SizeDesc, "99")),
+    ] {
+        let build = || list_children(&nodes, None, dir, &settings, |_, _| true);
+        let built = best_of(|| {
+            black_box(build());
+        });
+
+        let mut cache = ListingCache::default();
+        let listed = cache.get_or_build(dir, &settings, build).paths.len();
+        let started = Instant::now();
+        for index in 0..CACHED_LOOKUPS {
+            let listing = cache.get_or_build(dir, &settings, build);
+            black_box(listing.paths.get(index as usize % listed.max(1)));
+        }
+        let cached = started.elapsed() / CACHED_LOOKUPS;
+
+        println!(
+            "{name:<20} {listed:>7} rows  build {:>9.2} ms  cached lookup {:>9.3} us",
+            built.as_secs_f64() * 1000.0,
+            cached.as_secs_f64() * 1_000_000.0
+        );
+    }
+}
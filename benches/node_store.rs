@@ -0,0 +1,147 @@
+//! Heap held by the in-memory tree for a synthetic large scan, comparing
+//! `NodeStore` with the path-keyed maps it replaced. Run with
+//! `cargo bench --bench node_store`.
+
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::{Instant, SystemTime};
+
+use storagescope::model::{AgeSummary, FsEntryKind, NodeSummary};
+use storagescope::store::NodeStore;
+
+const ROOT: &str = "/srv/data/projects";
+const DIRS: usize = 2_000;
+const SUBDIRS_PER_DIR: usize = 10;
+const FILES_PER_SUBDIR: usize = 50;
+
+/// Tracks the bytes currently allocated through the system allocator.
+struct Counting;
+
+static LIVE_BYTES: AtomicUsize = AtomicUsize::new(0);
+
+// SAFETY: every call is forwarded to `System` unchanged; only a counter is updated.
+unsafe impl GlobalAlloc for Counting {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        LIVE_BYTES.fetch_add(layout.size(), Ordering::Relaxed);
+        // SAFETY: same contract as our caller's.
+        unsafe { System.alloc(layout) }
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        LIVE_BYTES.fetch_sub(layout.size(), Ordering::Relaxed);
+        // SAFETY: same contract as our caller's.
+        unsafe { System.dealloc(ptr, layout) }
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        LIVE_BYTES.fetch_add(new_size, Ordering::Relaxed);
+        LIVE_BYTES.fetch_sub(layout.size(), Ordering::Relaxed);
+        // SAFETY: same contract as our caller's.
+        unsafe { System.realloc(ptr, layout, new_size) }
+    }
+}
+
+#[global_allocator]
+static ALLOCATOR: Counting = Counting;
+
+fn node(path: PathBuf, kind: FsEntryKind, bytes: u64) -> NodeSummary {
+    NodeSummary {
+        path,
+        kind,
+        apparent_bytes: bytes,
+        allocated_bytes: bytes,
+        naive_apparent_bytes: bytes,
+        naive_allocated_bytes: bytes,
+        children_count: 0,
+        excluded_entries: 0,
+        age: AgeSummary::default(),
+        owner: None,
+        is_complete: true,
+        last_updated: SystemTime::now(),
+    }
+}
+
+/// Calls `visit` with every node of the synthetic tree, parents first, as the
+/// scanner would report them with `--show-files true`.
+fn synthetic_tree(mut visit: impl FnMut(NodeSummary)) {
+    let root = PathBuf::from(ROOT);
+    visit(node(root.clone(), FsEntryKind::Dir, 0));
+    for dir_idx in 0..DIRS {
+        let dir = root.join(format!("project-{dir_idx:04}"));
+        visit(node(dir.clone(), FsEntryKind::Dir, 0));
+        for sub_idx in 0..SUBDIRS_PER_DIR {
+            let sub = dir.join(format!("module-{sub_idx:02}"));
+            visit(node(sub.clone(), FsEntryKind::Dir, 0));
+            for file_idx in 0..FILES_PER_SUBDIR {
+                let file = sub.join(format!("source-file-{file_idx:03}.rs"));
+                visit(node(file, FsEntryKind::File, 4096));
+            }
+        }
+    }
+}
+
+/// The layout `App` used before `NodeStore`: every summary keyed by its path,
+/// plus each path listed again under its parent.
+#[derive(Default)]
+struct PathMaps {
+    nodes: HashMap<PathBuf, NodeSummary>,
+    children: HashMap<PathBuf, Vec<PathBuf>>,
+}
+
+impl PathMaps {
+    fn insert(&mut self, node: NodeSummary) {
+        let path = node.path.clone();
+        let parent = path.parent().map(Path::to_path_buf);
+        if self.nodes.insert(path.clone(), node).is_none()
+            && let Some(parent) = parent
+        {
+            self.children.entry(parent).or_default().push(path);
+        }
+    }
+}
+
+/// Builds a tree with `build` and returns it with its heap size in bytes.
+fn measure<T>(build: impl FnOnce() -> T) -> (T, usize, f64) {
+    let before = LIVE_BYTES.load(Ordering::Relaxed);
+    let started = Instant::now();
+    let tree = build();
+    let elapsed = started.elapsed().as_secs_f64();
+    (tree, LIVE_BYTES.load(Ordering::Relaxed) - before, elapsed)
+}
+
+fn report(name: &str, nodes: usize, bytes: usize, seconds: f64) {
+    println!(
+        "{name:<26} {nodes} nodes  {:>8.1} MiB  {:>6.1} B/node  built in {:>6.2} s",
+        bytes as f64 / (1024.0 * 1024.0),
+        bytes as f64 / nodes as f64,
+        seconds
+    );
+}
+
+fn main() {
+    let (maps, maps_bytes, maps_seconds) = measure(|| {
+        let mut maps = PathMaps::default();
+        synthetic_tree(|node| maps.insert(node));
+        maps
+    });
+    let nodes = maps.nodes.len();
+    report("path-keyed HashMaps", nodes, maps_bytes, maps_seconds);
+    drop(maps);
+
+    let (store, store_bytes, store_seconds) = measure(|| {
+        let mut store = NodeStore::new();
+        synthetic_tree(|node| {
+            store.upsert(node);
+        });
+        store
+    });
+    assert_eq!(store.len(), nodes);
+    report("NodeStore", nodes, store_bytes, store_seconds);
+
+    println!(
+        "NodeStore uses {:.0}% less memory",
+        100.0 * (1.0 - store_bytes as f64 / maps_bytes as f64)
+    );
+}
@@ -0,0 +1,98 @@
+//! Entries per second for each directory walking backend over a generated
+//! tree. Run with `cargo bench --bench walk`. Every backend stats each entry
+//! once, so the numbers compare how listings and stats are issued, not how
+//! many stats are saved.
+
+use std::fs;
+use std::path::Path;
+use std::time::{Duration, Instant};
+
+use storagescope::model::{DEFAULT_STALE_AFTER, ScanEvent, ScanOptions};
+use storagescope::platform::WalkBackend;
+use storagescope::scanner::run_scan_blocking_with;
+use tempfile::TempDir;
+
+const DEPTH: usize = 4;
+const FANOUT: usize = 8;
+const FILES_PER_DIR: usize = 12;
+const ROUNDS: usize = 5;
+
+fn generate_tree(root: &Path, depth: usize) {
+    for file_idx in 0..FILES_PER_DIR {
+        fs::write(root.join(format!("file-{file_idx}.bin")), [0_u8; 16]).expect("write");
+    }
+    if depth == 0 {
+        return;
+    }
+    for dir_idx in 0..FANOUT {
+        let child = root.join(format!("dir-{dir_idx}"));
+        fs::create_dir(&child).expect("create dir");
+        generate_tree(&child, depth - 1);
+    }
+}
+
+fn backends() -> Vec<(&'static str, WalkBackend)> {
+    #[cfg_attr(not(target_os = "linux"), allow(unused_mut))]
+    let mut backends = vec![("std::fs", WalkBackend::Std)];
+    #[cfg(target_os = "linux")]
+    backends.push(("openat/fstatat", WalkBackend::Fd));
+    #[cfg(all(target_os = "linux", feature = "statx"))]
+    backends.extend([
+        ("statx", WalkBackend::Statx),
+        ("statx/io_uring", WalkBackend::StatxBatch),
+    ]);
+    backends
+}
+
+/// Scans `root` once and returns the entry count and the elapsed time.
+fn scan(root: &Path, backend: WalkBackend, threads: usize) -> (u64, Duration) {
+    let started = Instant::now();
+    let events = run_scan_blocking_with(
+        ScanOptions {
+            root: root.to_path_buf(),
+            one_file_system: true,
+            follow_symlinks: false,
+            show_hidden: true,
+            show_files: false,
+            max_depth: None,
+            threads,
+            exclude_patterns: Vec::new(),
+            include_patterns: Vec::new(),
+            report_excluded: false,
+            stale_after: DEFAULT_STALE_AFTER,
+        },
+        backend,
+    );
+    let elapsed = started.elapsed();
+    let entries = events
+        .iter()
+        .find_map(|event| match event {
+            ScanEvent::Complete(progress) => Some(progress.visited_entries),
+            _ => None,
+        })
+        .expect("scan completes");
+    (entries, elapsed)
+}
+
+fn main() {
+    let temp = TempDir::new().expect("temp dir");
+    generate_tree(temp.path(), DEPTH);
+
+    let mut thread_counts = vec![1, storagescope::scanner::default_thread_count()];
+    thread_counts.dedup();
+    for threads in thread_counts {
+        for (name, backend) in backends() {
+            // Warm the dentry and inode caches so every backend reads the same state.
+            let (entries, _) = scan(temp.path(), backend, threads);
+            let best = (0..ROUNDS)
+                .map(|_| scan(temp.path(), backend, threads).1)
+                .min()
+                .expect("at least one round");
+            let rate = entries as f64 / best.as_secs_f64();
+            println!(
+                "{name:<16} threads={threads:<3} {entries} entries in {:>8.2} ms  {rate:>12.0} entries/s",
+                best.as_secs_f64() * 1000.0
+            );
+        }
+    }
+}
@@ -1,7 +1,10 @@
-use std::collections::HashMap;
+use std::cell::RefCell;
+use std::collections::{BTreeSet, HashMap, HashSet};
+use std::ffi::{OsStr, OsString};
 use std::io;
 use std::path::{Path, PathBuf};
-use std::time::{Duration, Instant};
+use std::rc::Rc;
+use std::time::{Duration, Instant, SystemTime};
 
 use crossterm::event::{
     self, DisableMouseCapture, EnableMouseCapture, Event, KeyCode, KeyEvent, KeyEventKind,
@@ -16,17 +19,42 @@ use ratatui::backend::CrosstermBackend;
 use ratatui::layout::{Constraint, Layout, Rect};
 
 use crate::cli::Config;
-use crate::delete::delete_target;
+use crate::delete::{DeleteMode, delete_target};
+use crate::diff::Baseline;
+use crate::duplicates::{DuplicateEvent, DuplicateGroup, DuplicateSession, start_duplicate_search};
 use crate::errors::AppError;
-use crate::model::{FsEntryKind, NodeSummary, ScanEvent, ScanProgress, SizeMetric, SortMode};
+use crate::external::{
+    ExternalLaunch, copy_to_clipboard, expand_template, open_detached, working_dir,
+};
+use crate::filetypes::{
+    BreakdownSort, FileCategory, TypeGrouping, TypeTally, breakdown, extension_of,
+};
+use crate::keymap::Action;
+use crate::largest::{LARGEST_FILES_CAPACITY, LargestFiles};
+use crate::listing::{Listing, ListingCache, ListingSettings, list_children};
+use crate::model::{
+    ColumnVisibility, FsEntryKind, NodeStats, NodeSummary, ScanEvent, ScanProgress, SizeMetric,
+    SortMode, TableColumn,
+};
+use crate::owners::{OwnerId, OwnerKind, OwnerNames, OwnerTally, owner_breakdown};
 use crate::platform::disk_usage;
 use crate::scanner::{ScanSession, start_scan};
-use crate::ui::{DialogStateView, FOOTER_HEIGHT, HEADER_HEIGHT, RowModel, ViewModel, format_bytes};
+use crate::snapshot::{Snapshot, save_snapshot};
+use crate::store::{NodeId, NodeStore};
+use crate::treemap::hit_test;
+use crate::ui::{
+    DialogStateView, FOOTER_HEIGHT, HEADER_HEIGHT, PanelRow, PanelView, RowModel, TreemapColoring,
+    TreemapTile, TreemapView, ViewModel, format_age, format_bytes, format_delta, treemap_layout,
+};
+#[cfg(target_os = "linux")]
+use crate::watch::{WatchSession, start_watch};
 
 const MAX_EVENTS_PER_TICK: usize = 8192;
 const SPINNER_FRAMES: [char; 4] = ['|', '/', '-', '\\'];
 const MOUSE_DOUBLE_CLICK_WINDOW: Duration = Duration::from_millis(350);
 const MOUSE_SCROLL_STEP: isize = 1;
+/// Largest children drawn inside a directory's treemap tile.
+const TREEMAP_NESTED_TILES: usize = 48;
 const MOUSE_SCROLL_MIN_INTERVAL: Duration = Duration::from_millis(35);
 const POLL_INTERVAL_WHILE_SCANNING: Duration = Duration::from_millis(24);
 const POLL_INTERVAL_IDLE: Duration = Duration::from_millis(80);
@@ -39,6 +67,10 @@ enum ScanState {
     Complete(ScanProgress),
     Error(String),
     Cancelled,
+    Snapshot {
+        host: String,
+        created_at: SystemTime,
+    },
 }
 
 impl ScanState {
@@ -55,9 +87,21 @@ impl ScanState {
             ),
             Self::Error(message) => format!("error: {message}"),
             Self::Cancelled => "cancelled".to_string(),
+            Self::Snapshot { host, created_at } => format!(
+                "snapshot from {host}, taken {} ago",
+                format_age(
+                    SystemTime::now()
+                        .duration_since(*created_at)
+                        .unwrap_or_default()
+                )
+            ),
         }
     }
 
+    fn is_snapshot(&self) -> bool {
+        matches!(self, Self::Snapshot { .. })
+    }
+
     fn is_scanning(&self) -> bool {
         matches!(self, Self::Scanning(_))
     }
@@ -70,78 +114,108 @@ impl ScanState {
     }
 }
 
+/// Duplicate search results for one root, or the search still running for it.
 #[derive(Debug, Clone)]
-enum DeleteDialog {
-    None,
-    Confirm { target: PathBuf },
-    TypePhrase { target: PathBuf, typed: String },
+enum DuplicateState {
+    Idle,
+    Searching {
+        root: PathBuf,
+        hashed_files: u64,
+        total_files: u64,
+    },
+    Done {
+        root: PathBuf,
+        groups: Vec<DuplicateGroup>,
+    },
+}
+
+impl DuplicateState {
+    fn root(&self) -> Option<&Path> {
+        match self {
+            Self::Idle => None,
+            Self::Searching { root, .. } | Self::Done { root, .. } => Some(root),
+        }
+    }
+
+    fn groups(&self) -> &[DuplicateGroup] {
+        match self {
+            Self::Done { groups, .. } => groups,
+            _ => &[],
+        }
+    }
 }
 
-#[derive(Debug, Clone, Copy)]
-enum TableColumn {
-    Name,
-    Kind,
-    Size,
-    Relative,
-    Path,
+#[derive(Debug, Clone)]
+enum DeleteDialog {
+    None,
+    Confirm {
+        targets: Vec<PathBuf>,
+    },
+    TypePhrase {
+        targets: Vec<PathBuf>,
+        typed: String,
+    },
+    /// Per-item outcome of a batch delete; any key dismisses it.
+    Summary {
+        results: Vec<(PathBuf, Option<String>)>,
+    },
 }
 
-#[derive(Debug, Clone, Copy)]
-struct ColumnVisibility {
-    name: bool,
-    kind: bool,
-    size: bool,
-    relative: bool,
-    path: bool,
+/// What the main table area shows.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+enum ViewMode {
+    Children,
+    Extensions,
+    Categories,
+    LargestFiles,
+    Duplicates,
+    Users,
+    Groups,
 }
 
-impl ColumnVisibility {
-    fn all_visible() -> Self {
-        Self {
-            name: true,
-            kind: true,
-            size: true,
-            relative: true,
-            path: true,
+impl ViewMode {
+    fn cycle(self) -> Self {
+        match self {
+            Self::Children | Self::LargestFiles | Self::Duplicates | Self::Users | Self::Groups => {
+                Self::Extensions
+            }
+            Self::Extensions => Self::Categories,
+            Self::Categories => Self::Children,
         }
     }
 
-    fn is_visible(self, column: TableColumn) -> bool {
-        match column {
-            TableColumn::Name => self.name,
-            TableColumn::Kind => self.kind,
-            TableColumn::Size => self.size,
-            TableColumn::Relative => self.relative,
-            TableColumn::Path => self.path,
+    fn cycle_owners(self) -> Self {
+        match self {
+            Self::Users => Self::Groups,
+            Self::Groups => Self::Children,
+            _ => Self::Users,
         }
     }
 
-    fn set_visible(&mut self, column: TableColumn, visible: bool) {
-        match column {
-            TableColumn::Name => self.name = visible,
-            TableColumn::Kind => self.kind = visible,
-            TableColumn::Size => self.size = visible,
-            TableColumn::Relative => self.relative = visible,
-            TableColumn::Path => self.path = visible,
+    fn owner_kind(self) -> Option<OwnerKind> {
+        match self {
+            Self::Users => Some(OwnerKind::User),
+            Self::Groups => Some(OwnerKind::Group),
+            _ => None,
         }
     }
+}
 
-    fn visible_count(self) -> usize {
-        [self.name, self.kind, self.size, self.relative, self.path]
-            .into_iter()
-            .filter(|visible| *visible)
-            .count()
-    }
+/// Directories below `root` holding bytes of `owner`, collected from the owner
+/// tallies of `generation`.
+struct OwnerFilterDirs {
+    root: PathBuf,
+    generation: u64,
+    owner: OwnerId,
+    dirs: HashSet<NodeId>,
+}
 
-    fn label(column: TableColumn) -> &'static str {
-        match column {
-            TableColumn::Name => "Name",
-            TableColumn::Kind => "Kind",
-            TableColumn::Size => "Size",
-            TableColumn::Relative => "Relative",
-            TableColumn::Path => "Path",
-        }
-    }
+/// A table row; in tree mode `prefix` holds the indentation guides and the
+/// expand marker, and bars compare the row with its siblings only.
+struct TreeRow {
+    path: PathBuf,
+    prefix: String,
+    sibling_max_bytes: Option<u64>,
 }
 
 pub struct App {
@@ -149,8 +223,7 @@ pub struct App {
     startup_root: PathBuf,
     current_root: PathBuf,
     active_scan_root: Option<PathBuf>,
-    nodes: HashMap<PathBuf, NodeSummary>,
-    children: HashMap<PathBuf, Vec<PathBuf>>,
+    nodes: NodeStore,
     selected_index: usize,
     table_scroll_offset: usize,
     column_visibility: ColumnVisibility,
@@ -164,7 +237,10 @@ pub struct App {
     scanner: Option<ScanSession>,
     quit: bool,
     delete_dialog: DeleteDialog,
+    marked: BTreeSet<NodeId>,
     help_modal_open: bool,
+    /// External program to hand the terminal to after the current event.
+    pending_launch: Option<ExternalLaunch>,
     spinner_tick: usize,
     last_frame_area: Rect,
     last_mouse_click: Option<(usize, Instant)>,
@@ -172,22 +248,63 @@ pub struct App {
     disk_line_cache: Option<String>,
     disk_line_cache_root: Option<PathBuf>,
     disk_line_last_update: Option<Instant>,
+    baseline: Option<Baseline>,
+    /// Per-directory tallies of the files directly inside each directory.
+    file_types: HashMap<NodeId, TypeTally>,
+    file_types_generation: u64,
+    /// Tally for `current_root`'s subtree, tagged with the generation it was built from.
+    subtree_types: Option<(PathBuf, u64, TypeTally)>,
+    /// Per-directory owner tallies of each directory's own inode and its non-directory children.
+    owners: HashMap<NodeId, OwnerTally>,
+    owners_generation: u64,
+    /// Tally for `current_root`'s subtree, tagged with the generation it was built from.
+    subtree_owners: Option<(PathBuf, u64, OwnerTally)>,
+    owner_names: OwnerNames,
+    /// Limits the children table to entries holding bytes of this user or group.
+    owner_filter: Option<OwnerId>,
+    owner_filter_dirs: RefCell<Option<OwnerFilterDirs>>,
+    /// Children table listings by directory.
+    listings: RefCell<ListingCache>,
+    /// Largest regular files reported by the scanner, independent of `show_files`.
+    largest_files: LargestFiles,
+    /// Regular files of each directory that `show_files` kept out of the tree,
+    /// so duplicate search still sees them.
+    duplicate_candidates: HashMap<NodeId, Vec<NodeSummary>>,
+    duplicates: DuplicateState,
+    duplicate_search: Option<DuplicateSession>,
+    view: ViewMode,
+    /// Lists expanded directories' contents inline below them.
+    tree_mode: bool,
+    expanded: HashSet<NodeId>,
+    /// Draws the children view as a treemap instead of the table.
+    treemap: Option<TreemapColoring>,
+    /// Dominant file category of each child and grandchild of the cached root.
+    treemap_categories: Option<(PathBuf, u64, HashMap<PathBuf, FileCategory>)>,
+    breakdown_sort: BreakdownSort,
+    panel_index: usize,
+    panel_scroll_offset: usize,
+    #[cfg(target_os = "linux")]
+    watcher: Option<WatchSession>,
 }
 
 impl App {
-    pub fn new(config: Config) -> Self {
-        Self {
+    pub fn new(mut config: Config) -> Self {
+        let snapshot = config.snapshot.take();
+        let baseline = config
+            .diff_baseline
+            .take()
+            .map(|baseline| Baseline::from_snapshot(baseline, &config.startup_root));
+        let mut app = Self {
             startup_root: config.startup_root.clone(),
             current_root: config.startup_root.clone(),
             active_scan_root: None,
             metric: config.initial_metric,
-            sort_mode: SortMode::SizeDesc,
+            sort_mode: config.initial_sort,
+            column_visibility: config.initial_columns,
             config,
-            nodes: HashMap::new(),
-            children: HashMap::new(),
+            nodes: NodeStore::new(),
             selected_index: 0,
             table_scroll_offset: 0,
-            column_visibility: ColumnVisibility::all_visible(),
             filter: String::new(),
             filter_mode: false,
             warnings: Vec::new(),
@@ -196,7 +313,9 @@ impl App {
             scanner: None,
             quit: false,
             delete_dialog: DeleteDialog::None,
+            marked: BTreeSet::new(),
             help_modal_open: false,
+            pending_launch: None,
             spinner_tick: 0,
             last_frame_area: Rect::default(),
             last_mouse_click: None,
@@ -204,11 +323,53 @@ impl App {
             disk_line_cache: None,
             disk_line_cache_root: None,
             disk_line_last_update: None,
+            baseline,
+            file_types: HashMap::new(),
+            file_types_generation: 0,
+            subtree_types: None,
+            owners: HashMap::new(),
+            owners_generation: 0,
+            subtree_owners: None,
+            owner_names: OwnerNames::load(),
+            owner_filter: None,
+            owner_filter_dirs: RefCell::new(None),
+            listings: RefCell::new(ListingCache::default()),
+            largest_files: LargestFiles::new(LARGEST_FILES_CAPACITY),
+            duplicate_candidates: HashMap::new(),
+            duplicates: DuplicateState::Idle,
+            duplicate_search: None,
+            view: ViewMode::Children,
+            tree_mode: false,
+            expanded: HashSet::new(),
+            treemap: None,
+            treemap_categories: None,
+            breakdown_sort: BreakdownSort::SizeDesc,
+            panel_index: 0,
+            panel_scroll_offset: 0,
+            #[cfg(target_os = "linux")]
+            watcher: None,
+        };
+
+        if let Some(snapshot) = snapshot {
+            for node in snapshot.nodes {
+                if node.kind == FsEntryKind::File {
+                    app.largest_files.offer(&node);
+                }
+                app.upsert_node(node);
+            }
+            app.scan_state = ScanState::Snapshot {
+                host: snapshot.host,
+                created_at: snapshot.created_at,
+            };
         }
+
+        app
     }
 
     pub fn run(&mut self) -> Result<(), AppError> {
-        self.start_scan_at(self.startup_root.clone());
+        if !self.scan_state.is_snapshot() {
+            self.start_scan_at(self.startup_root.clone());
+        }
 
         enable_raw_mode().map_err(|error| AppError::Terminal(error.to_string()))?;
         let mut stdout = io::stdout();
@@ -238,6 +399,8 @@ impl App {
     ) -> Result<(), AppError> {
         while !self.quit {
             self.drain_scan_events();
+            self.drain_watch_events();
+            self.drain_duplicate_events();
             self.spinner_tick = self.spinner_tick.wrapping_add(1);
             let size = terminal
                 .size()
@@ -257,10 +420,8 @@ impl App {
 
             if event::poll(poll_interval).map_err(|error| AppError::Terminal(error.to_string()))? {
                 match event::read().map_err(|error| AppError::Terminal(error.to_string()))? {
-                    Event::Key(key) => {
-                        if key.kind == KeyEventKind::Press {
-                            self.handle_key(key)?;
-                        }
+                    Event::Key(key) if key.kind == KeyEventKind::Press => {
+                        self.handle_key(key)?;
                     }
                     Event::Mouse(mouse) => {
                         self.handle_mouse(mouse)?;
@@ -268,8 +429,53 @@ impl App {
                     _ => {}
                 }
             }
+
+            if let Some(launch) = self.pending_launch.take() {
+                self.run_launch(terminal, launch)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Carries out a launch queued by `queue_launch`; foreground programs get
+    /// the terminal until they exit.
+    fn run_launch(
+        &mut self,
+        terminal: &mut Terminal<CrosstermBackend<io::Stdout>>,
+        launch: ExternalLaunch,
+    ) -> Result<(), AppError> {
+        if let Some(mut command) = launch.command() {
+            suspend_terminal(terminal)?;
+            let status = command.status();
+            resume_terminal(terminal)?;
+            self.message = Some(match status {
+                Ok(status) if status.success() => format!(
+                    "Back in StorageScope; press {} to rescan if anything changed",
+                    self.config.keymap.primary(Action::Rescan)
+                ),
+                Ok(status) => format!("External command exited with {status}"),
+                Err(error) => format!("Could not start external command: {error}"),
+            });
+            return Ok(());
         }
 
+        match launch {
+            ExternalLaunch::Open { path } => {
+                self.message = Some(match open_detached(&path) {
+                    Ok(()) => format!("Opened {}", path.display()),
+                    Err(error) => format!("Could not open {}: {error}", path.display()),
+                });
+            }
+            ExternalLaunch::CopyPath { path } => {
+                let text = path.to_string_lossy();
+                self.message = Some(match copy_to_clipboard(terminal.backend_mut(), &text) {
+                    Ok(()) => format!("Copied {text} to the clipboard"),
+                    Err(error) => format!("Could not copy to the clipboard: {error}"),
+                });
+            }
+            ExternalLaunch::Shell { .. } | ExternalLaunch::Command { .. } => {}
+        }
         Ok(())
     }
 
@@ -291,15 +497,11 @@ impl App {
         }
 
         match mouse.kind {
-            MouseEventKind::ScrollDown => {
-                if self.can_process_scroll() {
-                    self.move_selection(MOUSE_SCROLL_STEP);
-                }
+            MouseEventKind::ScrollDown if self.can_process_scroll() => {
+                self.move_selection(MOUSE_SCROLL_STEP);
             }
-            MouseEventKind::ScrollUp => {
-                if self.can_process_scroll() {
-                    self.move_selection(-MOUSE_SCROLL_STEP);
-                }
+            MouseEventKind::ScrollUp if self.can_process_scroll() => {
+                self.move_selection(-MOUSE_SCROLL_STEP);
             }
             MouseEventKind::Down(MouseButton::Left) => {
                 if let Some(index) = self.mouse_row_to_index(mouse.column, mouse.row) {
@@ -312,11 +514,21 @@ impl App {
                         })
                         .unwrap_or(false);
 
-                    self.selected_index = index;
-                    self.ensure_selection_in_bounds();
+                    if self.view == ViewMode::Children {
+                        self.selected_index = index;
+                        self.ensure_selection_in_bounds();
+                    } else {
+                        self.panel_index = index;
+                        self.ensure_panel_in_bounds();
+                    }
 
                     if is_double_click {
-                        self.drill_into_selection();
+                        match self.view {
+                            ViewMode::Children => self.drill_into_selection(),
+                            ViewMode::LargestFiles => self.reveal_largest_file(),
+                            ViewMode::Users | ViewMode::Groups => self.apply_owner_filter(),
+                            ViewMode::Extensions | ViewMode::Categories | ViewMode::Duplicates => {}
+                        }
                         self.last_mouse_click = None;
                     } else {
                         self.last_mouse_click = Some((index, now));
@@ -325,10 +537,10 @@ impl App {
                     self.last_mouse_click = None;
                 }
             }
-            MouseEventKind::Down(MouseButton::Right) => {
-                if self.point_in_table(mouse.column, mouse.row) {
-                    self.navigate_to_parent();
-                }
+            MouseEventKind::Down(MouseButton::Right)
+                if self.point_in_table(mouse.column, mouse.row) =>
+            {
+                self.navigate_to_parent();
             }
             _ => {}
         }
@@ -357,7 +569,7 @@ impl App {
             && row < table.y.saturating_add(table.height)
     }
 
-    fn mouse_row_to_index(&self, column: u16, row: u16) -> Option<usize> {
+    fn mouse_row_to_index(&mut self, column: u16, row: u16) -> Option<usize> {
         let table = self.table_area();
 
         if table.width <= 2 || table.height <= 3 {
@@ -373,9 +585,25 @@ impl App {
             return None;
         }
 
+        if self.view == ViewMode::Children && self.treemap.is_some() {
+            let weights: Vec<u64> = self
+                .visible_node_paths()
+                .iter()
+                .map(|path| {
+                    self.nodes
+                        .get(path)
+                        .map_or(0, |node| node.metric_bytes(self.metric))
+                })
+                .collect();
+            return hit_test(&treemap_layout(table, &weights), column, row);
+        }
+
         let relative_index = (row - data_top) as usize;
-        let index = self.table_scroll_offset.saturating_add(relative_index);
-        let len = self.visible_node_paths().len();
+        let (offset, len) = match self.view {
+            ViewMode::Children => (self.table_scroll_offset, self.visible_len()),
+            _ => (self.panel_scroll_offset, self.panel_len()),
+        };
+        let index = offset.saturating_add(relative_index);
         (index < len).then_some(index)
     }
 
@@ -397,6 +625,8 @@ impl App {
         if let Some(mut scan) = self.scanner.take() {
             scan.stop();
         }
+        self.stop_watch();
+        self.stop_duplicate_search();
 
         self.prune_subtree(&root);
         self.warnings.clear();
@@ -412,21 +642,27 @@ impl App {
     }
 
     fn prune_subtree(&mut self, root: &Path) {
-        let to_remove: Vec<PathBuf> = self
-            .nodes
-            .keys()
-            .filter(|path| Self::in_subtree(path, root))
-            .cloned()
-            .collect();
-
-        for path in &to_remove {
-            self.nodes.remove(path);
-            self.children.remove(path);
-        }
+        self.forget_entries(root);
+        self.nodes.remove_subtree(root);
+        self.listings.get_mut().invalidate(root);
+        self.largest_files.remove_subtree(root);
+    }
 
-        for child_paths in self.children.values_mut() {
-            child_paths.retain(|path| !Self::in_subtree(path, root));
+    /// Drops the marks, expanded rows and tallies of `root` and everything
+    /// below it; runs before the store frees their ids for reuse.
+    fn forget_entries(&mut self, root: &Path) {
+        let Some(root) = self.nodes.id(root) else {
+            return;
+        };
+        for id in self.nodes.subtree_ids(root) {
+            self.marked.remove(&id);
+            self.expanded.remove(&id);
+            self.file_types.remove(&id);
+            self.owners.remove(&id);
+            self.duplicate_candidates.remove(&id);
         }
+        self.file_types_generation += 1;
+        self.owners_generation += 1;
     }
 
     fn in_subtree(path: &Path, root: &Path) -> bool {
@@ -455,6 +691,15 @@ impl App {
                     self.scan_state = ScanState::Scanning(ScanProgress::default());
                 }
                 ScanEvent::NodeUpdated(node) => self.upsert_node(node),
+                ScanEvent::Relisted { path, children } => self.forget_unlisted(&path, &children),
+                ScanEvent::FileTypes { path, tally } => self.record_file_types(&path, tally),
+                ScanEvent::Owners { path, tally } => self.record_owners(&path, tally),
+                ScanEvent::DuplicateCandidates { path, files } => {
+                    self.record_duplicate_candidates(&path, files);
+                }
+                ScanEvent::LargeFile(file) => {
+                    self.largest_files.offer(&file);
+                }
                 ScanEvent::Progress(progress) => {
                     self.scan_state = ScanState::Scanning(progress);
                 }
@@ -465,6 +710,11 @@ impl App {
                     self.scan_state = ScanState::Complete(progress);
                     self.active_scan_root = None;
                     should_stop_scanner = true;
+                    self.save_snapshot_if_requested();
+                    self.start_watch_if_requested();
+                    if self.view == ViewMode::Duplicates {
+                        self.start_duplicate_search();
+                    }
                     break;
                 }
                 ScanEvent::Error(message) => {
@@ -482,34 +732,190 @@ impl App {
             }
         }
 
-        if should_stop_scanner {
-            if let Some(mut scanner) = self.scanner.take() {
-                scanner.stop();
-            }
+        if should_stop_scanner && let Some(mut scanner) = self.scanner.take() {
+            scanner.stop();
         }
 
         self.ensure_selection_in_bounds();
     }
 
-    fn upsert_node(&mut self, node: NodeSummary) {
-        let path = node.path.clone();
-        let parent = path.parent().map(Path::to_path_buf);
-        let is_new = self.nodes.insert(path.clone(), node).is_none();
+    #[cfg(target_os = "linux")]
+    fn start_watch_if_requested(&mut self) {
+        if !self.config.watch {
+            return;
+        }
+
+        let mut options = self.config.scan_options.clone();
+        options.root = self.startup_root.clone();
+        match start_watch(options, &self.nodes) {
+            Ok(session) => self.watcher = Some(session),
+            Err(error) => self.message = Some(format!("Cannot start watch mode: {error}")),
+        }
+    }
+
+    #[cfg(not(target_os = "linux"))]
+    fn start_watch_if_requested(&mut self) {
+        if self.config.watch {
+            self.message = Some("--watch is only supported on Linux".to_string());
+        }
+    }
+
+    #[cfg(target_os = "linux")]
+    fn stop_watch(&mut self) {
+        if let Some(mut watcher) = self.watcher.take() {
+            watcher.stop();
+        }
+    }
+
+    #[cfg(not(target_os = "linux"))]
+    fn stop_watch(&mut self) {}
+
+    fn is_watching(&self) -> bool {
+        #[cfg(target_os = "linux")]
+        {
+            self.watcher.is_some()
+        }
+        #[cfg(not(target_os = "linux"))]
+        {
+            false
+        }
+    }
+
+    #[cfg(target_os = "linux")]
+    fn drain_watch_events(&mut self) {
+        let Some(watcher) = &self.watcher else {
+            return;
+        };
+        let events: Vec<ScanEvent> = watcher
+            .receiver()
+            .try_iter()
+            .take(MAX_EVENTS_PER_TICK)
+            .collect();
+        if events.is_empty() {
+            return;
+        }
+
+        for event in events {
+            match event {
+                ScanEvent::NodeUpdated(node) => self.upsert_node(node),
+                ScanEvent::Relisted { path, children } => self.forget_unlisted(&path, &children),
+                ScanEvent::FileTypes { path, tally } => self.record_file_types(&path, tally),
+                ScanEvent::Owners { path, tally } => self.record_owners(&path, tally),
+                ScanEvent::DuplicateCandidates { path, files } => {
+                    self.record_duplicate_candidates(&path, files);
+                }
+                ScanEvent::LargeFile(file) => {
+                    self.largest_files.offer(&file);
+                }
+                ScanEvent::Warning { path, message } => {
+                    self.warnings.push(format!("{}: {message}", path.display()));
+                }
+                _ => {}
+            }
+        }
+        self.ensure_selection_in_bounds();
+    }
+
+    #[cfg(not(target_os = "linux"))]
+    fn drain_watch_events(&mut self) {}
+
+    fn drain_duplicate_events(&mut self) {
+        let Some(session) = &self.duplicate_search else {
+            return;
+        };
+        let events: Vec<DuplicateEvent> = session
+            .receiver()
+            .try_iter()
+            .take(MAX_EVENTS_PER_TICK)
+            .collect();
 
-        if is_new {
-            if let Some(parent_path) = parent {
-                self.children.entry(parent_path).or_default().push(path);
+        for event in events {
+            match event {
+                DuplicateEvent::Progress {
+                    hashed_files: hashed,
+                    total_files: total,
+                } => {
+                    if let DuplicateState::Searching {
+                        hashed_files,
+                        total_files,
+                        ..
+                    } = &mut self.duplicates
+                    {
+                        *hashed_files = hashed;
+                        *total_files = total;
+                    }
+                }
+                DuplicateEvent::Warning { path, message } => {
+                    self.warnings.push(format!("{}: {message}", path.display()));
+                }
+                DuplicateEvent::Complete(groups) => {
+                    if let Some(root) = self.duplicates.root().map(Path::to_path_buf) {
+                        self.duplicates = DuplicateState::Done { root, groups };
+                    }
+                    self.duplicate_search = None;
+                }
+                DuplicateEvent::Cancelled => {
+                    self.duplicates = DuplicateState::Idle;
+                    self.duplicate_search = None;
+                }
             }
         }
+        if self.view == ViewMode::Duplicates {
+            self.ensure_panel_in_bounds();
+        }
+    }
+
+    fn save_snapshot_if_requested(&mut self) {
+        let Some(path) = self.config.save_snapshot.clone() else {
+            return;
+        };
+
+        let mut scan_options = self.config.scan_options.clone();
+        scan_options.root = self.startup_root.clone();
+        let snapshot = Snapshot::from_tree(scan_options, &self.startup_root, &self.nodes);
+        self.message = Some(match save_snapshot(&path, &snapshot) {
+            Ok(()) => format!("Saved snapshot to {}", path.display()),
+            Err(error) => error.to_string(),
+        });
+    }
+
+    fn upsert_node(&mut self, node: NodeSummary) {
+        self.listings.get_mut().invalidate(&node.path);
+        self.nodes.upsert(node);
+    }
+
+    fn record_file_types(&mut self, path: &Path, tally: TypeTally) {
+        if !tally.is_empty() {
+            self.file_types.insert(self.nodes.intern(path), tally);
+        } else if let Some(id) = self.nodes.id(path) {
+            self.file_types.remove(&id);
+        }
+        self.file_types_generation += 1;
+    }
+
+    fn record_owners(&mut self, path: &Path, tally: OwnerTally) {
+        if !tally.is_empty() {
+            self.owners.insert(self.nodes.intern(path), tally);
+        } else if let Some(id) = self.nodes.id(path) {
+            self.owners.remove(&id);
+        }
+        self.owners_generation += 1;
+    }
+
+    fn record_duplicate_candidates(&mut self, path: &Path, files: Vec<NodeSummary>) {
+        if !files.is_empty() {
+            self.duplicate_candidates
+                .insert(self.nodes.intern(path), files);
+        } else if let Some(id) = self.nodes.id(path) {
+            self.duplicate_candidates.remove(&id);
+        }
     }
 
     fn handle_key(&mut self, key: KeyEvent) -> Result<(), AppError> {
+        let action = self.config.keymap.action_for(&key);
         if self.help_modal_open {
-            match key.code {
-                KeyCode::Esc | KeyCode::Char('?') | KeyCode::F(1) | KeyCode::Char('q') => {
-                    self.help_modal_open = false;
-                }
-                _ => {}
+            if key.code == KeyCode::Esc || matches!(action, Some(Action::Help | Action::Quit)) {
+                self.help_modal_open = false;
             }
             return Ok(());
         }
@@ -523,166 +929,1175 @@ impl App {
             return Ok(());
         }
 
-        match key.code {
-            KeyCode::Char('q') => self.quit = true,
-            KeyCode::Down | KeyCode::Char('j') => self.move_selection(1),
-            KeyCode::Up | KeyCode::Char('k') => self.move_selection(-1),
-            KeyCode::Enter => self.drill_into_selection(),
-            KeyCode::Backspace | KeyCode::Char('h') => self.navigate_to_parent(),
-            KeyCode::Char('s') => {
+        if self.view != ViewMode::Children && self.handle_panel_action(action) {
+            return Ok(());
+        }
+
+        let Some(action) = action else {
+            return Ok(());
+        };
+        match action {
+            Action::Quit => self.quit = true,
+            Action::MoveDown => self.move_selection(1),
+            Action::MoveUp => self.move_selection(-1),
+            Action::Open => self.drill_into_selection(),
+            Action::Parent => self.navigate_to_parent(),
+            Action::CycleSort => {
                 self.sort_mode = self.sort_mode.cycle();
+                if self.sort_mode == SortMode::GrowthDesc && self.baseline.is_none() {
+                    self.sort_mode = self.sort_mode.cycle();
+                }
                 self.ensure_selection_in_bounds();
             }
-            KeyCode::Char('N') => self.toggle_column(TableColumn::Name),
-            KeyCode::Char('K') => self.toggle_column(TableColumn::Kind),
-            KeyCode::Char('S') => self.toggle_column(TableColumn::Size),
-            KeyCode::Char('R') => self.toggle_column(TableColumn::Relative),
-            KeyCode::Char('P') => self.toggle_column(TableColumn::Path),
-            KeyCode::Char('m') => self.metric = self.metric.toggle(),
-            KeyCode::Char('r') => self.start_scan_at(self.current_root.clone()),
-            KeyCode::Char('/') => self.filter_mode = true,
-            KeyCode::Char('?') | KeyCode::F(1) => self.help_modal_open = true,
-            KeyCode::Esc => {
+            Action::ToggleColumn(column) => self.toggle_column(column),
+            Action::ToggleMetric => self.metric = self.metric.toggle(),
+            Action::CycleFileTypes => self.set_view(self.view.cycle()),
+            Action::LargestFiles => self.toggle_largest_files(),
+            Action::Duplicates => self.toggle_duplicates(),
+            Action::CycleOwners => self.set_view(self.view.cycle_owners()),
+            Action::Expand if self.tree_mode => self.expand_selected(),
+            Action::Collapse if self.tree_mode => self.collapse_selected(),
+            Action::Expand | Action::Collapse => {}
+            Action::TreeMode => {
+                self.set_view(ViewMode::Children);
+                self.tree_mode = !self.tree_mode;
+                self.treemap = None;
+                self.ensure_selection_in_bounds();
+            }
+            Action::Treemap => {
+                self.set_view(ViewMode::Children);
+                self.tree_mode = false;
+                self.treemap = match self.treemap {
+                    None => Some(TreemapColoring::Depth),
+                    Some(TreemapColoring::Depth) => Some(TreemapColoring::Category),
+                    Some(TreemapColoring::Category) => None,
+                };
+            }
+            Action::Rescan => {
+                if self.scan_state.is_snapshot() {
+                    self.message =
+                        Some("Rescan is unavailable while browsing a snapshot".to_string());
+                } else {
+                    self.start_scan_at(self.current_root.clone());
+                }
+            }
+            Action::Filter => self.filter_mode = true,
+            Action::Help => self.help_modal_open = true,
+            Action::Clear => {
                 self.filter.clear();
+                self.owner_filter = None;
                 self.message = None;
+                self.marked.clear();
                 self.ensure_selection_in_bounds();
             }
-            KeyCode::Char('d') => {
-                if !self.config.no_delete {
-                    if let Some(node) = self.selected_node() {
-                        self.delete_dialog = DeleteDialog::Confirm {
-                            target: node.path.clone(),
-                        };
-                    }
+            Action::Shell | Action::OpenExternal | Action::CopyPath | Action::RunCommand(_) => {
+                self.queue_launch(action);
+            }
+            Action::Mark => self.toggle_mark_selected(),
+            Action::MarkAll => self.toggle_mark_visible(),
+            Action::Delete => {
+                let targets = self.delete_targets();
+                if self.view == ViewMode::Duplicates
+                    && let Some(group) = self.group_fully_targeted(&targets)
+                {
+                    self.message = Some(format!(
+                        "Refusing to delete every copy of {}; leave at least one unmarked",
+                        group.paths[0].display()
+                    ));
+                } else if !self.config.no_delete && !targets.is_empty() {
+                    self.delete_dialog = DeleteDialog::Confirm { targets };
                 }
             }
-            _ => {}
         }
 
         Ok(())
     }
 
-    fn toggle_column(&mut self, column: TableColumn) {
-        let currently_visible = self.column_visibility.is_visible(column);
-        if currently_visible && self.column_visibility.visible_count() == 1 {
-            self.message = Some("At least one column must stay visible".to_string());
-            return;
-        }
-
-        self.column_visibility
-            .set_visible(column, !currently_visible);
-
-        let state = if currently_visible { "hidden" } else { "shown" };
-        self.message = Some(format!(
-            "Column {} {state}",
-            ColumnVisibility::label(column)
-        ));
-    }
-
-    fn handle_filter_key(&mut self, key: KeyEvent) {
-        match key.code {
-            KeyCode::Esc => {
-                self.filter_mode = false;
-                self.filter.clear();
+    /// Actions for the breakdown panels; returns false for actions the main
+    /// table handler should still see (quit, metric, rescan, help, view cycling).
+    fn handle_panel_action(&mut self, action: Option<Action>) -> bool {
+        match action {
+            Some(Action::MoveDown) => self.move_selection(1),
+            Some(Action::MoveUp) => self.move_selection(-1),
+            Some(Action::CycleSort) if self.view != ViewMode::LargestFiles => {
+                self.breakdown_sort = self.breakdown_sort.cycle();
+                self.ensure_panel_in_bounds();
             }
-            KeyCode::Enter => self.filter_mode = false,
-            KeyCode::Backspace => {
-                self.filter.pop();
-                self.ensure_selection_in_bounds();
+            Some(Action::Open) if self.view == ViewMode::LargestFiles => {
+                self.reveal_largest_file();
             }
-            KeyCode::Char(ch) => {
-                if !ch.is_control() {
-                    self.filter.push(ch);
-                    self.ensure_selection_in_bounds();
-                }
+            Some(Action::Open) if self.view.owner_kind().is_some() => self.apply_owner_filter(),
+            Some(Action::Mark) if self.view == ViewMode::Duplicates => {
+                self.adopt_selected_duplicate();
+                self.toggle_mark_selected();
             }
+            Some(Action::Delete) if self.view == ViewMode::Duplicates => {
+                self.adopt_selected_duplicate();
+                return false;
+            }
+            Some(Action::Clear | Action::Parent) => self.set_view(ViewMode::Children),
+            Some(
+                Action::Quit
+                | Action::Shell
+                | Action::OpenExternal
+                | Action::CopyPath
+                | Action::RunCommand(_)
+                | Action::ToggleMetric
+                | Action::Rescan
+                | Action::CycleFileTypes
+                | Action::LargestFiles
+                | Action::Duplicates
+                | Action::CycleOwners
+                | Action::Treemap
+                | Action::TreeMode
+                | Action::Help,
+            ) => return false,
             _ => {}
         }
+        true
     }
 
-    fn handle_delete_dialog_key(&mut self, key: &KeyEvent) -> Result<bool, AppError> {
-        match &mut self.delete_dialog {
-            DeleteDialog::None => Ok(false),
-            DeleteDialog::Confirm { target } => {
-                match key.code {
-                    KeyCode::Esc => self.delete_dialog = DeleteDialog::None,
-                    KeyCode::Enter => {
-                        self.delete_dialog = DeleteDialog::TypePhrase {
-                            target: target.clone(),
-                            typed: String::new(),
-                        }
-                    }
-                    _ => {}
-                }
-                Ok(true)
-            }
-            DeleteDialog::TypePhrase { target, typed } => {
-                match key.code {
-                    KeyCode::Esc => self.delete_dialog = DeleteDialog::None,
-                    KeyCode::Backspace => {
-                        typed.pop();
-                    }
-                    KeyCode::Char(ch) => {
-                        if !ch.is_control() {
-                            typed.push(ch);
-                        }
-                    }
-                    KeyCode::Enter => {
-                        if typed == "DELETE" {
-                            match delete_target(target, &self.startup_root) {
-                                Ok(()) => {
-                                    self.message =
-                                        Some(format!("Deleted {}", target.to_string_lossy()));
-                                    self.delete_dialog = DeleteDialog::None;
-                                    self.start_scan_at(self.current_root.clone());
-                                }
-                                Err(error) => {
-                                    self.message = Some(error.to_string());
-                                    self.delete_dialog = DeleteDialog::None;
-                                }
-                            }
-                        } else {
-                            self.message = Some("Type DELETE exactly to confirm".to_string());
-                        }
-                    }
-                    _ => {}
-                }
-                Ok(true)
-            }
+    fn set_view(&mut self, view: ViewMode) {
+        self.view = view;
+        self.panel_index = 0;
+        self.panel_scroll_offset = 0;
+        self.ensure_panel_in_bounds();
+    }
+
+    fn toggle_largest_files(&mut self) {
+        if self.view == ViewMode::LargestFiles {
+            self.set_view(ViewMode::Children);
+        } else {
+            self.set_view(ViewMode::LargestFiles);
         }
     }
 
-    fn move_selection(&mut self, delta: isize) {
-        let len = self.visible_node_paths().len();
-        if len == 0 {
-            self.selected_index = 0;
-            self.table_scroll_offset = 0;
+    fn toggle_duplicates(&mut self) {
+        if self.view == ViewMode::Duplicates {
+            self.set_view(ViewMode::Children);
             return;
         }
 
-        let current = self.selected_index as isize;
-        let max = (len - 1) as isize;
-        let next = (current + delta).clamp(0, max);
-        self.selected_index = next as usize;
-        self.sync_table_scroll(len);
+        if self.duplicates.root() != Some(self.current_root.as_path()) {
+            self.start_duplicate_search();
+        }
+        self.set_view(ViewMode::Duplicates);
     }
 
-    fn drill_into_selection(&mut self) {
-        if let Some(node) = self.selected_node() {
-            if matches!(node.kind, FsEntryKind::Dir | FsEntryKind::Symlink) {
-                self.current_root = node.path.clone();
-                self.selected_index = 0;
-                self.ensure_selection_in_bounds();
+    /// Hashes the files scanned below `current_root` in the background, whether
+    /// they are in the tree or were only reported as duplicate candidates.
+    fn start_duplicate_search(&mut self) {
+        self.stop_duplicate_search();
+
+        let mut files: Vec<NodeSummary> = self
+            .nodes
+            .subtree(&self.current_root)
+            .filter(|(_, node)| node.kind == FsEntryKind::File)
+            .map(|(path, node)| NodeSummary::from_parts(path, *node))
+            .collect();
+        if let Some(root) = self.nodes.id(&self.current_root) {
+            for id in self.nodes.subtree_ids(root) {
+                if let Some(candidates) = self.duplicate_candidates.get(&id) {
+                    files.extend(candidates.iter().cloned());
+                }
             }
         }
+        let root = self.current_root.clone();
+        if files.is_empty() {
+            self.duplicates = DuplicateState::Done {
+                root,
+                groups: Vec::new(),
+            };
+            return;
+        }
+
+        self.duplicates = DuplicateState::Searching {
+            root,
+            hashed_files: 0,
+            total_files: 0,
+        };
+        self.duplicate_search = Some(start_duplicate_search(files));
     }
 
-    fn navigate_to_parent(&mut self) {
-        if self.current_root == self.startup_root {
-            return;
+    fn stop_duplicate_search(&mut self) {
+        if let Some(mut session) = self.duplicate_search.take() {
+            session.stop();
         }
+        self.duplicates = DuplicateState::Idle;
+    }
 
-        let next_root = self
+    /// Group and path for every row of the duplicates view, in display order.
+    fn duplicate_rows(&self) -> Vec<(usize, &DuplicateGroup, &Path)> {
+        self.duplicates
+            .groups()
+            .iter()
+            .enumerate()
+            .flat_map(|(index, group)| {
+                group
+                    .paths
+                    .iter()
+                    .map(move |path| (index, group, path.as_path()))
+            })
+            .collect()
+    }
+
+    /// A duplicate group whose every copy is among `targets`, if any.
+    fn group_fully_targeted(&self, targets: &[PathBuf]) -> Option<&DuplicateGroup> {
+        self.duplicates.groups().iter().find(|group| {
+            group
+                .paths
+                .iter()
+                .all(|path| targets.iter().any(|target| path.starts_with(target)))
+        })
+    }
+
+    /// Moves the selected copy from its directory's duplicate candidates into
+    /// the tree, so it can be marked and deleted like any other entry.
+    fn adopt_selected_duplicate(&mut self) {
+        let Some(path) = self
+            .duplicate_rows()
+            .get(self.panel_index)
+            .map(|(_, _, path)| path.to_path_buf())
+        else {
+            return;
+        };
+        let Some(candidates) = path
+            .parent()
+            .and_then(|parent| self.nodes.id(parent))
+            .and_then(|parent| self.duplicate_candidates.get_mut(&parent))
+        else {
+            return;
+        };
+        if let Some(index) = candidates.iter().position(|file| file.path == path) {
+            let file = candidates.swap_remove(index);
+            self.upsert_node(file);
+        }
+    }
+
+    /// Drops deleted paths from the duplicate groups, discarding groups left with one copy.
+    fn forget_duplicates(&mut self, root: &Path) {
+        if let DuplicateState::Done { groups, .. } = &mut self.duplicates {
+            for group in groups.iter_mut() {
+                group.paths.retain(|path| !Self::in_subtree(path, root));
+            }
+            groups.retain(|group| group.paths.len() > 1);
+        }
+    }
+
+    /// Restricts the children table to the owner selected in the owner view.
+    fn apply_owner_filter(&mut self) {
+        let Some(kind) = self.view.owner_kind() else {
+            return;
+        };
+        let (metric, sort) = (self.metric, self.breakdown_sort);
+        let tally = self.current_subtree_owners().clone();
+        let Some(entry) = owner_breakdown(&tally, kind, sort, metric, &self.owner_names)
+            .into_iter()
+            .nth(self.panel_index)
+        else {
+            return;
+        };
+
+        self.owner_filter = Some(entry.owner);
+        self.message = Some(format!(
+            "Listing entries owned by {kind} {}; Esc clears",
+            entry.label
+        ));
+        self.set_view(ViewMode::Children);
+        self.selected_index = 0;
+        self.ensure_selection_in_bounds();
+    }
+
+    /// Opens the directory holding the selected largest file, selecting the file
+    /// when files are listed in the table.
+    fn reveal_largest_file(&mut self) {
+        let Some(file) = self
+            .largest_files
+            .below(&self.current_root, self.metric)
+            .get(self.panel_index)
+            .map(|file| file.path.clone())
+        else {
+            return;
+        };
+        let Some(parent) = file.parent() else {
+            return;
+        };
+
+        self.current_root = parent.to_path_buf();
+        self.set_view(ViewMode::Children);
+        self.selected_index = self
+            .visible_node_paths()
+            .iter()
+            .position(|path| *path == file)
+            .unwrap_or(0);
+        self.ensure_selection_in_bounds();
+    }
+
+    /// File-type tally for every directory below `current_root`, rebuilt only
+    /// when the root or the underlying tallies changed.
+    fn current_subtree_types(&mut self) -> &TypeTally {
+        let fresh = self
+            .subtree_types
+            .as_ref()
+            .is_some_and(|(root, generation, _)| {
+                *root == self.current_root && *generation == self.file_types_generation
+            });
+        if !fresh {
+            let mut tally = TypeTally::default();
+            if let Some(root) = self.nodes.id(&self.current_root) {
+                for (dir, dir_tally) in &self.file_types {
+                    if self.nodes.ancestors(*dir).any(|ancestor| ancestor == root) {
+                        tally.merge(dir_tally);
+                    }
+                }
+            }
+            self.subtree_types =
+                Some((self.current_root.clone(), self.file_types_generation, tally));
+        }
+        &self.subtree_types.as_ref().expect("subtree tally cached").2
+    }
+
+    /// Owner tally for every directory below `current_root`, rebuilt only
+    /// when the root or the underlying tallies changed.
+    fn current_subtree_owners(&mut self) -> &OwnerTally {
+        let fresh = self
+            .subtree_owners
+            .as_ref()
+            .is_some_and(|(root, generation, _)| {
+                *root == self.current_root && *generation == self.owners_generation
+            });
+        if !fresh {
+            let mut tally = OwnerTally::default();
+            if let Some(root) = self.nodes.id(&self.current_root) {
+                for (dir, dir_tally) in &self.owners {
+                    if self.nodes.ancestors(*dir).any(|ancestor| ancestor == root) {
+                        tally.merge(dir_tally);
+                    }
+                }
+            }
+            self.subtree_owners = Some((self.current_root.clone(), self.owners_generation, tally));
+        }
+        &self
+            .subtree_owners
+            .as_ref()
+            .expect("subtree tally cached")
+            .2
+    }
+
+    /// Whether `path`, an entry below `current_root`, holds bytes of `owner`.
+    fn owned_by(&self, path: &Path, owner: OwnerId) -> bool {
+        let Some(id) = self.nodes.id(path) else {
+            return false;
+        };
+        let Some(node) = self.nodes.stats(id) else {
+            return false;
+        };
+        if !self.owners.contains_key(&id) {
+            return node
+                .owner
+                .is_some_and(|node_owner| owner.matches(node_owner));
+        }
+
+        let mut cache = self.owner_filter_dirs.borrow_mut();
+        let fresh = cache.as_ref().is_some_and(|cached| {
+            cached.root == self.current_root
+                && cached.generation == self.owners_generation
+                && cached.owner == owner
+        });
+        if !fresh {
+            let root = self.nodes.id(&self.current_root);
+            let dirs = self
+                .owners
+                .iter()
+                .filter(|(_, tally)| tally.stat(owner).entries > 0)
+                .filter_map(|(dir, _)| self.nodes.branch(root?, *dir))
+                .flatten()
+                .collect();
+            *cache = Some(OwnerFilterDirs {
+                root: self.current_root.clone(),
+                generation: self.owners_generation,
+                owner,
+                dirs,
+            });
+        }
+        cache
+            .as_ref()
+            .is_some_and(|cached| cached.dirs.contains(&id))
+    }
+
+    fn panel_len(&mut self) -> usize {
+        if let Some(kind) = self.view.owner_kind() {
+            return self.current_subtree_owners().len(kind);
+        }
+        let grouping = match self.view {
+            ViewMode::Children => return 0,
+            ViewMode::LargestFiles => {
+                return self
+                    .largest_files
+                    .below(&self.current_root, self.metric)
+                    .len();
+            }
+            ViewMode::Duplicates => {
+                return self
+                    .duplicates
+                    .groups()
+                    .iter()
+                    .map(|group| group.paths.len())
+                    .sum();
+            }
+            ViewMode::Users | ViewMode::Groups => return 0,
+            ViewMode::Extensions => TypeGrouping::Extension,
+            ViewMode::Categories => TypeGrouping::Category,
+        };
+        let tally = self.current_subtree_types();
+        match grouping {
+            TypeGrouping::Extension => tally.extensions().count(),
+            TypeGrouping::Category => tally.categories().len(),
+        }
+    }
+
+    fn ensure_panel_in_bounds(&mut self) {
+        let len = self.panel_len();
+        self.panel_index = self.panel_index.min(len.saturating_sub(1));
+        self.panel_scroll_offset = compute_scroll_offset(
+            self.panel_scroll_offset,
+            self.panel_index,
+            len,
+            self.table_visible_rows(),
+        );
+    }
+
+    fn build_panel(&mut self) -> Option<PanelView> {
+        let grouping = match self.view {
+            ViewMode::Children => return None,
+            ViewMode::LargestFiles => return Some(self.build_largest_files_panel()),
+            ViewMode::Duplicates => return Some(self.build_duplicates_panel()),
+            ViewMode::Users => return Some(self.build_owners_panel(OwnerKind::User)),
+            ViewMode::Groups => return Some(self.build_owners_panel(OwnerKind::Group)),
+            ViewMode::Extensions => TypeGrouping::Extension,
+            ViewMode::Categories => TypeGrouping::Category,
+        };
+        let (metric, sort) = (self.metric, self.breakdown_sort);
+        let entries = breakdown(self.current_subtree_types(), grouping, sort, metric);
+        let total: u64 = entries
+            .iter()
+            .map(|entry| entry.stat.metric_bytes(metric))
+            .sum();
+
+        let rows = entries
+            .into_iter()
+            .map(|entry| {
+                let bytes = entry.stat.metric_bytes(metric);
+                let share = if total == 0 {
+                    0.0
+                } else {
+                    bytes as f64 / total as f64 * 100.0
+                };
+                let mut cells = vec![entry.label];
+                if grouping == TypeGrouping::Extension {
+                    cells.push(entry.category.to_string());
+                }
+                cells.push(entry.stat.files.to_string());
+                cells.push(format_bytes(bytes));
+                cells.push(format!("{share:.1}%"));
+                PanelRow {
+                    cells,
+                    bar_bytes: bytes,
+                }
+            })
+            .collect();
+
+        let mut columns = vec![(
+            match grouping {
+                TypeGrouping::Extension => "Extension",
+                TypeGrouping::Category => "Category",
+            },
+            Constraint::Length(16),
+        )];
+        if grouping == TypeGrouping::Extension {
+            columns.push(("Category", Constraint::Length(12)));
+        }
+        columns.extend([
+            ("Files", Constraint::Length(10)),
+            ("Size", Constraint::Length(12)),
+            ("Share", Constraint::Length(8)),
+        ]);
+
+        let key = |action| self.config.keymap.primary(action);
+        Some(PanelView {
+            title: format!(
+                "File types by {} ({}: next view, {}: sort)",
+                match grouping {
+                    TypeGrouping::Extension => "extension",
+                    TypeGrouping::Category => "category",
+                },
+                key(Action::CycleFileTypes),
+                key(Action::CycleSort),
+            ),
+            columns: columns
+                .into_iter()
+                .map(|(label, width)| (label.to_string(), width))
+                .collect(),
+            rows,
+            selected_index: self.panel_index,
+            scroll_offset: self.panel_scroll_offset,
+            empty_hint: if self.scan_state.is_scanning() {
+                "Waiting for the scan to reach files...".to_string()
+            } else {
+                "No files were tallied under this path.".to_string()
+            },
+        })
+    }
+
+    /// Dominant file category below every child and grandchild of `current_root`,
+    /// rebuilt only when the root or the file-type tallies changed.
+    fn current_treemap_categories(&mut self) -> &HashMap<PathBuf, FileCategory> {
+        let fresh = self
+            .treemap_categories
+            .as_ref()
+            .is_some_and(|(root, generation, _)| {
+                *root == self.current_root && *generation == self.file_types_generation
+            });
+        if !fresh {
+            let root = self.nodes.id(&self.current_root);
+            let mut tallies: HashMap<NodeId, TypeTally> = HashMap::new();
+            for (dir, tally) in &self.file_types {
+                let Some(branch) = root.and_then(|root| self.nodes.branch(root, *dir)) else {
+                    continue;
+                };
+                for ancestor in branch.into_iter().rev().take(2) {
+                    tallies.entry(ancestor).or_default().merge(tally);
+                }
+            }
+            let dominant = tallies
+                .into_iter()
+                .filter_map(|(id, tally)| {
+                    let (category, _) = tally.categories().into_iter().max_by(|a, b| {
+                        a.1.allocated_bytes
+                            .cmp(&b.1.allocated_bytes)
+                            .then_with(|| b.0.cmp(&a.0))
+                    })?;
+                    Some((self.nodes.path(id), category))
+                })
+                .collect();
+            self.treemap_categories = Some((
+                self.current_root.clone(),
+                self.file_types_generation,
+                dominant,
+            ));
+        }
+        &self
+            .treemap_categories
+            .as_ref()
+            .expect("treemap categories cached")
+            .2
+    }
+
+    fn build_treemap(&mut self, coloring: TreemapColoring) -> TreemapView {
+        if coloring == TreemapColoring::Category {
+            self.current_treemap_categories();
+        }
+        let categories = self
+            .treemap_categories
+            .as_ref()
+            .filter(|_| coloring == TreemapColoring::Category)
+            .map(|(_, _, categories)| categories);
+        let category_of = |path: &Path, node: &NodeStats| match node.kind {
+            FsEntryKind::Dir | FsEntryKind::Symlink => {
+                categories.and_then(|categories| categories.get(path).copied())
+            }
+            FsEntryKind::File => Some(FileCategory::for_extension(&extension_of(path))),
+            FsEntryKind::Other => None,
+        };
+        let tile = |path: &Path, node: &NodeStats, children: Vec<TreemapTile>| TreemapTile {
+            name: path
+                .file_name()
+                .map(|name| name.to_string_lossy().into_owned())
+                .unwrap_or_else(|| path.to_string_lossy().into_owned()),
+            size_bytes: node.metric_bytes(self.metric),
+            category: category_of(path, node),
+            is_loading: !node.is_complete,
+            marked: self.is_marked(path),
+            children,
+        };
+
+        let tiles = self
+            .visible_node_paths()
+            .iter()
+            .map(|path| {
+                let Some(node) = self.nodes.get(path) else {
+                    // Removed entries keep their slot so indices match the table.
+                    return TreemapTile {
+                        name: String::new(),
+                        size_bytes: 0,
+                        category: None,
+                        is_loading: false,
+                        marked: false,
+                        children: Vec::new(),
+                    };
+                };
+                let mut nested: Vec<(PathBuf, &NodeStats)> = self
+                    .nodes
+                    .child_paths(path)
+                    .into_iter()
+                    .filter_map(|child| {
+                        let node = self.nodes.get(&child)?;
+                        Some((child, node))
+                    })
+                    .collect();
+                nested.sort_by_key(|(_, child)| std::cmp::Reverse(child.metric_bytes(self.metric)));
+                nested.truncate(TREEMAP_NESTED_TILES);
+                let children = nested
+                    .into_iter()
+                    .map(|(child_path, child)| tile(&child_path, child, Vec::new()))
+                    .collect();
+                tile(path, node, children)
+            })
+            .collect();
+
+        TreemapView {
+            tiles,
+            selected_index: self.selected_index,
+            coloring,
+        }
+    }
+
+    fn build_owners_panel(&mut self, kind: OwnerKind) -> PanelView {
+        let (metric, sort) = (self.metric, self.breakdown_sort);
+        let tally = self.current_subtree_owners().clone();
+        let entries = owner_breakdown(&tally, kind, sort, metric, &self.owner_names);
+        let total: u64 = entries
+            .iter()
+            .map(|entry| entry.stat.metric_bytes(metric))
+            .sum();
+
+        let rows = entries
+            .into_iter()
+            .map(|entry| {
+                let bytes = entry.stat.metric_bytes(metric);
+                let share = if total == 0 {
+                    0.0
+                } else {
+                    bytes as f64 / total as f64 * 100.0
+                };
+                let label = if self.owner_filter == Some(entry.owner) {
+                    format!("{} (filter)", entry.label)
+                } else {
+                    entry.label
+                };
+                PanelRow {
+                    cells: vec![
+                        label,
+                        entry.owner.id.to_string(),
+                        entry.stat.entries.to_string(),
+                        format_bytes(bytes),
+                        format!("{share:.1}%"),
+                    ],
+                    bar_bytes: bytes,
+                }
+            })
+            .collect();
+
+        let (noun, next, column) = match kind {
+            OwnerKind::User => ("user", "groups", "User"),
+            OwnerKind::Group => ("group", "back", "Group"),
+        };
+        let key = |action| self.config.keymap.primary(action);
+        PanelView {
+            title: format!(
+                "Usage by {noun} ({}: {next}, {}: sort, {}: filter table)",
+                key(Action::CycleOwners),
+                key(Action::CycleSort),
+                key(Action::Open),
+            ),
+            columns: vec![
+                (column.to_string(), Constraint::Length(20)),
+                ("Id".to_string(), Constraint::Length(8)),
+                ("Entries".to_string(), Constraint::Length(10)),
+                ("Size".to_string(), Constraint::Length(12)),
+                ("Share".to_string(), Constraint::Length(8)),
+            ],
+            rows,
+            selected_index: self.panel_index,
+            scroll_offset: self.panel_scroll_offset,
+            empty_hint: if self.scan_state.is_scanning() {
+                "Waiting for the scan to finish a directory...".to_string()
+            } else {
+                "No ownership data for this path (snapshots do not record it).".to_string()
+            },
+        }
+    }
+
+    fn build_largest_files_panel(&self) -> PanelView {
+        let rows = self
+            .largest_files
+            .below(&self.current_root, self.metric)
+            .into_iter()
+            .map(|file| {
+                let bytes = file.metric_bytes(self.metric);
+                let location = file
+                    .path
+                    .parent()
+                    .and_then(|parent| parent.strip_prefix(&self.current_root).ok())
+                    .map(|relative| format!("./{}", relative.to_string_lossy()))
+                    .unwrap_or_default();
+                PanelRow {
+                    cells: vec![
+                        file.path
+                            .file_name()
+                            .map(|name| name.to_string_lossy().into_owned())
+                            .unwrap_or_default(),
+                        format_bytes(bytes),
+                        location,
+                    ],
+                    bar_bytes: bytes,
+                }
+            })
+            .collect();
+
+        PanelView {
+            title: format!(
+                "Largest files (top {LARGEST_FILES_CAPACITY} of scan, {}: open folder, {}: back)",
+                self.config.keymap.primary(Action::Open),
+                self.config.keymap.primary(Action::LargestFiles),
+            ),
+            columns: vec![
+                ("Name".to_string(), Constraint::Length(28)),
+                ("Size".to_string(), Constraint::Length(12)),
+                ("Folder".to_string(), Constraint::Min(10)),
+            ],
+            rows,
+            selected_index: self.panel_index,
+            scroll_offset: self.panel_scroll_offset,
+            empty_hint: if self.scan_state.is_scanning() {
+                "Waiting for the scan to reach files...".to_string()
+            } else {
+                "No files were found under this path.".to_string()
+            },
+        }
+    }
+
+    fn build_duplicates_panel(&self) -> PanelView {
+        let rows = self
+            .duplicate_rows()
+            .into_iter()
+            .map(|(index, group, path)| {
+                let wasted = group.wasted_bytes(self.metric);
+                let name = path
+                    .file_name()
+                    .map(|name| name.to_string_lossy().into_owned())
+                    .unwrap_or_default();
+                PanelRow {
+                    cells: vec![
+                        format!("#{} ({}x)", index + 1, group.paths.len()),
+                        if self.is_marked(path) {
+                            format!("[x] {name}")
+                        } else {
+                            name
+                        },
+                        format_bytes(match self.metric {
+                            SizeMetric::Allocated => group.allocated_bytes,
+                            SizeMetric::Apparent => group.apparent_bytes,
+                        }),
+                        format_bytes(wasted),
+                        path.parent()
+                            .map(|parent| parent.to_string_lossy().into_owned())
+                            .unwrap_or_default(),
+                    ],
+                    bar_bytes: wasted,
+                }
+            })
+            .collect();
+
+        let title = match &self.duplicates {
+            DuplicateState::Searching {
+                hashed_files,
+                total_files,
+                ..
+            } => format!("Duplicate files (hashing {hashed_files}/{total_files} candidates...)"),
+            _ => {
+                let groups = self.duplicates.groups();
+                let wasted: u64 = groups
+                    .iter()
+                    .map(|group| group.wasted_bytes(self.metric))
+                    .sum();
+                let key = |action| self.config.keymap.primary(action);
+                format!(
+                    "Duplicate files: {} groups, {} wasted ({}: mark, {}: delete, {}: back)",
+                    groups.len(),
+                    format_bytes(wasted),
+                    key(Action::Mark),
+                    key(Action::Delete),
+                    key(Action::Duplicates),
+                )
+            }
+        };
+
+        PanelView {
+            title,
+            columns: vec![
+                ("Group".to_string(), Constraint::Length(12)),
+                ("Name".to_string(), Constraint::Length(28)),
+                ("Size".to_string(), Constraint::Length(12)),
+                ("Wasted".to_string(), Constraint::Length(12)),
+                ("Folder".to_string(), Constraint::Min(10)),
+            ],
+            rows,
+            selected_index: self.panel_index,
+            scroll_offset: self.panel_scroll_offset,
+            empty_hint: match &self.duplicates {
+                DuplicateState::Searching { .. } => "Hashing candidate files...".to_string(),
+                _ if self.scan_state.is_scanning() => {
+                    "No duplicates yet; the search reruns when the scan completes.".to_string()
+                }
+                _ => "No duplicate files found under this path.".to_string(),
+            },
+        }
+    }
+
+    fn toggle_column(&mut self, column: TableColumn) {
+        let currently_visible = self.column_visibility.is_visible(column);
+        if currently_visible && self.column_visibility.visible_count() == 1 {
+            self.message = Some("At least one column must stay visible".to_string());
+            return;
+        }
+
+        self.column_visibility
+            .set_visible(column, !currently_visible);
+
+        let state = if currently_visible { "hidden" } else { "shown" };
+        self.message = Some(format!(
+            "Column {} {state}",
+            ColumnVisibility::label(column)
+        ));
+    }
+
+    fn handle_filter_key(&mut self, key: KeyEvent) {
+        match key.code {
+            KeyCode::Esc => {
+                self.filter_mode = false;
+                self.filter.clear();
+            }
+            KeyCode::Enter => self.filter_mode = false,
+            KeyCode::Backspace => {
+                self.filter.pop();
+                self.ensure_selection_in_bounds();
+            }
+            KeyCode::Char(ch) if !ch.is_control() => {
+                self.filter.push(ch);
+                self.ensure_selection_in_bounds();
+            }
+            _ => {}
+        }
+    }
+
+    fn handle_delete_dialog_key(&mut self, key: &KeyEvent) -> Result<bool, AppError> {
+        match &mut self.delete_dialog {
+            DeleteDialog::None => Ok(false),
+            DeleteDialog::Confirm { targets } => {
+                match key.code {
+                    KeyCode::Esc => self.delete_dialog = DeleteDialog::None,
+                    KeyCode::Enter => {
+                        self.delete_dialog = DeleteDialog::TypePhrase {
+                            targets: std::mem::take(targets),
+                            typed: String::new(),
+                        }
+                    }
+                    _ => {}
+                }
+                Ok(true)
+            }
+            DeleteDialog::TypePhrase { targets, typed } => {
+                match key.code {
+                    KeyCode::Esc => self.delete_dialog = DeleteDialog::None,
+                    KeyCode::Backspace => {
+                        typed.pop();
+                    }
+                    KeyCode::Char(ch) if !ch.is_control() => {
+                        typed.push(ch);
+                    }
+                    KeyCode::Enter => {
+                        if typed == "DELETE" {
+                            let targets = std::mem::take(targets);
+                            self.delete_dialog = DeleteDialog::None;
+                            self.run_delete(targets);
+                        } else {
+                            self.message = Some("Type DELETE exactly to confirm".to_string());
+                        }
+                    }
+                    _ => {}
+                }
+                Ok(true)
+            }
+            DeleteDialog::Summary { .. } => {
+                self.delete_dialog = DeleteDialog::None;
+                Ok(true)
+            }
+        }
+    }
+
+    fn run_delete(&mut self, targets: Vec<PathBuf>) {
+        let mode = self.config.delete_mode;
+        let verb = match mode {
+            DeleteMode::Trash => "Moved to trash:",
+            DeleteMode::Permanent => "Deleted",
+        };
+
+        let results: Vec<(PathBuf, Option<String>)> = targets
+            .into_iter()
+            .map(|target| {
+                let outcome = delete_target(&target, &self.startup_root, mode)
+                    .err()
+                    .map(|error| error.to_string());
+                (target, outcome)
+            })
+            .collect();
+        let succeeded = results.iter().filter(|(_, error)| error.is_none()).count();
+        self.marked.clear();
+
+        // A running scan may still report totals that include the deleted entries,
+        // so only patch the cached tree when nothing is scanning.
+        if succeeded > 0 {
+            if self.scanner.is_some() {
+                self.start_scan_at(self.current_root.clone());
+            } else {
+                for (target, _) in results.iter().filter(|(_, error)| error.is_none()) {
+                    self.remove_subtree(target);
+                }
+                self.disk_line_last_update = None;
+                self.ensure_selection_in_bounds();
+                self.ensure_panel_in_bounds();
+            }
+        }
+
+        match results.as_slice() {
+            [(target, None)] => {
+                self.message = Some(format!("{verb} {}", target.to_string_lossy()));
+            }
+            [(_, Some(error))] => self.message = Some(error.clone()),
+            _ => {
+                self.message = Some(format!("{verb} {succeeded} of {} entries", results.len()));
+                self.delete_dialog = DeleteDialog::Summary { results };
+            }
+        }
+    }
+
+    /// Removes a node and its descendants from the cached tree without touching
+    /// ancestor totals; returns the removed node.
+    fn forget_subtree(&mut self, path: &Path) -> Option<NodeStats> {
+        self.forget_entries(path);
+        let removed = self.nodes.remove_subtree(path)?;
+        self.listings.get_mut().invalidate(path);
+        self.largest_files.remove_subtree(path);
+        self.forget_duplicates(path);
+        Some(removed)
+    }
+
+    /// Forgets the children of `dir` that a fresh listing no longer contains.
+    fn forget_unlisted(&mut self, dir: &Path, children: &[OsString]) {
+        let listed: HashSet<&OsStr> = children.iter().map(OsString::as_os_str).collect();
+        let stale: Vec<PathBuf> = self
+            .nodes
+            .child_paths(dir)
+            .into_iter()
+            .filter(|path| path.file_name().is_none_or(|name| !listed.contains(name)))
+            .collect();
+        for path in stale {
+            self.forget_subtree(&path);
+        }
+    }
+
+    /// Drops a deleted entry and everything below it from the cached tree and
+    /// subtracts its totals from each ancestor up to `startup_root`.
+    fn remove_subtree(&mut self, path: &Path) {
+        #[cfg(target_os = "linux")]
+        if let Some(watcher) = &self.watcher {
+            watcher.removed(path);
+        }
+        // Directories carry their own owner tally; other entries live in their parent's.
+        let has_owner_tally = self
+            .nodes
+            .id(path)
+            .is_some_and(|id| self.owners.contains_key(&id));
+        let Some(removed) = self.forget_subtree(path) else {
+            return;
+        };
+        let Some(parent) = path.parent() else {
+            return;
+        };
+        if !has_owner_tally
+            && let Some(owner) = removed.owner
+            && let Some(parent_id) = self.nodes.id(parent)
+            && let Some(tally) = self.owners.get_mut(&parent_id)
+        {
+            tally.remove_entry(owner, removed.apparent_bytes, removed.allocated_bytes);
+            self.owners_generation += 1;
+        }
+        if let Some(parent_node) = self.nodes.get_mut(parent) {
+            parent_node.children_count = parent_node.children_count.saturating_sub(1);
+        }
+
+        for ancestor in parent.ancestors() {
+            if !ancestor.starts_with(&self.startup_root) {
+                break;
+            }
+            if let Some(node) = self.nodes.get_mut(ancestor) {
+                node.subtract_totals(&removed);
+                node.last_updated = SystemTime::now();
+                self.listings.get_mut().invalidate(ancestor);
+            }
+        }
+    }
+
+    fn is_marked(&self, path: &Path) -> bool {
+        self.nodes
+            .id(path)
+            .is_some_and(|id| self.marked.contains(&id))
+    }
+
+    fn toggle_mark_selected(&mut self) {
+        let Some(id) = self
+            .selected_node()
+            .and_then(|node| self.nodes.id(&node.path))
+        else {
+            return;
+        };
+        if !self.marked.remove(&id) {
+            self.marked.insert(id);
+        }
+        self.move_selection(1);
+    }
+
+    /// Marks every visible row, or clears them when all are already marked.
+    fn toggle_mark_visible(&mut self) {
+        let visible: Vec<NodeId> = self
+            .visible_node_paths()
+            .iter()
+            .filter_map(|path| self.nodes.id(path))
+            .filter(|id| self.nodes.stats(*id).is_some())
+            .collect();
+        if visible.iter().all(|id| self.marked.contains(id)) {
+            for id in &visible {
+                self.marked.remove(id);
+            }
+        } else {
+            self.marked.extend(visible);
+        }
+    }
+
+    /// Marked entries (or the selected row when nothing is marked). Entries nested
+    /// below another marked entry are dropped since deleting the ancestor covers them.
+    fn delete_targets(&self) -> Vec<PathBuf> {
+        if self.marked.is_empty() {
+            return self
+                .selected_node()
+                .map(|node| vec![node.path])
+                .unwrap_or_default();
+        }
+
+        let mut targets: Vec<PathBuf> = self
+            .marked
+            .iter()
+            .filter(|id| self.nodes.stats(**id).is_some())
+            .filter(|id| {
+                !self
+                    .nodes
+                    .ancestors(**id)
+                    .skip(1)
+                    .any(|ancestor| self.marked.contains(&ancestor))
+            })
+            .map(|id| self.nodes.path(*id))
+            .collect();
+        targets.sort();
+        targets
+    }
+
+    fn targets_size(&self, targets: &[PathBuf]) -> u64 {
+        targets
+            .iter()
+            .filter_map(|path| self.nodes.get(path))
+            .map(|node| node.metric_bytes(self.metric))
+            .sum()
+    }
+
+    fn move_selection(&mut self, delta: isize) {
+        if self.view != ViewMode::Children {
+            let max = self.panel_len().saturating_sub(1) as isize;
+            self.panel_index = (self.panel_index as isize + delta).clamp(0, max) as usize;
+            self.ensure_panel_in_bounds();
+            return;
+        }
+
+        let len = self.visible_len();
+        if len == 0 {
+            self.selected_index = 0;
+            self.table_scroll_offset = 0;
+            return;
+        }
+
+        let current = self.selected_index as isize;
+        let max = (len - 1) as isize;
+        let next = (current + delta).clamp(0, max);
+        self.selected_index = next as usize;
+        self.sync_table_scroll(len);
+    }
+
+    /// Expands the selected directory in place, or steps into it when it
+    /// already is expanded.
+    fn expand_selected(&mut self) {
+        let Some(path) = self.visible_path(self.selected_index) else {
+            return;
+        };
+        if !self.is_expandable(&path) {
+            return;
+        }
+        let Some(id) = self.nodes.id(&path) else {
+            return;
+        };
+        if !self.expanded.insert(id) {
+            self.move_selection(1);
+        }
+    }
+
+    /// Collapses the selected directory, or selects its parent row when it is
+    /// not expanded.
+    fn collapse_selected(&mut self) {
+        let paths = self.visible_node_paths();
+        let Some(path) = paths.get(self.selected_index) else {
+            return;
+        };
+        if self
+            .nodes
+            .id(path)
+            .is_some_and(|id| self.expanded.remove(&id))
+        {
+            self.ensure_selection_in_bounds();
+            return;
+        }
+        if let Some(parent) = path.parent()
+            && let Some(index) = paths.iter().position(|candidate| candidate == parent)
+        {
+            self.selected_index = index;
+            self.ensure_selection_in_bounds();
+        }
+    }
+
+    fn drill_into_selection(&mut self) {
+        if let Some(node) = self.selected_node()
+            && matches!(node.kind, FsEntryKind::Dir | FsEntryKind::Symlink)
+        {
+            self.current_root = node.path.clone();
+            self.selected_index = 0;
+            self.ensure_selection_in_bounds();
+        }
+    }
+
+    fn navigate_to_parent(&mut self) {
+        if self.current_root == self.startup_root {
+            return;
+        }
+
+        let next_root = self
             .current_root
             .parent()
             .filter(|parent| parent.starts_with(&self.startup_root))
@@ -693,78 +2108,211 @@ impl App {
         self.ensure_selection_in_bounds();
     }
 
+    /// Queues an external hand-off for the selected entry, or for the current
+    /// directory when nothing is selected.
+    fn queue_launch(&mut self, action: Action) {
+        let (path, is_dir) = match self.selected_entry() {
+            Some(node) => (node.path, node.kind == FsEntryKind::Dir),
+            None => (self.current_root.clone(), true),
+        };
+        let dir = working_dir(&path, is_dir);
+        if action != Action::CopyPath && !dir.is_dir() {
+            self.message = Some(format!("{} does not exist on this machine", dir.display()));
+            return;
+        }
+
+        self.pending_launch = match action {
+            Action::Shell => Some(ExternalLaunch::Shell { dir }),
+            Action::OpenExternal => Some(ExternalLaunch::Open { path }),
+            Action::CopyPath => Some(ExternalLaunch::CopyPath { path }),
+            Action::RunCommand(index) => match self
+                .config
+                .commands
+                .get(index)
+                .map(|template| expand_template(template, &path, &dir))
+            {
+                Some(Ok(line)) => Some(ExternalLaunch::Command { line, dir }),
+                Some(Err(message)) => {
+                    self.message = Some(message);
+                    None
+                }
+                None => None,
+            },
+            _ => None,
+        };
+    }
+
+    /// The selected row of the table or of an entry panel.
+    fn selected_entry(&self) -> Option<NodeSummary> {
+        if self.view == ViewMode::LargestFiles {
+            return self
+                .largest_files
+                .below(&self.current_root, self.metric)
+                .get(self.panel_index)
+                .map(|file| (*file).clone());
+        }
+        self.selected_node()
+    }
+
     fn selected_node(&self) -> Option<NodeSummary> {
-        let paths = self.visible_node_paths();
-        let path = paths.get(self.selected_index)?;
-        self.nodes.get(path).cloned()
+        match self.view {
+            ViewMode::Children => {
+                let path = self.visible_path(self.selected_index)?;
+                self.nodes.summary(&path)
+            }
+            ViewMode::Duplicates => {
+                let (_, _, path) = *self.duplicate_rows().get(self.panel_index)?;
+                self.nodes.summary(path)
+            }
+            ViewMode::Extensions
+            | ViewMode::Categories
+            | ViewMode::LargestFiles
+            | ViewMode::Users
+            | ViewMode::Groups => None,
+        }
     }
 
     fn visible_node_paths(&self) -> Vec<PathBuf> {
-        let mut paths = self
-            .children
-            .get(&self.current_root)
+        self.visible_rows()
+            .into_iter()
+            .map(|row| row.path)
+            .collect()
+    }
+
+    /// Number of table rows; outside tree mode this reads the cached listing
+    /// without building any rows.
+    fn visible_len(&self) -> usize {
+        if self.tree_mode {
+            return self.visible_rows().len();
+        }
+        self.sorted_children(&self.current_root).paths.len()
+    }
+
+    fn visible_path(&self, index: usize) -> Option<PathBuf> {
+        if self.tree_mode {
+            return self
+                .visible_rows()
+                .into_iter()
+                .nth(index)
+                .map(|row| row.path);
+        }
+        self.sorted_children(&self.current_root)
+            .paths
+            .get(index)
             .cloned()
-            .unwrap_or_default();
+    }
 
-        let filter = self.filter.to_lowercase();
-        if !filter.is_empty() {
-            paths.retain(|path| {
-                let name = path
-                    .file_name()
-                    .map(|n| n.to_string_lossy().to_lowercase())
-                    .unwrap_or_default();
-                let full_path = path.to_string_lossy().to_lowercase();
-                name.contains(&filter) || full_path.contains(&filter)
-            });
+    /// Table rows in display order: the children of `current_root`, plus the
+    /// contents of expanded directories in tree mode.
+    fn visible_rows(&self) -> Vec<TreeRow> {
+        let mut rows = Vec::new();
+        if self.tree_mode {
+            self.push_tree_rows(&self.current_root, "", &mut rows);
+        } else {
+            rows = self.visible_rows_window(0, usize::MAX);
         }
+        rows
+    }
 
-        paths.retain(|path| self.nodes.contains_key(path));
+    /// Up to `len` table rows from `offset` on; outside tree mode only those
+    /// rows are built.
+    fn visible_rows_window(&self, offset: usize, len: usize) -> Vec<TreeRow> {
+        if self.tree_mode {
+            return self
+                .visible_rows()
+                .into_iter()
+                .skip(offset)
+                .take(len)
+                .collect();
+        }
+        self.sorted_children(&self.current_root)
+            .paths
+            .iter()
+            .skip(offset)
+            .take(len)
+            .map(|path| TreeRow {
+                path: path.clone(),
+                prefix: String::new(),
+                sibling_max_bytes: None,
+            })
+            .collect()
+    }
 
-        match self.sort_mode {
-            SortMode::SizeDesc => paths.sort_by(|a, b| {
-                let left = self
-                    .nodes
-                    .get(a)
-                    .map(|node| node.metric_bytes(self.metric))
-                    .unwrap_or_default();
-                let right = self
-                    .nodes
-                    .get(b)
-                    .map(|node| node.metric_bytes(self.metric))
-                    .unwrap_or_default();
-                right.cmp(&left).then_with(|| a.cmp(b))
-            }),
-            SortMode::SizeAsc => paths.sort_by(|a, b| {
-                let left = self
-                    .nodes
-                    .get(a)
-                    .map(|node| node.metric_bytes(self.metric))
-                    .unwrap_or_default();
-                let right = self
+    /// Appends the children of `dir` and, recursively, of its expanded
+    /// subdirectories; `guides` holds the indentation drawn for `dir`'s level.
+    fn push_tree_rows(&self, dir: &Path, guides: &str, rows: &mut Vec<TreeRow>) {
+        let listing = self.sorted_children(dir);
+        let sibling_max_bytes = listing.max_bytes;
+        let top_level = dir == self.current_root;
+
+        for (index, path) in listing.paths.iter().enumerate() {
+            let last = index + 1 == listing.paths.len();
+            let expandable = self.is_expandable(path);
+            let expanded = expandable
+                && self
                     .nodes
-                    .get(b)
-                    .map(|node| node.metric_bytes(self.metric))
-                    .unwrap_or_default();
-                left.cmp(&right).then_with(|| a.cmp(b))
-            }),
-            SortMode::Name => paths.sort_by(|a, b| {
-                let a_name = a
-                    .file_name()
-                    .map(|name| name.to_string_lossy().to_lowercase())
-                    .unwrap_or_default();
-                let b_name = b
-                    .file_name()
-                    .map(|name| name.to_string_lossy().to_lowercase())
-                    .unwrap_or_default();
-                a_name.cmp(&b_name)
-            }),
+                    .id(path)
+                    .is_some_and(|id| self.expanded.contains(&id));
+            let connector = match (top_level, last) {
+                (true, _) => "",
+                (false, true) => "└─ ",
+                (false, false) => "├─ ",
+            };
+            let marker = match (expandable, expanded) {
+                (true, true) => "▾ ",
+                (true, false) => "▸ ",
+                (false, _) => "  ",
+            };
+            rows.push(TreeRow {
+                path: path.clone(),
+                prefix: format!("{guides}{connector}{marker}"),
+                sibling_max_bytes,
+            });
+
+            if expanded {
+                let nested_guides = match (top_level, last) {
+                    (true, _) => String::new(),
+                    (false, true) => format!("{guides}   "),
+                    (false, false) => format!("{guides}│  "),
+                };
+                self.push_tree_rows(path, &nested_guides, rows);
+            }
         }
+    }
+
+    fn is_expandable(&self, path: &Path) -> bool {
+        self.nodes.has_children(path)
+    }
 
-        paths
+    /// Filtered and sorted children of `dir`, including baseline entries that
+    /// no longer exist; cached until `dir` or one of its children changes.
+    fn sorted_children(&self, dir: &Path) -> Rc<Listing> {
+        let settings = ListingSettings {
+            sort_mode: self.sort_mode,
+            metric: self.metric,
+            filter: self.filter.to_lowercase(),
+            older_than: self.config.older_than,
+            owner_filter: self.owner_filter,
+            owners_generation: self.owner_filter.map_or(0, |_| self.owners_generation),
+        };
+        self.listings.borrow_mut().get_or_build(dir, &settings, || {
+            list_children(
+                &self.nodes,
+                self.baseline.as_ref(),
+                dir,
+                &settings,
+                |path, owner| self.owned_by(path, owner),
+            )
+        })
+    }
+
+    fn node_delta(&self, path: &Path) -> Option<i64> {
+        let baseline = self.baseline.as_ref()?;
+        Some(baseline.delta(path, self.nodes.get(path), self.metric))
     }
 
     fn ensure_selection_in_bounds(&mut self) {
-        let len = self.visible_node_paths().len();
+        let len = self.visible_len();
         if len == 0 {
             self.selected_index = 0;
             self.table_scroll_offset = 0;
@@ -786,54 +2334,201 @@ impl App {
     }
 
     fn build_view_model(&mut self) -> ViewModel {
+        let now = SystemTime::now();
+        // Before the first frame the table height is unknown, so every row is built.
+        let (rows_offset, window) = match self.table_visible_rows() {
+            0 => (0, usize::MAX),
+            height => (
+                self.table_scroll_offset
+                    .min(self.visible_len().saturating_sub(1)),
+                height,
+            ),
+        };
         let rows: Vec<RowModel> = self
-            .visible_node_paths()
+            .visible_rows_window(rows_offset, window)
             .into_iter()
-            .filter_map(|path| self.nodes.get(&path))
-            .map(|node| RowModel {
-                name: node
-                    .path
-                    .file_name()
-                    .map(|n| n.to_string_lossy().into_owned())
-                    .unwrap_or_else(|| node.path.to_string_lossy().into_owned()),
-                kind: node.kind,
-                size_bytes: node.metric_bytes(self.metric),
-                path_display: node.path.to_string_lossy().into_owned(),
-                is_loading: !node.is_complete,
+            .filter_map(|row| {
+                let path = row.path;
+                let current = self.nodes.get(&path);
+                let diff = self
+                    .baseline
+                    .as_ref()
+                    .and_then(|baseline| baseline.status(&path, current));
+                // Removed rows are rendered from the baseline with a zero current size.
+                let node = current.or_else(|| self.baseline.as_ref()?.node(&path))?;
+                Some(RowModel {
+                    name: path
+                        .file_name()
+                        .map(|n| n.to_string_lossy().into_owned())
+                        .unwrap_or_else(|| path.to_string_lossy().into_owned()),
+                    kind: node.kind,
+                    size_bytes: current.map_or(0, |node| node.metric_bytes(self.metric)),
+                    modified_age: node
+                        .age
+                        .modified
+                        .map(|modified| now.duration_since(modified).unwrap_or_default()),
+                    path_display: path.to_string_lossy().into_owned(),
+                    is_loading: !node.is_complete,
+                    excluded_entries: node.excluded_entries,
+                    marked: self.is_marked(&path),
+                    diff,
+                    delta_bytes: self.node_delta(&path).unwrap_or_default(),
+                    tree_prefix: row.prefix,
+                    sibling_max_bytes: row.sibling_max_bytes,
+                })
             })
             .collect();
         let show_loading_hint = rows.is_empty() && self.scan_state.is_scanning();
 
+        let display_targets = |targets: &[PathBuf]| -> Vec<String> {
+            targets
+                .iter()
+                .map(|target| target.to_string_lossy().into_owned())
+                .collect()
+        };
         let dialog = match &self.delete_dialog {
             DeleteDialog::None => DialogStateView::None,
-            DeleteDialog::Confirm { target } => DialogStateView::Confirm {
-                target: target.to_string_lossy().into_owned(),
+            DeleteDialog::Confirm { targets } => DialogStateView::Confirm {
+                targets: display_targets(targets),
+                total_size: format_bytes(self.targets_size(targets)),
             },
-            DeleteDialog::TypePhrase { target, typed } => DialogStateView::TypePhrase {
-                target: target.to_string_lossy().into_owned(),
+            DeleteDialog::TypePhrase { targets, typed } => DialogStateView::TypePhrase {
+                targets: display_targets(targets),
+                total_size: format_bytes(self.targets_size(targets)),
                 typed: typed.clone(),
             },
+            DeleteDialog::Summary { results } => DialogStateView::Summary {
+                lines: results
+                    .iter()
+                    .map(|(target, error)| match error {
+                        None => format!("ok      {}", target.to_string_lossy()),
+                        Some(error) => format!("FAILED  {error}"),
+                    })
+                    .collect(),
+            },
         };
 
+        let marked_line = (!self.marked.is_empty()).then(|| {
+            let targets = self.delete_targets();
+            format!(
+                "Marked: {} ({})",
+                self.marked.len(),
+                format_bytes(self.targets_size(&targets))
+            )
+        });
+
+        let diff_line = self.baseline.as_ref().map(|baseline| {
+            format!(
+                "Diff vs {} ({} ago): {}",
+                baseline.host,
+                format_age(
+                    SystemTime::now()
+                        .duration_since(baseline.created_at)
+                        .unwrap_or_default()
+                ),
+                format_delta(self.node_delta(&self.current_root).unwrap_or_default())
+            )
+        });
+
+        let shared_bytes_line = self
+            .nodes
+            .get(&self.current_root)
+            .map(|node| node.shared_bytes(self.metric))
+            .filter(|shared| *shared > 0)
+            .map(|shared| format!("Hardlinked (counted once): {}", format_bytes(shared)));
+
+        let stale_after = self.config.scan_options.stale_after;
+        let age_line = self
+            .nodes
+            .get(&self.current_root)
+            .map(|node| node.age.stale_metric_bytes(self.metric))
+            .filter(|stale| *stale > 0 || self.config.older_than.is_some())
+            .map(|stale| {
+                let mut line = format!(
+                    "Untouched >{}: {}",
+                    format_age(stale_after),
+                    format_bytes(stale)
+                );
+                if self.config.older_than.is_some() {
+                    line.push_str(" (listing older entries only)");
+                }
+                line
+            });
+
+        let owner_line = self.owner_filter.map(|owner| {
+            let bytes = self
+                .current_subtree_owners()
+                .stat(owner)
+                .metric_bytes(self.metric);
+            format!(
+                "Owner {} {}: {} here",
+                owner.kind,
+                self.owner_names.name(owner),
+                format_bytes(bytes)
+            )
+        });
+
+        let panel = self.build_panel();
+        let treemap = self
+            .treemap
+            .filter(|_| self.view == ViewMode::Children)
+            .map(|coloring| self.build_treemap(coloring));
+
         ViewModel {
             current_root: self.current_root.to_string_lossy().into_owned(),
             disk_line: self.current_disk_line(),
             metric: self.metric.as_str().to_string(),
-            sort_mode: self.sort_mode.as_str().to_string(),
-            scan_status: self.scan_state.as_status(),
+            sort_mode: if matches!(self.view, ViewMode::LargestFiles | ViewMode::Duplicates) {
+                SortMode::SizeDesc.as_str().to_string()
+            } else if panel.is_some() {
+                self.breakdown_sort.as_str().to_string()
+            } else {
+                self.sort_mode.as_str().to_string()
+            },
+            scan_status: if self.is_watching() {
+                format!("{}, watching for changes", self.scan_state.as_status())
+            } else {
+                self.scan_state.as_status()
+            },
+            shared_bytes_line,
+            diff_line,
+            marked_line,
+            age_line,
+            owner_line,
             filter: self.filter.clone(),
             filter_mode: self.filter_mode,
             rows,
+            panel,
+            treemap,
+            tree_mode: self.tree_mode,
             selected_index: self.selected_index,
-            table_scroll_offset: self.table_scroll_offset,
+            table_scroll_offset: rows_offset,
+            max_size_bytes: self
+                .sorted_children(&self.current_root)
+                .max_bytes
+                .unwrap_or(0),
             show_name_column: self.column_visibility.name,
             show_kind_column: self.column_visibility.kind,
             show_size_column: self.column_visibility.size,
+            show_modified_column: self.column_visibility.modified,
             show_relative_column: self.column_visibility.relative,
             show_path_column: self.column_visibility.path,
+            show_delta_column: self.baseline.is_some(),
             warning_line: self.warnings.last().cloned(),
             message_line: self.message.clone(),
             delete_enabled: !self.config.no_delete,
+            delete_mode: self.config.delete_mode,
+            keymap: self.config.keymap.clone(),
+            commands: self
+                .config
+                .commands
+                .iter()
+                .enumerate()
+                .map(|(index, template)| {
+                    let keys = self.config.keymap.label(Action::RunCommand(index), " / ");
+                    (keys, template.clone())
+                })
+                .collect(),
             dialog,
             loading_hint: if show_loading_hint {
                 let spinner = SPINNER_FRAMES[self.spinner_tick % SPINNER_FRAMES.len()];
@@ -871,7 +2566,11 @@ impl App {
             .unwrap_or(true);
 
         if path_changed || stale || self.disk_line_cache.is_none() {
-            self.disk_line_cache = Some(build_disk_line(&self.current_root));
+            self.disk_line_cache = Some(if self.scan_state.is_snapshot() {
+                "Disk: not available while browsing a snapshot".to_string()
+            } else {
+                build_disk_line(&self.current_root)
+            });
             self.disk_line_cache_root = Some(self.current_root.clone());
             self.disk_line_last_update = Some(now);
         }
@@ -882,6 +2581,33 @@ impl App {
     }
 }
 
+/// Gives the terminal back to the shell while a foreground program runs.
+fn suspend_terminal(terminal: &mut Terminal<CrosstermBackend<io::Stdout>>) -> Result<(), AppError> {
+    disable_raw_mode().map_err(|error| AppError::Terminal(error.to_string()))?;
+    execute!(
+        terminal.backend_mut(),
+        DisableMouseCapture,
+        LeaveAlternateScreen
+    )
+    .map_err(|error| AppError::Terminal(error.to_string()))?;
+    terminal
+        .show_cursor()
+        .map_err(|error| AppError::Terminal(error.to_string()))
+}
+
+fn resume_terminal(terminal: &mut Terminal<CrosstermBackend<io::Stdout>>) -> Result<(), AppError> {
+    enable_raw_mode().map_err(|error| AppError::Terminal(error.to_string()))?;
+    execute!(
+        terminal.backend_mut(),
+        EnterAlternateScreen,
+        EnableMouseCapture
+    )
+    .map_err(|error| AppError::Terminal(error.to_string()))?;
+    terminal
+        .clear()
+        .map_err(|error| AppError::Terminal(error.to_string()))
+}
+
 fn build_disk_line(path: &Path) -> String {
     let Some(usage) = disk_usage(path) else {
         return "Disk: unavailable".to_string();
@@ -935,15 +2661,30 @@ fn compute_scroll_offset(
 #[cfg(test)]
 mod tests {
     use std::fs;
-    use std::path::PathBuf;
+    use std::path::{Path, PathBuf};
+    use std::time::SystemTime;
 
-    use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+    use crossterm::event::{
+        KeyCode, KeyEvent, KeyModifiers, MouseButton, MouseEvent, MouseEventKind,
+    };
+    use ratatui::layout::Rect;
     use tempfile::TempDir;
 
     use crate::cli::Config;
-    use crate::model::{ScanOptions, SizeMetric};
+    use crate::delete::DeleteMode;
+    use crate::diff::DiffStatus;
+    use crate::keymap::{Action, KeyBinding, Keymap};
+    use crate::model::{
+        AgeSummary, ColumnVisibility, DEFAULT_STALE_AFTER, FsEntryKind, NodeSummary, ScanEvent,
+        ScanOptions, SizeMetric, SortMode,
+    };
+    use crate::owners::{Owner, OwnerNames, OwnerTally};
+    use crate::scanner::run_scan_blocking;
+    use crate::snapshot::Snapshot;
 
-    use super::{App, DeleteDialog, compute_scroll_offset};
+    use super::{App, DeleteDialog, DuplicateState, compute_scroll_offset};
+    use crate::filetypes::{FileCategory, TypeTally};
+    use crate::ui::{TreemapColoring, treemap_layout};
 
     fn test_config(startup_root: PathBuf) -> Config {
         Config {
@@ -955,9 +2696,42 @@ mod tests {
                 show_hidden: true,
                 show_files: true,
                 max_depth: None,
+                threads: 1,
+                exclude_patterns: Vec::new(),
+                include_patterns: Vec::new(),
+                report_excluded: true,
+                stale_after: DEFAULT_STALE_AFTER,
             },
             initial_metric: SizeMetric::Allocated,
             no_delete: false,
+            delete_mode: DeleteMode::Permanent,
+            report: None,
+            save_snapshot: None,
+            snapshot: None,
+            diff_baseline: None,
+            watch: false,
+            older_than: None,
+            initial_sort: SortMode::SizeDesc,
+            initial_columns: ColumnVisibility::all_visible(),
+            keymap: Keymap::default(),
+            commands: Vec::new(),
+        }
+    }
+
+    fn summary(path: impl AsRef<Path>, kind: FsEntryKind, bytes: u64) -> NodeSummary {
+        NodeSummary {
+            path: path.as_ref().to_path_buf(),
+            kind,
+            apparent_bytes: bytes,
+            allocated_bytes: bytes,
+            naive_apparent_bytes: bytes,
+            naive_allocated_bytes: bytes,
+            children_count: 0,
+            excluded_entries: 0,
+            age: AgeSummary::default(),
+            owner: None,
+            is_complete: true,
+            last_updated: SystemTime::now(),
         }
     }
 
@@ -965,6 +2739,10 @@ mod tests {
         KeyEvent::new(KeyCode::Enter, KeyModifiers::NONE)
     }
 
+    fn key(ch: char) -> KeyEvent {
+        KeyEvent::new(KeyCode::Char(ch), KeyModifiers::NONE)
+    }
+
     #[test]
     fn keeps_selection_visible_when_moving_down() {
         assert_eq!(compute_scroll_offset(0, 0, 100, 5), 0);
@@ -997,7 +2775,7 @@ mod tests {
         let mut app = App::new(test_config(startup.clone()));
         app.current_root = child;
         app.delete_dialog = DeleteDialog::TypePhrase {
-            target: startup.clone(),
+            targets: vec![startup.clone()],
             typed: "DELETE".to_string(),
         };
 
@@ -1014,6 +2792,89 @@ mod tests {
         assert!(startup.exists());
     }
 
+    #[test]
+    fn batch_delete_removes_marked_entries_and_reports_each() {
+        let temp = TempDir::new().expect("temp dir");
+        for name in ["cache-a", "cache-b", "keep"] {
+            fs::create_dir(temp.path().join(name)).expect("create dir");
+            fs::write(temp.path().join(name).join("blob"), vec![0_u8; 64]).expect("write");
+        }
+        let startup = fs::canonicalize(temp.path()).expect("canonical startup");
+
+        let mut app = App::new(test_config(startup.clone()));
+        for event in run_scan_blocking(app.config.scan_options.clone()) {
+            if let ScanEvent::NodeUpdated(node) = event {
+                app.upsert_node(node);
+            }
+        }
+
+        app.handle_key(key('a')).expect("mark all");
+        let keep_index = app
+            .visible_node_paths()
+            .iter()
+            .position(|path| path.ends_with("keep"))
+            .expect("keep visible");
+        app.selected_index = keep_index;
+        app.handle_key(key(' ')).expect("unmark keep");
+        assert_eq!(app.delete_targets().len(), 2);
+
+        app.handle_key(key('d')).expect("open dialog");
+        app.handle_key(enter_key()).expect("confirm");
+        for ch in "DELETE".chars() {
+            app.handle_key(key(ch)).expect("type");
+        }
+        app.handle_key(enter_key()).expect("delete");
+
+        assert!(!startup.join("cache-a").exists());
+        assert!(!startup.join("cache-b").exists());
+        assert!(startup.join("keep").exists());
+        assert!(app.marked.is_empty());
+        assert_eq!(app.message.as_deref(), Some("Deleted 2 of 2 entries"));
+        let DeleteDialog::Summary { results } = &app.delete_dialog else {
+            panic!("expected summary dialog");
+        };
+        assert_eq!(results.len(), 2);
+        assert!(results.iter().all(|(_, error)| error.is_none()));
+    }
+
+    #[test]
+    fn delete_updates_cached_tree_without_rescanning() {
+        let temp = TempDir::new().expect("temp dir");
+        let nested = temp.path().join("project").join("target");
+        fs::create_dir_all(nested.join("debug")).expect("create nested");
+        fs::write(nested.join("debug").join("app"), vec![0_u8; 8192]).expect("write app");
+        fs::write(temp.path().join("project").join("main.rs"), vec![0_u8; 100]).expect("write");
+        let startup = fs::canonicalize(temp.path()).expect("canonical startup");
+        let project = startup.join("project");
+        let target = project.join("target");
+
+        let mut app = App::new(test_config(startup.clone()));
+        if let Some(mut scan) = app.scanner.take() {
+            scan.stop();
+        }
+        for event in run_scan_blocking(app.config.scan_options.clone()) {
+            if let ScanEvent::NodeUpdated(node) = event {
+                app.upsert_node(node);
+            }
+        }
+        let node = |app: &App, path: &Path| *app.nodes.get(path).expect("scanned node");
+        let removed = node(&app, &target).apparent_bytes;
+        let root_before = node(&app, &startup).apparent_bytes;
+        let project_children = node(&app, &project).children_count;
+
+        app.run_delete(vec![target.clone()]);
+
+        assert!(!target.exists());
+        assert!(app.scanner.is_none());
+        assert!(!app.nodes.contains(&target));
+        assert!(!app.nodes.contains(&target.join("debug").join("app")));
+        assert!(!app.nodes.has_children(&target));
+        assert!(!app.nodes.child_paths(&project).contains(&target));
+        assert_eq!(node(&app, &project).children_count, project_children - 1);
+        assert_eq!(node(&app, &startup).apparent_bytes, root_before - removed);
+        assert!(app.nodes.contains(&project.join("main.rs")));
+    }
+
     #[test]
     fn navigate_to_parent_clamps_to_startup_root() {
         let temp = TempDir::new().expect("temp dir");
@@ -1035,4 +2896,562 @@ mod tests {
         assert_eq!(app.selected_index, 0);
         assert_eq!(app.table_scroll_offset, 0);
     }
+
+    #[test]
+    fn loaded_snapshot_is_browsable_without_scanning() {
+        let root = PathBuf::from("/remote/server");
+        let mut config = test_config(root.clone());
+        config.snapshot = Some(Snapshot {
+            created_at: SystemTime::now(),
+            host: "build-01".to_string(),
+            scan_options: config.scan_options.clone(),
+            nodes: vec![
+                summary("/remote/server", FsEntryKind::Dir, 300),
+                summary("/remote/server/small", FsEntryKind::Dir, 100),
+                summary("/remote/server/large", FsEntryKind::Dir, 200),
+            ],
+        });
+
+        let mut app = App::new(config);
+        assert!(app.scan_state.is_snapshot());
+        assert_eq!(
+            app.selected_node().map(|node| node.path),
+            Some(root.join("large"))
+        );
+
+        app.handle_key(KeyEvent::new(KeyCode::Char('r'), KeyModifiers::NONE))
+            .expect("handle key");
+        assert!(app.scanner.is_none());
+        assert!(
+            app.message
+                .as_deref()
+                .unwrap_or_default()
+                .contains("unavailable while browsing a snapshot")
+        );
+    }
+
+    #[test]
+    fn diff_baseline_marks_added_and_removed_rows() {
+        let root = PathBuf::from("/remote/server");
+        let node = |path: &str, bytes| summary(path, FsEntryKind::Dir, bytes);
+        let mut config = test_config(root.clone());
+        config.snapshot = Some(Snapshot {
+            created_at: SystemTime::now(),
+            host: "build-01".to_string(),
+            scan_options: config.scan_options.clone(),
+            nodes: vec![
+                node("/remote/server", 900),
+                node("/remote/server/videos", 800),
+                node("/remote/server/downloads", 100),
+            ],
+        });
+        config.diff_baseline = Some(Snapshot {
+            created_at: SystemTime::now(),
+            host: "build-01".to_string(),
+            scan_options: config.scan_options.clone(),
+            nodes: vec![
+                node("/remote/server", 700),
+                node("/remote/server/videos", 200),
+                node("/remote/server/tmp", 500),
+            ],
+        });
+
+        let mut app = App::new(config);
+        app.sort_mode = SortMode::GrowthDesc;
+        let model = app.build_view_model();
+        let rows: Vec<(&str, Option<DiffStatus>, i64)> = model
+            .rows
+            .iter()
+            .map(|row| (row.name.as_str(), row.diff, row.delta_bytes))
+            .collect();
+
+        assert_eq!(
+            rows,
+            vec![
+                ("videos", Some(DiffStatus::Present), 600),
+                ("downloads", Some(DiffStatus::Added), 100),
+                ("tmp", Some(DiffStatus::Removed), -500),
+            ]
+        );
+        assert!(model.show_delta_column);
+        assert!(
+            model
+                .diff_line
+                .as_deref()
+                .unwrap_or_default()
+                .contains("+200 B")
+        );
+    }
+
+    #[test]
+    fn largest_files_view_lists_files_hidden_from_the_table() {
+        let temp = TempDir::new().expect("temp dir");
+        let nested = temp.path().join("media").join("2024");
+        fs::create_dir_all(&nested).expect("create nested");
+        fs::write(nested.join("clip.mp4"), vec![0_u8; 9000]).expect("write clip");
+        fs::write(temp.path().join("notes.txt"), vec![0_u8; 10]).expect("write notes");
+        let startup = fs::canonicalize(temp.path()).expect("canonical startup");
+
+        let mut config = test_config(startup.clone());
+        config.scan_options.show_files = false;
+        let mut app = App::new(config);
+        for event in run_scan_blocking(app.config.scan_options.clone()) {
+            match event {
+                ScanEvent::NodeUpdated(node) => app.upsert_node(node),
+                ScanEvent::LargeFile(file) => {
+                    app.largest_files.offer(&file);
+                }
+                _ => {}
+            }
+        }
+        assert!(!app.nodes.contains(&startup.join("notes.txt")));
+
+        app.handle_key(key('f')).expect("open largest files");
+        let panel = app.build_view_model().panel.expect("largest files panel");
+        let rows: Vec<(&str, &str)> = panel
+            .rows
+            .iter()
+            .map(|row| (row.cells[0].as_str(), row.cells[2].as_str()))
+            .collect();
+        assert_eq!(
+            rows,
+            vec![("clip.mp4", "./media/2024"), ("notes.txt", "./")]
+        );
+
+        app.handle_key(enter_key()).expect("reveal clip");
+        assert_eq!(app.current_root, startup.join("media").join("2024"));
+        assert!(app.build_view_model().panel.is_none());
+    }
+
+    #[test]
+    fn panel_titles_name_the_configured_keys() {
+        let mut config = test_config(PathBuf::from("/srv"));
+        config.keymap = Keymap::default().with_overrides(&[
+            (
+                Action::LargestFiles,
+                KeyBinding::parse("L").expect("valid key"),
+            ),
+            (Action::Open, KeyBinding::parse("l").expect("valid key")),
+        ]);
+        let mut app = App::new(config);
+
+        app.handle_key(key('L')).expect("open largest files");
+        let panel = app.build_view_model().panel.expect("largest files panel");
+        assert!(panel.title.contains("l: open folder, Shift+L: back"));
+    }
+
+    #[test]
+    fn duplicates_view_groups_copies_and_keeps_one_on_delete() {
+        let temp = TempDir::new().expect("temp dir");
+        for dir in ["dataset", "backup"] {
+            fs::create_dir(temp.path().join(dir)).expect("create dir");
+            fs::write(temp.path().join(dir).join("train.csv"), vec![3_u8; 4096])
+                .expect("write copy");
+        }
+        fs::write(temp.path().join("other.csv"), vec![4_u8; 4096]).expect("write other");
+        let startup = fs::canonicalize(temp.path()).expect("canonical startup");
+
+        let mut config = test_config(startup.clone());
+        config.scan_options.show_files = false;
+        let mut app = App::new(config);
+        for event in run_scan_blocking(app.config.scan_options.clone()) {
+            match event {
+                ScanEvent::NodeUpdated(node) => app.upsert_node(node),
+                ScanEvent::DuplicateCandidates { path, files } => {
+                    app.record_duplicate_candidates(&path, files);
+                }
+                _ => {}
+            }
+        }
+        assert!(!app.nodes.contains(&startup.join("other.csv")));
+        let before = app.nodes.get(&startup).expect("root").apparent_bytes;
+
+        app.handle_key(key('u')).expect("open duplicates");
+        let deadline = std::time::Instant::now() + std::time::Duration::from_secs(10);
+        while matches!(app.duplicates, DuplicateState::Searching { .. }) {
+            assert!(
+                std::time::Instant::now() < deadline,
+                "duplicate search timed out"
+            );
+            std::thread::sleep(std::time::Duration::from_millis(5));
+            app.drain_duplicate_events();
+        }
+        let panel = app.build_view_model().panel.expect("duplicates panel");
+        assert_eq!(panel.rows.len(), 2);
+        assert!(panel.rows.iter().all(|row| row.cells[1] == "train.csv"));
+
+        app.handle_key(key(' ')).expect("mark first copy");
+        app.handle_key(key(' ')).expect("mark second copy");
+        app.handle_key(key('d')).expect("try delete");
+        assert!(matches!(app.delete_dialog, DeleteDialog::None));
+        assert!(
+            app.message
+                .as_deref()
+                .unwrap_or_default()
+                .contains("Refusing to delete every copy")
+        );
+
+        app.handle_key(key(' ')).expect("unmark second copy");
+        app.handle_key(key('d')).expect("open dialog");
+        app.handle_key(enter_key()).expect("confirm");
+        for ch in "DELETE".chars() {
+            app.handle_key(key(ch)).expect("type");
+        }
+        app.handle_key(enter_key()).expect("delete");
+
+        assert!(!startup.join("backup").join("train.csv").exists());
+        assert!(startup.join("dataset").join("train.csv").exists());
+        assert!(app.duplicates.groups().is_empty());
+        assert_eq!(
+            app.nodes.get(&startup).expect("root").apparent_bytes,
+            before - 4096
+        );
+    }
+
+    #[test]
+    fn older_than_filter_hides_recent_entries_and_sorts_oldest_first() {
+        let now = SystemTime::now();
+        let days_ago = |days: u64| Some(now - std::time::Duration::from_secs(days * 86_400));
+        let node = |path: &str, modified| NodeSummary {
+            age: AgeSummary {
+                modified,
+                ..AgeSummary::default()
+            },
+            ..summary(path, FsEntryKind::Dir, 10)
+        };
+        let mut config = test_config(PathBuf::from("/srv"));
+        config.older_than = Some(std::time::Duration::from_secs(180 * 86_400));
+        config.snapshot = Some(Snapshot {
+            created_at: now,
+            host: "build-01".to_string(),
+            scan_options: config.scan_options.clone(),
+            nodes: vec![
+                node("/srv", days_ago(1)),
+                node("/srv/active", days_ago(1)),
+                node("/srv/old", days_ago(200)),
+                node("/srv/ancient", days_ago(900)),
+                node("/srv/unknown", None),
+            ],
+        });
+
+        let mut app = App::new(config);
+        app.sort_mode = SortMode::OldestFirst;
+        let model = app.build_view_model();
+        let names: Vec<&str> = model.rows.iter().map(|row| row.name.as_str()).collect();
+        assert_eq!(names, vec!["ancient", "old"]);
+        assert!(model.rows[0].modified_age.is_some());
+    }
+
+    #[test]
+    fn owner_view_filters_table_to_entries_holding_owner_bytes() {
+        let node = |path: &str, kind, uid, bytes| NodeSummary {
+            owner: Some(Owner { uid, gid: 100 }),
+            ..summary(path, kind, bytes)
+        };
+        let tally = |entries: &[(u32, u64)]| {
+            let mut tally = OwnerTally::default();
+            for (uid, bytes) in entries {
+                tally.add_entry(
+                    Owner {
+                        uid: *uid,
+                        gid: 100,
+                    },
+                    *bytes,
+                    *bytes,
+                );
+            }
+            tally
+        };
+
+        let mut app = App::new(test_config(PathBuf::from("/srv")));
+        app.owner_names = OwnerNames::default();
+        for summary in [
+            node("/srv", FsEntryKind::Dir, 0, 4),
+            node("/srv/alice", FsEntryKind::Dir, 1000, 4),
+            node("/srv/alice/deep", FsEntryKind::Dir, 1000, 4),
+            node("/srv/shared", FsEntryKind::Dir, 0, 4),
+            node("/srv/root.log", FsEntryKind::File, 0, 50),
+            node("/srv/alice.iso", FsEntryKind::File, 1000, 900),
+        ] {
+            app.upsert_node(summary);
+        }
+        app.record_owners(Path::new("/srv"), tally(&[(0, 4), (0, 50), (1000, 900)]));
+        app.record_owners(Path::new("/srv/alice"), tally(&[(1000, 4)]));
+        app.record_owners(
+            Path::new("/srv/alice/deep"),
+            tally(&[(1000, 4), (1000, 30)]),
+        );
+        app.record_owners(Path::new("/srv/shared"), tally(&[(0, 4), (1001, 7)]));
+
+        app.handle_key(key('o')).expect("open users view");
+        let panel = app.build_view_model().panel.expect("users panel");
+        let users: Vec<(&str, &str)> = panel
+            .rows
+            .iter()
+            .map(|row| (row.cells[0].as_str(), row.cells[3].as_str()))
+            .collect();
+        assert_eq!(
+            users,
+            vec![("1000", "938 B"), ("0", "58 B"), ("1001", "7 B")]
+        );
+
+        app.handle_key(enter_key()).expect("filter to uid 1000");
+        let model = app.build_view_model();
+        assert!(model.panel.is_none());
+        let names: Vec<&str> = model.rows.iter().map(|row| row.name.as_str()).collect();
+        assert_eq!(names, vec!["alice.iso", "alice"]);
+        assert!(model.owner_line.is_some_and(|line| line.contains("938 B")));
+
+        app.handle_key(KeyEvent::new(KeyCode::Esc, KeyModifiers::NONE))
+            .expect("clear owner filter");
+        assert_eq!(app.build_view_model().rows.len(), 4);
+    }
+
+    #[test]
+    fn removed_entries_leave_nothing_behind_on_reused_ids() {
+        let mut app = App::new(test_config(PathBuf::from("/srv")));
+        app.tree_mode = true;
+        for node in [
+            summary("/srv", FsEntryKind::Dir, 30),
+            summary("/srv/a", FsEntryKind::Dir, 20),
+            summary("/srv/a/x", FsEntryKind::File, 20),
+            summary("/srv/b", FsEntryKind::File, 10),
+        ] {
+            app.upsert_node(node);
+        }
+        let mut owners = OwnerTally::default();
+        owners.add_entry(Owner { uid: 0, gid: 0 }, 20, 20);
+        app.record_owners(Path::new("/srv/a"), owners);
+        app.handle_key(KeyEvent::new(KeyCode::Right, KeyModifiers::NONE))
+            .expect("expand");
+        app.handle_key(key(' ')).expect("mark");
+        let a = app.nodes.id(Path::new("/srv/a")).expect("interned");
+        assert!(app.is_marked(Path::new("/srv/a")) && app.expanded.contains(&a));
+
+        app.forget_subtree(Path::new("/srv/a"));
+        app.upsert_node(summary("/srv/c", FsEntryKind::Dir, 5));
+        app.upsert_node(summary("/srv/c/y", FsEntryKind::File, 5));
+
+        assert_eq!(app.nodes.id(Path::new("/srv/c/y")), Some(a));
+        assert!(app.marked.is_empty() && app.expanded.is_empty() && app.owners.is_empty());
+        assert!(!app.is_marked(Path::new("/srv/c/y")));
+    }
+
+    #[test]
+    fn cached_listing_follows_updates_and_sort_changes() {
+        let node = |path: &str, bytes| summary(path, FsEntryKind::File, bytes);
+        let names = |app: &App| -> Vec<String> {
+            app.visible_node_paths()
+                .iter()
+                .map(|path| path.file_name().unwrap().to_string_lossy().into_owned())
+                .collect()
+        };
+
+        let mut app = App::new(test_config(PathBuf::from("/srv")));
+        for summary in [node("/srv", 30), node("/srv/a", 10), node("/srv/b", 20)] {
+            app.upsert_node(summary);
+        }
+        assert_eq!(names(&app), ["b", "a"]);
+        assert_eq!(app.visible_len(), 2);
+
+        app.upsert_node(node("/srv/a", 50));
+        app.upsert_node(node("/srv/c", 5));
+        assert_eq!(names(&app), ["a", "b", "c"]);
+        assert_eq!(app.visible_path(1), Some(PathBuf::from("/srv/b")));
+
+        app.handle_key(key('s')).expect("cycle sort");
+        assert_eq!(names(&app), ["c", "b", "a"]);
+
+        app.remove_subtree(Path::new("/srv/b"));
+        assert_eq!(names(&app), ["c", "a"]);
+
+        // Once the table height is known only the rows on screen are built.
+        app.last_frame_area = Rect::new(0, 0, 100, 24);
+        for index in 0..100 {
+            app.upsert_node(node(&format!("/srv/f{index:03}"), 100 + index));
+        }
+        app.selected_index = 90;
+        app.ensure_selection_in_bounds();
+        let model = app.build_view_model();
+        let height = app.table_visible_rows();
+        assert_eq!(model.rows.len(), height);
+        assert_eq!(model.max_size_bytes, 199);
+        let selected = model.selected_index - model.table_scroll_offset;
+        assert_eq!(model.rows[selected].name, "f088");
+    }
+
+    #[test]
+    fn treemap_tiles_follow_table_selection_and_mouse_clicks() {
+        let mut app = App::new(test_config(PathBuf::from("/srv")));
+        app.last_frame_area = Rect::new(0, 0, 100, 40);
+        for summary in [
+            summary("/srv", FsEntryKind::Dir, 4000),
+            summary("/srv/media", FsEntryKind::Dir, 3000),
+            summary("/srv/media/clip.mp4", FsEntryKind::File, 2996),
+            summary("/srv/build.log", FsEntryKind::File, 1000),
+        ] {
+            app.upsert_node(summary);
+        }
+        let mut media_types = TypeTally::default();
+        media_types.add_file(&PathBuf::from("/srv/media/clip.mp4"), 2996, 2996);
+        app.record_file_types(Path::new("/srv/media"), media_types);
+
+        app.handle_key(key('v')).expect("open treemap");
+        let treemap = app.build_view_model().treemap.expect("treemap view");
+        assert_eq!(treemap.coloring, TreemapColoring::Depth);
+        let names: Vec<&str> = treemap
+            .tiles
+            .iter()
+            .map(|tile| tile.name.as_str())
+            .collect();
+        assert_eq!(names, vec!["media", "build.log"]);
+        assert_eq!(treemap.tiles[0].children.len(), 1);
+
+        app.handle_key(key('v')).expect("color by category");
+        let treemap = app.build_view_model().treemap.expect("treemap view");
+        assert_eq!(treemap.tiles[0].category, Some(FileCategory::Video));
+        assert_eq!(treemap.tiles[1].category, Some(FileCategory::Logs));
+
+        let rects = treemap_layout(app.table_area(), &[3000, 1000]);
+        let click = |rect: Rect| MouseEvent {
+            kind: MouseEventKind::Down(MouseButton::Left),
+            column: rect.x + rect.width / 2,
+            row: rect.y + rect.height / 2,
+            modifiers: KeyModifiers::NONE,
+        };
+        app.handle_mouse(click(rects[1])).expect("select log tile");
+        assert_eq!(app.selected_index, 1);
+        app.handle_mouse(click(rects[0]))
+            .expect("select media tile");
+        app.handle_mouse(click(rects[0]))
+            .expect("double click media tile");
+        assert_eq!(app.current_root, PathBuf::from("/srv/media"));
+
+        app.handle_key(key('v')).expect("back to table");
+        assert!(app.build_view_model().treemap.is_none());
+    }
+
+    #[test]
+    fn tree_mode_expands_directories_in_place() {
+        let mut app = App::new(test_config(PathBuf::from("/srv")));
+        for summary in [
+            summary("/srv", FsEntryKind::Dir, 3500),
+            summary("/srv/media", FsEntryKind::Dir, 3000),
+            summary("/srv/media/clips", FsEntryKind::Dir, 2000),
+            summary("/srv/media/clips/a.mp4", FsEntryKind::File, 2000),
+            summary("/srv/media/song.mp3", FsEntryKind::File, 1000),
+            summary("/srv/build.log", FsEntryKind::File, 500),
+        ] {
+            app.upsert_node(summary);
+        }
+        let rows = |app: &mut App| -> Vec<(String, String, Option<u64>)> {
+            app.build_view_model()
+                .rows
+                .into_iter()
+                .map(|row| (row.tree_prefix, row.name, row.sibling_max_bytes))
+                .collect()
+        };
+        let arrow = |code| KeyEvent::new(code, KeyModifiers::NONE);
+
+        app.handle_key(key('e')).expect("enable tree mode");
+        assert_eq!(
+            rows(&mut app),
+            vec![
+                ("▸ ".to_string(), "media".to_string(), Some(3000)),
+                ("  ".to_string(), "build.log".to_string(), Some(3000)),
+            ]
+        );
+
+        app.handle_key(arrow(KeyCode::Right)).expect("expand media");
+        app.handle_key(arrow(KeyCode::Right))
+            .expect("step into media");
+        assert_eq!(app.selected_index, 1);
+        app.handle_key(arrow(KeyCode::Right)).expect("expand clips");
+        assert_eq!(
+            rows(&mut app),
+            vec![
+                ("▾ ".to_string(), "media".to_string(), Some(3000)),
+                ("├─ ▾ ".to_string(), "clips".to_string(), Some(2000)),
+                ("│  └─   ".to_string(), "a.mp4".to_string(), Some(2000)),
+                ("└─   ".to_string(), "song.mp3".to_string(), Some(2000)),
+                ("  ".to_string(), "build.log".to_string(), Some(3000)),
+            ]
+        );
+
+        app.handle_key(arrow(KeyCode::Left))
+            .expect("collapse clips");
+        assert_eq!(rows(&mut app).len(), 4);
+        app.handle_key(arrow(KeyCode::Left))
+            .expect("select parent row");
+        assert_eq!(app.selected_index, 0);
+        app.handle_key(arrow(KeyCode::Left))
+            .expect("collapse media");
+        assert_eq!(rows(&mut app).len(), 2);
+
+        app.handle_key(key('e')).expect("back to flat table");
+        assert!(
+            rows(&mut app)
+                .iter()
+                .all(|(prefix, _, max)| prefix.is_empty() && max.is_none())
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn external_actions_queue_launches_for_the_selected_entry() {
+        use crate::external::{ExternalLaunch, shell_quote};
+
+        let temp = TempDir::new().expect("tempdir");
+        let root = temp.path().to_path_buf();
+        let media = root.join("media");
+        fs::create_dir(&media).expect("create media");
+
+        let mut config = test_config(root.clone());
+        config.commands = vec!["ncdu {path}".to_string()];
+        config.keymap = Keymap::default().with_overrides(&[(
+            Action::RunCommand(0),
+            KeyBinding::parse("n").expect("valid key"),
+        )]);
+        let mut app = App::new(config);
+        for summary in [
+            summary(&root, FsEntryKind::Dir, 300),
+            summary(&media, FsEntryKind::Dir, 200),
+            summary(root.join("notes.txt"), FsEntryKind::File, 100),
+        ] {
+            app.upsert_node(summary);
+        }
+
+        app.handle_key(key('n')).expect("run command");
+        assert_eq!(
+            app.pending_launch.take(),
+            Some(ExternalLaunch::Command {
+                line: format!("ncdu {}", shell_quote(&media.to_string_lossy())),
+                dir: media.clone(),
+            })
+        );
+
+        app.handle_key(key('j')).expect("select file");
+        app.handle_key(key('!')).expect("open shell");
+        assert_eq!(
+            app.pending_launch.take(),
+            Some(ExternalLaunch::Shell { dir: root.clone() })
+        );
+        app.handle_key(key('y')).expect("copy path");
+        assert_eq!(
+            app.pending_launch.take(),
+            Some(ExternalLaunch::CopyPath {
+                path: root.join("notes.txt")
+            })
+        );
+
+        fs::remove_dir(&media).expect("remove media");
+        app.handle_key(key('k')).expect("select media");
+        app.handle_key(key('!')).expect("open shell");
+        assert!(app.pending_launch.is_none());
+        assert!(
+            app.build_view_model()
+                .message_line
+                .is_some_and(|line| line.contains("does not exist"))
+        );
+    }
 }
@@ -1,8 +1,16 @@
 use std::path::PathBuf;
+use std::time::Duration;
 
-use clap::{ArgAction, Parser, ValueEnum};
+use clap::{ArgAction, ArgMatches, Parser, ValueEnum};
 
-use crate::model::{ScanOptions, SizeMetric};
+use crate::config_file::ConfigFile;
+use crate::delete::DeleteMode;
+use crate::keymap::{Action, Keymap};
+use crate::model::{ColumnVisibility, DEFAULT_STALE_AFTER, ScanOptions, SizeMetric, SortMode};
+use crate::pattern::parse_pattern_file;
+use crate::report::{ReportFormat, ReportOptions};
+use crate::scanner::default_thread_count;
+use crate::snapshot::{Snapshot, load_snapshot};
 
 #[derive(Debug, Parser)]
 #[command(name = "storagescope")]
@@ -32,13 +40,124 @@ pub struct Cli {
     #[arg(long, value_enum, default_value_t = MetricArg::Allocated)]
     pub metric: MetricArg,
 
-    /// Maximum traversal depth (0 means root only)
+    /// Maximum traversal depth (0 means root only); with --output only the printed depth
     #[arg(long)]
     pub max_depth: Option<usize>,
 
+    /// Number of scanner threads (defaults to the number of CPUs)
+    #[arg(long)]
+    pub threads: Option<usize>,
+
+    /// Skip entries matching this glob (repeatable; `name`, `*.iso`, `/proc`, `**/target`)
+    #[arg(long = "exclude", value_name = "GLOB")]
+    pub exclude: Vec<String>,
+
+    /// Only count files matching this glob (repeatable; directories are still traversed)
+    #[arg(long = "include", value_name = "GLOB")]
+    pub include: Vec<String>,
+
+    /// Read additional exclude globs from a file (one per line, `#` comments)
+    #[arg(long, value_name = "FILE")]
+    pub exclude_from: Option<PathBuf>,
+
+    /// Mark directories whose children were skipped by include/exclude patterns
+    #[arg(long, default_value_t = true, action = ArgAction::Set)]
+    pub report_excluded: bool,
+
     /// Disable delete action in the TUI
     #[arg(long, default_value_t = false)]
     pub no_delete: bool,
+
+    /// How the TUI delete action disposes of entries
+    #[arg(long, value_enum, default_value_t = DeleteModeArg::Permanent)]
+    pub delete_mode: DeleteModeArg,
+
+    /// Print a report to stdout instead of starting the TUI
+    #[arg(long, value_enum)]
+    pub output: Option<OutputArg>,
+
+    /// Limit the report to the N largest entries (per directory for `tree`)
+    #[arg(long, value_name = "N", requires = "output")]
+    pub top: Option<usize>,
+
+    /// Write a snapshot of the scanned tree to FILE after each completed scan
+    #[arg(long, value_name = "FILE")]
+    pub save: Option<PathBuf>,
+
+    /// Browse a previously saved snapshot instead of scanning the filesystem
+    #[arg(long, value_name = "FILE", conflicts_with = "save")]
+    pub load: Option<PathBuf>,
+
+    /// Keep the tree up to date after the scan using inotify (Linux only)
+    #[arg(long, default_value_t = false)]
+    pub watch: bool,
+
+    /// Compare against an older snapshot: per-directory deltas, added/removed markers
+    #[arg(long, value_name = "FILE")]
+    pub diff_against: Option<PathBuf>,
+
+    /// Only list entries not modified within AGE (`180d`, `12w`, `2y`, `36h`); also sets
+    /// the threshold for the untouched-bytes total
+    #[arg(long, value_name = "AGE", value_parser = parse_age)]
+    pub older_than: Option<Duration>,
+
+    /// Read defaults from FILE instead of `$XDG_CONFIG_HOME/storagescope/config.toml`
+    #[arg(long, value_name = "FILE")]
+    pub config: Option<PathBuf>,
+}
+
+/// Parses ages such as `180d`; a bare number is taken as days.
+pub fn parse_age(value: &str) -> Result<Duration, String> {
+    let value = value.trim();
+    let split = value
+        .find(|ch: char| !ch.is_ascii_digit())
+        .unwrap_or(value.len());
+    let (digits, unit) = value.split_at(split);
+    let amount: u64 = digits
+        .parse()
+        .map_err(|_| format!("invalid age `{value}`; expected e.g. 180d"))?;
+    let unit_secs = match unit {
+        "h" => 3_600,
+        "" | "d" => 86_400,
+        "w" => 7 * 86_400,
+        "y" => 365 * 86_400,
+        other => return Err(format!("unknown age unit `{other}`; use h, d, w or y")),
+    };
+    Ok(Duration::from_secs(amount.saturating_mul(unit_secs)))
+}
+
+#[derive(Debug, Clone, Copy, ValueEnum)]
+pub enum OutputArg {
+    Json,
+    Csv,
+    Tree,
+}
+
+impl OutputArg {
+    pub fn into_format(self) -> ReportFormat {
+        match self {
+            Self::Json => ReportFormat::Json,
+            Self::Csv => ReportFormat::Csv,
+            Self::Tree => ReportFormat::Tree,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, ValueEnum)]
+pub enum DeleteModeArg {
+    /// Move entries into the freedesktop trash (restorable)
+    Trash,
+    /// Remove entries from disk immediately
+    Permanent,
+}
+
+impl DeleteModeArg {
+    pub fn into_mode(self) -> DeleteMode {
+        match self {
+            Self::Trash => DeleteMode::Trash,
+            Self::Permanent => DeleteMode::Permanent,
+        }
+    }
 }
 
 #[derive(Debug, Clone, Copy, ValueEnum)]
@@ -62,11 +181,84 @@ pub struct Config {
     pub scan_options: ScanOptions,
     pub initial_metric: SizeMetric,
     pub no_delete: bool,
+    pub delete_mode: DeleteMode,
+    /// Headless report settings; `None` starts the interactive TUI.
+    pub report: Option<ReportOptions>,
+    pub save_snapshot: Option<PathBuf>,
+    /// Loaded snapshot to browse; the filesystem is not scanned or modified.
+    pub snapshot: Option<Snapshot>,
+    /// Older snapshot the current tree is compared against.
+    pub diff_baseline: Option<Snapshot>,
+    /// Follow filesystem changes after each completed scan.
+    pub watch: bool,
+    /// Hide table entries modified more recently than this.
+    pub older_than: Option<Duration>,
+    pub initial_sort: SortMode,
+    pub initial_columns: ColumnVisibility,
+    pub keymap: Keymap,
+    /// User command templates; `Action::RunCommand(n)` runs the n-th.
+    pub commands: Vec<String>,
 }
 
 impl Config {
-    pub fn from_cli(cli: Cli) -> std::io::Result<Self> {
+    /// Builds the configuration from parsed flags, taking defaults for flags
+    /// missing from `matches` out of the config file.
+    pub fn from_cli(mut cli: Cli, matches: &ArgMatches) -> std::io::Result<Self> {
+        let file = ConfigFile::load(cli.config.as_deref()).map_err(std::io::Error::other)?;
+        file.apply_to(&mut cli, matches);
+        let initial_sort = file.sort.unwrap_or(SortMode::SizeDesc);
+        let mut key_bindings = file.key_bindings.clone();
+        key_bindings.extend(
+            file.commands
+                .iter()
+                .enumerate()
+                .map(|(index, (key, _))| (Action::RunCommand(index), *key)),
+        );
+        let keymap = Keymap::default().with_overrides(&key_bindings);
+        let commands: Vec<String> = file
+            .commands
+            .iter()
+            .map(|(_, template)| template.clone())
+            .collect();
+
+        let report = cli.output.map(|output| ReportOptions {
+            format: output.into_format(),
+            top: cli.top,
+        });
+
+        let diff_baseline = cli
+            .diff_against
+            .as_deref()
+            .map(load_snapshot)
+            .transpose()
+            .map_err(std::io::Error::other)?;
+
+        if let Some(path) = &cli.load {
+            let snapshot = load_snapshot(path).map_err(std::io::Error::other)?;
+            return Ok(Self {
+                startup_root: snapshot.root().to_path_buf(),
+                scan_options: snapshot.scan_options.clone(),
+                initial_metric: cli.metric.into_metric(),
+                no_delete: true,
+                delete_mode: cli.delete_mode.into_mode(),
+                report,
+                save_snapshot: None,
+                snapshot: Some(snapshot),
+                diff_baseline,
+                watch: false,
+                older_than: cli.older_than,
+                initial_sort,
+                initial_columns: file.columns,
+                keymap,
+                commands,
+            });
+        }
+
         let startup_root = std::fs::canonicalize(cli.path)?;
+        let mut exclude_patterns = cli.exclude;
+        if let Some(path) = &cli.exclude_from {
+            exclude_patterns.extend(parse_pattern_file(&std::fs::read_to_string(path)?));
+        }
         Ok(Self {
             startup_root: startup_root.clone(),
             scan_options: ScanOptions {
@@ -76,9 +268,42 @@ impl Config {
                 show_hidden: cli.show_hidden,
                 show_files: cli.show_files,
                 max_depth: cli.max_depth,
+                threads: cli.threads.unwrap_or_else(default_thread_count).max(1),
+                exclude_patterns,
+                include_patterns: cli.include,
+                report_excluded: cli.report_excluded,
+                stale_after: cli.older_than.unwrap_or(DEFAULT_STALE_AFTER),
             },
             initial_metric: cli.metric.into_metric(),
             no_delete: cli.no_delete,
+            delete_mode: cli.delete_mode.into_mode(),
+            report,
+            save_snapshot: cli.save,
+            snapshot: None,
+            diff_baseline,
+            watch: cli.watch,
+            older_than: cli.older_than,
+            initial_sort,
+            initial_columns: file.columns,
+            keymap,
+            commands,
         })
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::parse_age;
+
+    #[test]
+    fn parses_ages_with_units() {
+        assert_eq!(parse_age("180d"), Ok(Duration::from_secs(180 * 86_400)));
+        assert_eq!(parse_age("2w"), Ok(Duration::from_secs(14 * 86_400)));
+        assert_eq!(parse_age("36h"), Ok(Duration::from_secs(36 * 3_600)));
+        assert_eq!(parse_age("30"), Ok(Duration::from_secs(30 * 86_400)));
+        assert!(parse_age("6mo").is_err());
+        assert!(parse_age("d").is_err());
+    }
+}
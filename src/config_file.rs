@@ -0,0 +1,590 @@
+//! Persistent preferences read from `$XDG_CONFIG_HOME/storagescope/config.toml`:
+//! defaults for command-line flags, the initial sort order and columns, and
+//! key bindings.
+//!
+//! The file is read with the `toml` crate; unknown keys are rejected so typos
+//! do not go unnoticed, and errors name the line they were found on.
+
+use std::fmt;
+use std::fs;
+use std::marker::PhantomData;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use clap::ArgMatches;
+use clap::ValueEnum;
+use clap::parser::ValueSource;
+use serde::Deserialize;
+use serde::de::{self, Deserializer, IntoDeserializer, MapAccess, SeqAccess, Visitor};
+
+use crate::cli::{Cli, DeleteModeArg, MetricArg, OutputArg, parse_age};
+use crate::errors::AppError;
+use crate::keymap::{Action, KeyBinding};
+use crate::model::{ColumnVisibility, SortMode, TableColumn};
+
+const CONFIG_DIR_NAME: &str = "storagescope";
+const CONFIG_FILE_NAME: &str = "config.toml";
+
+/// `$XDG_CONFIG_HOME/storagescope/config.toml`, falling back to `~/.config`.
+pub fn default_config_path() -> Option<PathBuf> {
+    let config_home = std::env::var_os("XDG_CONFIG_HOME")
+        .map(PathBuf::from)
+        .filter(|path| path.is_absolute())
+        .or_else(|| std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")))?;
+    Some(config_home.join(CONFIG_DIR_NAME).join(CONFIG_FILE_NAME))
+}
+
+/// Settings from the config file; `None` and empty fields leave the built-in
+/// defaults alone. Top-level keys are named after the long command-line flags.
+#[derive(Debug, Clone)]
+pub struct ConfigFile {
+    pub path: Option<PathBuf>,
+    pub one_file_system: Option<bool>,
+    pub follow_symlinks: Option<bool>,
+    pub show_hidden: Option<bool>,
+    pub show_files: Option<bool>,
+    pub metric: Option<MetricArg>,
+    pub max_depth: Option<usize>,
+    pub threads: Option<usize>,
+    pub exclude: Vec<String>,
+    pub include: Vec<String>,
+    pub exclude_from: Option<PathBuf>,
+    pub report_excluded: Option<bool>,
+    pub no_delete: Option<bool>,
+    pub delete_mode: Option<DeleteModeArg>,
+    pub output: Option<OutputArg>,
+    pub top: Option<usize>,
+    pub save: Option<PathBuf>,
+    pub load: Option<PathBuf>,
+    pub watch: Option<bool>,
+    pub diff_against: Option<PathBuf>,
+    pub older_than: Option<Duration>,
+    /// Initial table order (`sort`).
+    pub sort: Option<SortMode>,
+    /// Initially visible table columns (`[columns]`).
+    pub columns: ColumnVisibility,
+    /// `(action, key)` pairs from the `[keys]` table, in file order.
+    pub key_bindings: Vec<(Action, KeyBinding)>,
+    /// Command templates from the `[commands]` table, keyed by the key that runs them.
+    pub commands: Vec<(KeyBinding, String)>,
+}
+
+impl Default for ConfigFile {
+    fn default() -> Self {
+        Self {
+            path: None,
+            one_file_system: None,
+            follow_symlinks: None,
+            show_hidden: None,
+            show_files: None,
+            metric: None,
+            max_depth: None,
+            threads: None,
+            exclude: Vec::new(),
+            include: Vec::new(),
+            exclude_from: None,
+            report_excluded: None,
+            no_delete: None,
+            delete_mode: None,
+            output: None,
+            top: None,
+            save: None,
+            load: None,
+            watch: None,
+            diff_against: None,
+            older_than: None,
+            sort: None,
+            columns: ColumnVisibility::all_visible(),
+            key_bindings: Vec::new(),
+            commands: Vec::new(),
+        }
+    }
+}
+
+impl ConfigFile {
+    /// Reads `explicit`, or the default location when no path is given. A
+    /// missing default file is not an error; a missing explicit one is.
+    pub fn load(explicit: Option<&Path>) -> Result<Self, AppError> {
+        let path = match explicit {
+            Some(path) => path.to_path_buf(),
+            None => match default_config_path() {
+                Some(path) if path.is_file() => path,
+                _ => return Ok(Self::default()),
+            },
+        };
+        let text = fs::read_to_string(&path).map_err(|error| AppError::Config {
+            path: path.clone(),
+            reason: error.to_string(),
+        })?;
+        parse_config(&text).map_err(|reason| AppError::Config { path, reason })
+    }
+
+    /// Fills every flag `matches` did not receive on the command line.
+    /// Exclude and include globs from the file are kept in front of the ones
+    /// given on the command line.
+    pub fn apply_to(&self, cli: &mut Cli, matches: &ArgMatches) {
+        let given = |id: &str| matches.value_source(id) == Some(ValueSource::CommandLine);
+        fn fill<T: Clone>(target: &mut T, value: &Option<T>, given: bool) {
+            if let Some(value) = value
+                && !given
+            {
+                *target = value.clone();
+            }
+        }
+        fn fill_option<T: Clone>(target: &mut Option<T>, value: &Option<T>, given: bool) {
+            if value.is_some() && !given {
+                target.clone_from(value);
+            }
+        }
+
+        fill(&mut cli.path, &self.path, given("path"));
+        fill(
+            &mut cli.one_file_system,
+            &self.one_file_system,
+            given("one_file_system"),
+        );
+        fill(
+            &mut cli.follow_symlinks,
+            &self.follow_symlinks,
+            given("follow_symlinks"),
+        );
+        fill(
+            &mut cli.show_hidden,
+            &self.show_hidden,
+            given("show_hidden"),
+        );
+        fill(&mut cli.show_files, &self.show_files, given("show_files"));
+        fill(&mut cli.metric, &self.metric, given("metric"));
+        fill_option(&mut cli.max_depth, &self.max_depth, given("max_depth"));
+        fill_option(&mut cli.threads, &self.threads, given("threads"));
+        fill_option(
+            &mut cli.exclude_from,
+            &self.exclude_from,
+            given("exclude_from"),
+        );
+        fill(
+            &mut cli.report_excluded,
+            &self.report_excluded,
+            given("report_excluded"),
+        );
+        fill(&mut cli.no_delete, &self.no_delete, given("no_delete"));
+        fill(
+            &mut cli.delete_mode,
+            &self.delete_mode,
+            given("delete_mode"),
+        );
+        fill_option(&mut cli.output, &self.output, given("output"));
+        fill_option(&mut cli.top, &self.top, given("top"));
+        fill_option(&mut cli.save, &self.save, given("save"));
+        fill_option(&mut cli.load, &self.load, given("load"));
+        fill(&mut cli.watch, &self.watch, given("watch"));
+        fill_option(
+            &mut cli.diff_against,
+            &self.diff_against,
+            given("diff_against"),
+        );
+        fill_option(&mut cli.older_than, &self.older_than, given("older_than"));
+
+        cli.exclude.splice(0..0, self.exclude.iter().cloned());
+        cli.include.splice(0..0, self.include.iter().cloned());
+    }
+}
+
+/// Parses a config file; errors name the offending line.
+pub fn parse_config(text: &str) -> Result<ConfigFile, String> {
+    let raw: RawConfig = toml::from_str(text).map_err(|error| describe(text, &error))?;
+
+    let mut columns = ColumnVisibility::all_visible();
+    for (Parsed(column), visible) in raw.columns.0 {
+        columns.set_visible(column, visible);
+    }
+    if columns.visible_count() == 0 {
+        return Err("[columns] must leave at least one column visible".to_string());
+    }
+    let key_bindings = raw
+        .keys
+        .0
+        .into_iter()
+        .flat_map(|(Parsed(action), keys)| keys.0.into_iter().map(move |Parsed(key)| (action, key)))
+        .collect();
+    let commands = raw
+        .commands
+        .0
+        .into_iter()
+        .map(|(Parsed(key), Parsed(CommandTemplate(template)))| (key, template))
+        .collect();
+
+    Ok(ConfigFile {
+        path: parsed(raw.path),
+        one_file_system: raw.one_file_system,
+        follow_symlinks: raw.follow_symlinks,
+        show_hidden: raw.show_hidden,
+        show_files: raw.show_files,
+        metric: parsed(raw.metric),
+        max_depth: raw.max_depth,
+        threads: raw.threads,
+        exclude: raw.exclude.0,
+        include: raw.include.0,
+        exclude_from: parsed(raw.exclude_from),
+        report_excluded: raw.report_excluded,
+        no_delete: raw.no_delete,
+        delete_mode: parsed(raw.delete_mode),
+        output: parsed(raw.output),
+        top: raw.top,
+        save: parsed(raw.save),
+        load: parsed(raw.load),
+        watch: raw.watch,
+        diff_against: parsed(raw.diff_against),
+        older_than: parsed(raw.older_than),
+        sort: parsed(raw.sort),
+        columns,
+        key_bindings,
+        commands,
+    })
+}
+
+/// `line N: message` for errors that point into the file.
+fn describe(text: &str, error: &toml::de::Error) -> String {
+    let message = error.message().trim_end();
+    match error.span() {
+        Some(span) => {
+            let line = text[..span.start].matches('\n').count() + 1;
+            format!("line {line}: {message}")
+        }
+        None => message.to_string(),
+    }
+}
+
+/// The file as written; values that need more than a type check arrive as
+/// [`Parsed`] so their errors carry the line they came from.
+#[derive(Deserialize, Default)]
+#[serde(default, deny_unknown_fields, rename_all = "kebab-case")]
+struct RawConfig {
+    path: Option<Parsed<PathBuf>>,
+    one_file_system: Option<bool>,
+    follow_symlinks: Option<bool>,
+    show_hidden: Option<bool>,
+    show_files: Option<bool>,
+    metric: Option<Parsed<MetricArg>>,
+    max_depth: Option<usize>,
+    threads: Option<usize>,
+    exclude: OneOrMany<String>,
+    include: OneOrMany<String>,
+    exclude_from: Option<Parsed<PathBuf>>,
+    report_excluded: Option<bool>,
+    no_delete: Option<bool>,
+    delete_mode: Option<Parsed<DeleteModeArg>>,
+    output: Option<Parsed<OutputArg>>,
+    top: Option<usize>,
+    save: Option<Parsed<PathBuf>>,
+    load: Option<Parsed<PathBuf>>,
+    watch: Option<bool>,
+    diff_against: Option<Parsed<PathBuf>>,
+    older_than: Option<Parsed<Duration>>,
+    sort: Option<Parsed<SortMode>>,
+    columns: Pairs<Parsed<TableColumn>, bool>,
+    keys: Pairs<Parsed<Action>, OneOrMany<Parsed<KeyBinding>>>,
+    commands: Pairs<Parsed<KeyBinding>, Parsed<CommandTemplate>>,
+}
+
+/// A setting written as a string and parsed on the way in.
+struct Parsed<T>(T);
+
+fn parsed<T>(setting: Option<Parsed<T>>) -> Option<T> {
+    setting.map(|Parsed(value)| value)
+}
+
+trait FromSetting: Sized {
+    fn from_setting(text: &str) -> Result<Self, String>;
+}
+
+impl<'de, T: FromSetting> Deserialize<'de> for Parsed<T> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let text = String::deserialize(deserializer)?;
+        T::from_setting(&text)
+            .map(Parsed)
+            .map_err(de::Error::custom)
+    }
+}
+
+/// Paths starting with `~/` are taken relative to `$HOME`.
+impl FromSetting for PathBuf {
+    fn from_setting(text: &str) -> Result<Self, String> {
+        if let Some(rest) = text.strip_prefix("~/")
+            && let Some(home) = std::env::var_os("HOME")
+        {
+            return Ok(PathBuf::from(home).join(rest));
+        }
+        Ok(PathBuf::from(text))
+    }
+}
+
+impl FromSetting for MetricArg {
+    fn from_setting(text: &str) -> Result<Self, String> {
+        Self::from_str(text, true)
+    }
+}
+
+impl FromSetting for DeleteModeArg {
+    fn from_setting(text: &str) -> Result<Self, String> {
+        Self::from_str(text, true)
+    }
+}
+
+impl FromSetting for OutputArg {
+    fn from_setting(text: &str) -> Result<Self, String> {
+        Self::from_str(text, true)
+    }
+}
+
+impl FromSetting for Duration {
+    fn from_setting(text: &str) -> Result<Self, String> {
+        parse_age(text)
+    }
+}
+
+impl FromSetting for SortMode {
+    fn from_setting(text: &str) -> Result<Self, String> {
+        Self::from_name(text).ok_or_else(|| {
+            format!("unknown sort `{text}`; use size-desc, size-asc, name, oldest or growth")
+        })
+    }
+}
+
+impl FromSetting for TableColumn {
+    fn from_setting(text: &str) -> Result<Self, String> {
+        Self::from_name(text).ok_or_else(|| format!("unknown column `{text}`"))
+    }
+}
+
+impl FromSetting for Action {
+    fn from_setting(text: &str) -> Result<Self, String> {
+        Self::from_name(text).ok_or_else(|| format!("unknown action `{text}`"))
+    }
+}
+
+impl FromSetting for KeyBinding {
+    fn from_setting(text: &str) -> Result<Self, String> {
+        Self::parse(text)
+    }
+}
+
+/// A `[commands]` template; blank ones are rejected.
+struct CommandTemplate(String);
+
+impl FromSetting for CommandTemplate {
+    fn from_setting(text: &str) -> Result<Self, String> {
+        if text.trim().is_empty() {
+            return Err("empty command".to_string());
+        }
+        Ok(Self(text.to_string()))
+    }
+}
+
+/// A single value or an array of them.
+struct OneOrMany<T>(Vec<T>);
+
+impl<T> Default for OneOrMany<T> {
+    fn default() -> Self {
+        Self(Vec::new())
+    }
+}
+
+impl<'de, T: Deserialize<'de>> Deserialize<'de> for OneOrMany<T> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct OneOrManyVisitor<T>(PhantomData<T>);
+
+        impl<'de, T: Deserialize<'de>> Visitor<'de> for OneOrManyVisitor<T> {
+            type Value = OneOrMany<T>;
+
+            fn expecting(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+                formatter.write_str("a string or an array of strings")
+            }
+
+            fn visit_str<E: de::Error>(self, text: &str) -> Result<Self::Value, E> {
+                T::deserialize(text.into_deserializer()).map(|item| OneOrMany(vec![item]))
+            }
+
+            fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Self::Value, A::Error> {
+                let mut items = Vec::new();
+                while let Some(item) = seq.next_element()? {
+                    items.push(item);
+                }
+                Ok(OneOrMany(items))
+            }
+        }
+
+        deserializer.deserialize_any(OneOrManyVisitor(PhantomData))
+    }
+}
+
+/// A table's entries in file order.
+struct Pairs<K, V>(Vec<(K, V)>);
+
+impl<K, V> Default for Pairs<K, V> {
+    fn default() -> Self {
+        Self(Vec::new())
+    }
+}
+
+impl<'de, K: Deserialize<'de>, V: Deserialize<'de>> Deserialize<'de> for Pairs<K, V> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct PairsVisitor<K, V>(PhantomData<(K, V)>);
+
+        impl<'de, K: Deserialize<'de>, V: Deserialize<'de>> Visitor<'de> for PairsVisitor<K, V> {
+            type Value = Pairs<K, V>;
+
+            fn expecting(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+                formatter.write_str("a table")
+            }
+
+            fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Self::Value, A::Error> {
+                let mut entries = Vec::new();
+                while let Some(entry) = map.next_entry()? {
+                    entries.push(entry);
+                }
+                Ok(Pairs(entries))
+            }
+        }
+
+        deserializer.deserialize_map(PairsVisitor(PhantomData))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use clap::{CommandFactory, FromArgMatches};
+
+    use super::*;
+
+    #[test]
+    fn parses_flags_sort_columns_and_keys() {
+        let config = parse_config(
+            r#"
+# defaults for every run
+show-files = true
+metric = "apparent"
+threads = 4
+older-than = "26w"
+sort = "name"
+exclude = [
+    "**/target",   # build output
+    '/proc',
+]
+
+[columns]
+path = false
+modified = false
+
+[keys]
+quit = "Q"
+move-down = ["j", "Down"]
+
+[commands]
+n = "ncdu {path}"
+"Ctrl+g" = "git -C {dir} status"
+"#,
+        )
+        .expect("valid config");
+
+        assert_eq!(config.show_files, Some(true));
+        assert!(matches!(config.metric, Some(MetricArg::Apparent)));
+        assert_eq!(config.threads, Some(4));
+        assert_eq!(config.older_than, Some(Duration::from_secs(182 * 86_400)));
+        assert_eq!(config.sort, Some(SortMode::Name));
+        assert_eq!(config.exclude, vec!["**/target", "/proc"]);
+        assert!(!config.columns.path && !config.columns.modified && config.columns.name);
+        let key = |spec| KeyBinding::parse(spec).expect("valid key");
+        assert_eq!(
+            config.key_bindings,
+            vec![
+                (Action::Quit, key("Q")),
+                (Action::MoveDown, key("j")),
+                (Action::MoveDown, key("Down")),
+            ]
+        );
+        assert_eq!(
+            config.commands,
+            vec![
+                (key("n"), "ncdu {path}".to_string()),
+                (key("Ctrl+g"), "git -C {dir} status".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn reports_the_offending_line() {
+        let error = parse_config("show-files = true\nshow-file = true\n").unwrap_err();
+        assert!(
+            error.starts_with("line 2: unknown field `show-file`"),
+            "{error}"
+        );
+
+        let error = parse_config("threads = \"four\"").unwrap_err();
+        assert_eq!(
+            error,
+            "line 1: invalid type: string \"four\", expected usize"
+        );
+
+        let error = parse_config("exclude = [\"a\",\n").unwrap_err();
+        assert!(error.starts_with("line 1: unclosed array"), "{error}");
+
+        let error =
+            parse_config("[keys]\nquit = \"q\"\nmove-down = [\"j\", \"Hyper+j\"]\n").unwrap_err();
+        assert_eq!(error, "line 3: unknown modifier `Hyper` in `Hyper+j`");
+
+        let error = parse_config("\n[commands]\nx = \" \"\n").unwrap_err();
+        assert_eq!(error, "line 3: empty command");
+
+        let error = parse_config("[columns]\nname = false\nkind = false\nsize = false\nmodified = false\nrelative = false\npath = false\n").unwrap_err();
+        assert!(error.contains("at least one column"));
+    }
+
+    #[test]
+    fn accepts_quoted_keys_escapes_and_inline_tables() {
+        let config = parse_config(
+            r#"
+exclude = "*.iso" # a single glob
+keys = { quit = "\u0051" }
+commands = { "=" = "du -sh {path}", "Ctrl+=" = "echo \"a = b\" # not a comment" }
+"#,
+        )
+        .expect("valid config");
+
+        let key = |spec| KeyBinding::parse(spec).expect("valid key");
+        assert_eq!(config.exclude, vec!["*.iso"]);
+        assert_eq!(config.key_bindings, vec![(Action::Quit, key("Q"))]);
+        assert_eq!(
+            config.commands,
+            vec![
+                (key("="), "du -sh {path}".to_string()),
+                (key("Ctrl+="), "echo \"a = b\" # not a comment".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn command_line_flags_take_precedence() {
+        let config = parse_config(
+            "show-files = true\nshow-hidden = false\nthreads = 3\nexclude = [\"*.iso\"]\n",
+        )
+        .expect("valid config");
+        let matches = Cli::command()
+            .try_get_matches_from([
+                "storagescope",
+                "--show-files",
+                "false",
+                "--exclude",
+                "cache",
+            ])
+            .expect("valid arguments");
+        let mut cli = Cli::from_arg_matches(&matches).expect("parsed arguments");
+
+        config.apply_to(&mut cli, &matches);
+
+        assert!(!cli.show_files);
+        assert!(!cli.show_hidden);
+        assert_eq!(cli.threads, Some(3));
+        assert_eq!(cli.exclude, vec!["*.iso", "cache"]);
+    }
+}
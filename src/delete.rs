@@ -2,8 +2,27 @@ use std::fs;
 use std::path::{Path, PathBuf};
 
 use crate::errors::AppError;
+use crate::trash::move_to_trash;
+
+/// How a confirmed delete disposes of the target.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum DeleteMode {
+    /// Move into the freedesktop trash so the entry can be restored.
+    Trash,
+    /// Remove from disk immediately.
+    Permanent,
+}
+
+impl DeleteMode {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Trash => "trash",
+            Self::Permanent => "permanent",
+        }
+    }
+}
 
-pub fn delete_target(target: &Path, scan_root: &Path) -> Result<(), AppError> {
+pub fn delete_target(target: &Path, scan_root: &Path, mode: DeleteMode) -> Result<(), AppError> {
     let root_canonical = fs::canonicalize(scan_root).map_err(|error| AppError::Delete {
         path: scan_root.to_path_buf(),
         reason: format!("cannot resolve scan root: {error}"),
@@ -21,10 +40,18 @@ pub fn delete_target(target: &Path, scan_root: &Path) -> Result<(), AppError> {
 
     // There is still a small race window between the final check and the delete call.
     // We prefer operation-first deletes here to reduce metadata/check sequencing gaps.
-    remove_target(target).map_err(|error| AppError::Delete {
-        path: target.to_path_buf(),
-        reason: format!("delete failed (path may have changed concurrently): {error}"),
-    })
+    match mode {
+        DeleteMode::Permanent => remove_target(target).map_err(|error| AppError::Delete {
+            path: target.to_path_buf(),
+            reason: format!("delete failed (path may have changed concurrently): {error}"),
+        }),
+        DeleteMode::Trash => move_to_trash(target)
+            .map(|_| ())
+            .map_err(|error| AppError::Delete {
+                path: target.to_path_buf(),
+                reason: format!("move to trash failed: {error}"),
+            }),
+    }
 }
 
 fn canonical_target_within_root(target: &Path, root_canonical: &Path) -> Result<PathBuf, AppError> {
@@ -75,7 +102,7 @@ mod tests {
 
     use tempfile::TempDir;
 
-    use super::delete_target;
+    use super::{DeleteMode, delete_target};
 
     #[cfg(unix)]
     fn create_file_symlink(original: &Path, link: &Path) -> std::io::Result<()> {
@@ -92,7 +119,7 @@ mod tests {
         let temp = TempDir::new().expect("temp dir");
         let root = temp.path();
 
-        let error = delete_target(root, root).expect_err("must fail");
+        let error = delete_target(root, root, DeleteMode::Permanent).expect_err("must fail");
         assert!(
             error
                 .to_string()
@@ -107,7 +134,7 @@ mod tests {
         let target = root.join("delete-me.txt");
         fs::write(&target, "x").expect("write");
 
-        delete_target(&target, root).expect("delete should succeed");
+        delete_target(&target, root, DeleteMode::Permanent).expect("delete should succeed");
         assert!(!target.exists());
     }
 
@@ -118,7 +145,8 @@ mod tests {
         let outside_file = outside.path().join("outside.txt");
         fs::write(&outside_file, "x").expect("write outside");
 
-        let error = delete_target(&outside_file, root.path()).expect_err("must fail");
+        let error = delete_target(&outside_file, root.path(), DeleteMode::Permanent)
+            .expect_err("must fail");
         assert!(
             error
                 .to_string()
@@ -149,7 +177,8 @@ mod tests {
             }
         }
 
-        let error = delete_target(&link, root.path()).expect_err("must fail");
+        let error =
+            delete_target(&link, root.path(), DeleteMode::Permanent).expect_err("must fail");
         assert!(
             error
                 .to_string()
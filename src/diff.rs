@@ -0,0 +1,200 @@
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+use crate::model::{NodeStats, SizeMetric};
+use crate::snapshot::Snapshot;
+use crate::store::NodeStore;
+
+/// How a row compares to the baseline snapshot.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum DiffStatus {
+    Added,
+    Removed,
+    Present,
+}
+
+/// An older snapshot that the current tree is compared against.
+#[derive(Debug, Clone)]
+pub struct Baseline {
+    pub host: String,
+    pub created_at: SystemTime,
+    nodes: NodeStore,
+}
+
+impl Baseline {
+    /// Re-roots the snapshot onto `root` so trees scanned under different mount
+    /// points can still be compared path by path.
+    pub fn from_snapshot(snapshot: Snapshot, root: &Path) -> Self {
+        let snapshot_root = snapshot.root().to_path_buf();
+        let mut nodes = NodeStore::new();
+
+        for mut node in snapshot.nodes {
+            let Ok(relative) = node.path.strip_prefix(&snapshot_root) else {
+                continue;
+            };
+            let path = if relative.as_os_str().is_empty() {
+                root.to_path_buf()
+            } else {
+                root.join(relative)
+            };
+            node.path = path;
+            nodes.upsert(node);
+        }
+
+        Self {
+            host: snapshot.host,
+            created_at: snapshot.created_at,
+            nodes,
+        }
+    }
+
+    pub fn node(&self, path: &Path) -> Option<&NodeStats> {
+        self.nodes.get(path)
+    }
+
+    pub fn children(&self, path: &Path) -> Vec<PathBuf> {
+        self.nodes.child_paths(path)
+    }
+
+    pub fn status(&self, path: &Path, current: Option<&NodeStats>) -> Option<DiffStatus> {
+        match (current.is_some(), self.nodes.contains(path)) {
+            (true, false) => Some(DiffStatus::Added),
+            (false, true) => Some(DiffStatus::Removed),
+            (true, true) => Some(DiffStatus::Present),
+            (false, false) => None,
+        }
+    }
+
+    /// Growth in bytes since the baseline; missing nodes count as zero.
+    ///
+    /// Compares naive totals, which count every hardlink in full: which directory the
+    /// deduplicated bytes of a shared inode land in depends on the order a parallel scan
+    /// visits its links, so comparing those would report growth that is not there.
+    pub fn delta(&self, path: &Path, current: Option<&NodeStats>, metric: SizeMetric) -> i64 {
+        let now = current
+            .map(|node| node.naive_metric_bytes(metric))
+            .unwrap_or(0);
+        let before = self
+            .nodes
+            .get(path)
+            .map(|node| node.naive_metric_bytes(metric))
+            .unwrap_or(0);
+        signed_delta(now, before)
+    }
+}
+
+fn signed_delta(now: u64, before: u64) -> i64 {
+    if now >= before {
+        i64::try_from(now - before).unwrap_or(i64::MAX)
+    } else {
+        -i64::try_from(before - now).unwrap_or(i64::MAX)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::SystemTime;
+
+    use super::*;
+    use crate::model::{AgeSummary, DEFAULT_STALE_AFTER, FsEntryKind, NodeSummary, ScanOptions};
+
+    fn node(path: &str, bytes: u64) -> NodeSummary {
+        NodeSummary {
+            path: PathBuf::from(path),
+            kind: FsEntryKind::Dir,
+            apparent_bytes: bytes,
+            allocated_bytes: bytes,
+            naive_apparent_bytes: bytes,
+            naive_allocated_bytes: bytes,
+            children_count: 0,
+            excluded_entries: 0,
+            age: AgeSummary::default(),
+            owner: None,
+            is_complete: true,
+            last_updated: SystemTime::now(),
+        }
+    }
+
+    fn baseline() -> Baseline {
+        let snapshot = Snapshot {
+            created_at: SystemTime::now(),
+            host: "nas".to_string(),
+            scan_options: ScanOptions {
+                root: PathBuf::from("/mnt/old"),
+                one_file_system: true,
+                follow_symlinks: false,
+                show_hidden: true,
+                show_files: false,
+                max_depth: None,
+                threads: 1,
+                exclude_patterns: Vec::new(),
+                include_patterns: Vec::new(),
+                report_excluded: true,
+                stale_after: DEFAULT_STALE_AFTER,
+            },
+            nodes: vec![
+                node("/mnt/old", 1000),
+                node("/mnt/old/videos", 600),
+                node("/mnt/old/tmp", 400),
+            ],
+        };
+        Baseline::from_snapshot(snapshot, Path::new("/data"))
+    }
+
+    #[test]
+    fn rebases_snapshot_paths_onto_current_root() {
+        let baseline = baseline();
+        assert!(baseline.node(Path::new("/data/videos")).is_some());
+        assert_eq!(baseline.children(Path::new("/data")).len(), 2);
+    }
+
+    #[test]
+    fn classifies_added_removed_and_changed() {
+        let baseline = baseline();
+        let grown = node("/data/videos", 1600).stats();
+        let fresh = node("/data/downloads", 50).stats();
+
+        assert_eq!(
+            baseline.status(Path::new("/data/videos"), Some(&grown)),
+            Some(DiffStatus::Present)
+        );
+        assert_eq!(
+            baseline.delta(
+                Path::new("/data/videos"),
+                Some(&grown),
+                SizeMetric::Allocated
+            ),
+            1000
+        );
+        assert_eq!(
+            baseline.status(Path::new("/data/downloads"), Some(&fresh)),
+            Some(DiffStatus::Added)
+        );
+        assert_eq!(
+            baseline.status(Path::new("/data/tmp"), None),
+            Some(DiffStatus::Removed)
+        );
+        assert_eq!(
+            baseline.delta(Path::new("/data/tmp"), None, SizeMetric::Allocated),
+            -400
+        );
+    }
+
+    #[test]
+    fn delta_ignores_where_hardlinked_bytes_were_attributed() {
+        let baseline = baseline();
+        // The same 600 bytes, but this scan credited a hardlink to them elsewhere.
+        let mut videos = node("/data/videos", 600);
+        videos.apparent_bytes = 0;
+        videos.allocated_bytes = 0;
+
+        assert_eq!(
+            baseline.delta(
+                Path::new("/data/videos"),
+                Some(&videos.stats()),
+                SizeMetric::Allocated
+            ),
+            0
+        );
+    }
+}
@@ -0,0 +1,291 @@
+//! Duplicate detection over scanned files: candidates are grouped by size, then by a
+//! hash of their first block, then by a hash of their whole content, on a background
+//! thread so the TUI stays responsive while large files are read.
+
+use std::collections::HashMap;
+use std::collections::hash_map::RandomState;
+use std::fs::File;
+use std::hash::{BuildHasher, Hasher};
+use std::io::{self, Read};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::thread::{self, JoinHandle};
+
+use crossbeam_channel::{Receiver, Sender, unbounded};
+
+use crate::model::{NodeSummary, SizeMetric};
+
+/// Bytes read for the partial hash that weeds out same-sized files cheaply.
+const PARTIAL_HASH_BYTES: usize = 16 * 1024;
+const READ_BUFFER_LEN: usize = 128 * 1024;
+const PROGRESS_EMIT_EVERY: u64 = 64;
+
+/// Files with identical content; every copy beyond the first is wasted space.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct DuplicateGroup {
+    pub apparent_bytes: u64,
+    pub allocated_bytes: u64,
+    pub paths: Vec<PathBuf>,
+}
+
+impl DuplicateGroup {
+    pub fn wasted_bytes(&self, metric: SizeMetric) -> u64 {
+        let size = match metric {
+            SizeMetric::Allocated => self.allocated_bytes,
+            SizeMetric::Apparent => self.apparent_bytes,
+        };
+        size.saturating_mul(self.paths.len().saturating_sub(1) as u64)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub enum DuplicateEvent {
+    Progress { hashed_files: u64, total_files: u64 },
+    Warning { path: PathBuf, message: String },
+    Complete(Vec<DuplicateGroup>),
+    Cancelled,
+}
+
+pub struct DuplicateSession {
+    receiver: Receiver<DuplicateEvent>,
+    cancel: Arc<AtomicBool>,
+    join: Option<JoinHandle<()>>,
+}
+
+impl DuplicateSession {
+    pub fn receiver(&self) -> &Receiver<DuplicateEvent> {
+        &self.receiver
+    }
+
+    pub fn stop(&mut self) {
+        self.cancel.store(true, Ordering::Relaxed);
+        if let Some(join) = self.join.take() {
+            let _ = join.join();
+        }
+    }
+}
+
+impl Drop for DuplicateSession {
+    fn drop(&mut self) {
+        self.stop();
+    }
+}
+
+/// Starts hashing `files` in the background. Only regular files with unique bytes
+/// are considered, so extra hardlinks to one inode are never reported as copies.
+pub fn start_duplicate_search(files: Vec<NodeSummary>) -> DuplicateSession {
+    let (tx, rx) = unbounded();
+    let cancel = Arc::new(AtomicBool::new(false));
+    let cancel_for_thread = Arc::clone(&cancel);
+
+    let join = thread::spawn(move || {
+        let event = match find_duplicates(files, &tx, &cancel_for_thread) {
+            Some(groups) => DuplicateEvent::Complete(groups),
+            None => DuplicateEvent::Cancelled,
+        };
+        let _ = tx.send(event);
+    });
+
+    DuplicateSession {
+        receiver: rx,
+        cancel,
+        join: Some(join),
+    }
+}
+
+/// Runs all three grouping passes; returns `None` when cancelled. Groups are
+/// ordered by wasted allocated bytes, largest first.
+pub fn find_duplicates(
+    files: Vec<NodeSummary>,
+    tx: &Sender<DuplicateEvent>,
+    cancel: &AtomicBool,
+) -> Option<Vec<DuplicateGroup>> {
+    let mut by_size: HashMap<u64, Vec<NodeSummary>> = HashMap::new();
+    for file in files {
+        if file.apparent_bytes > 0 {
+            by_size.entry(file.apparent_bytes).or_default().push(file);
+        }
+    }
+    by_size.retain(|_, same_size| same_size.len() > 1);
+
+    let total_files: u64 = by_size.values().map(|files| files.len() as u64).sum();
+    let mut hasher = ContentHasher::new(tx, cancel, total_files);
+    let mut groups = Vec::new();
+
+    for same_size in by_size.into_values() {
+        for same_prefix in hasher.split(same_size, Some(PARTIAL_HASH_BYTES))? {
+            for same_content in hasher.split(same_prefix, None)? {
+                groups.push(DuplicateGroup {
+                    apparent_bytes: same_content[0].apparent_bytes,
+                    allocated_bytes: same_content[0].allocated_bytes,
+                    paths: {
+                        let mut paths: Vec<PathBuf> =
+                            same_content.into_iter().map(|file| file.path).collect();
+                        paths.sort();
+                        paths
+                    },
+                });
+            }
+        }
+    }
+
+    groups.sort_by(|a, b| {
+        b.wasted_bytes(SizeMetric::Allocated)
+            .cmp(&a.wasted_bytes(SizeMetric::Allocated))
+            .then_with(|| a.paths.cmp(&b.paths))
+    });
+    Some(groups)
+}
+
+/// Hashes file contents and reports progress; unreadable files are dropped with a warning.
+struct ContentHasher<'a> {
+    tx: &'a Sender<DuplicateEvent>,
+    cancel: &'a AtomicBool,
+    /// Two independently keyed SipHash states give a 128-bit digest per file.
+    keys: [RandomState; 2],
+    buf: Vec<u8>,
+    hashed_files: u64,
+    total_files: u64,
+}
+
+impl<'a> ContentHasher<'a> {
+    fn new(tx: &'a Sender<DuplicateEvent>, cancel: &'a AtomicBool, total_files: u64) -> Self {
+        Self {
+            tx,
+            cancel,
+            keys: [RandomState::new(), RandomState::new()],
+            buf: vec![0; READ_BUFFER_LEN],
+            hashed_files: 0,
+            total_files,
+        }
+    }
+
+    /// Splits `files` by content digest (of the first `limit` bytes, or everything),
+    /// keeping only subgroups that still hold more than one file.
+    fn split(
+        &mut self,
+        files: Vec<NodeSummary>,
+        limit: Option<usize>,
+    ) -> Option<Vec<Vec<NodeSummary>>> {
+        let mut by_digest: HashMap<(u64, u64), Vec<NodeSummary>> = HashMap::new();
+        for file in files {
+            if self.cancel.load(Ordering::Relaxed) {
+                return None;
+            }
+            match self.digest(&file.path, limit) {
+                Ok(digest) => by_digest.entry(digest).or_default().push(file),
+                Err(error) => {
+                    let _ = self.tx.send(DuplicateEvent::Warning {
+                        path: file.path,
+                        message: format!("cannot hash file: {error}"),
+                    });
+                }
+            }
+        }
+        // A cancelled read leaves a truncated digest behind; never report it.
+        if self.cancel.load(Ordering::Relaxed) {
+            return None;
+        }
+        Some(
+            by_digest
+                .into_values()
+                .filter(|same| same.len() > 1)
+                .collect(),
+        )
+    }
+
+    fn digest(&mut self, path: &Path, limit: Option<usize>) -> io::Result<(u64, u64)> {
+        let mut file = File::open(path)?;
+        let mut hashers = [self.keys[0].build_hasher(), self.keys[1].build_hasher()];
+        let mut remaining = limit.unwrap_or(usize::MAX);
+
+        while remaining > 0 {
+            if self.cancel.load(Ordering::Relaxed) {
+                break;
+            }
+            let want = remaining.min(self.buf.len());
+            let read = match file.read(&mut self.buf[..want]) {
+                Ok(0) => break,
+                Ok(read) => read,
+                Err(error) if error.kind() == io::ErrorKind::Interrupted => continue,
+                Err(error) => return Err(error),
+            };
+            for hasher in &mut hashers {
+                hasher.write(&self.buf[..read]);
+            }
+            remaining -= read;
+        }
+
+        // Only full-content passes count towards progress; partial hashes are cheap.
+        if limit.is_none() {
+            self.hashed_files += 1;
+            if self.hashed_files.is_multiple_of(PROGRESS_EMIT_EVERY) {
+                let _ = self.tx.send(DuplicateEvent::Progress {
+                    hashed_files: self.hashed_files,
+                    total_files: self.total_files,
+                });
+            }
+        }
+        Ok((hashers[0].finish(), hashers[1].finish()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+    use std::time::SystemTime;
+
+    use tempfile::TempDir;
+
+    use super::*;
+    use crate::model::{AgeSummary, FsEntryKind};
+
+    fn file_node(path: PathBuf) -> NodeSummary {
+        let bytes = fs::metadata(&path).expect("metadata").len();
+        NodeSummary {
+            path,
+            kind: FsEntryKind::File,
+            apparent_bytes: bytes,
+            allocated_bytes: bytes,
+            naive_apparent_bytes: bytes,
+            naive_allocated_bytes: bytes,
+            children_count: 0,
+            excluded_entries: 0,
+            age: AgeSummary::default(),
+            owner: None,
+            is_complete: true,
+            last_updated: SystemTime::now(),
+        }
+    }
+
+    #[test]
+    fn groups_identical_files_and_skips_same_sized_ones() {
+        let temp = TempDir::new().expect("temp dir");
+        let mut dataset = vec![7_u8; PARTIAL_HASH_BYTES * 2];
+        fs::write(temp.path().join("a.bin"), &dataset).expect("write a");
+        fs::write(temp.path().join("b.bin"), &dataset).expect("write b");
+        // Same size and same first block, different tail.
+        *dataset.last_mut().expect("non-empty") = 8;
+        fs::write(temp.path().join("c.bin"), &dataset).expect("write c");
+        fs::write(temp.path().join("empty-1"), b"").expect("write empty");
+        fs::write(temp.path().join("empty-2"), b"").expect("write empty");
+
+        let files = ["a.bin", "b.bin", "c.bin", "empty-1", "empty-2"]
+            .into_iter()
+            .map(|name| file_node(temp.path().join(name)))
+            .collect();
+        let (tx, _rx) = unbounded();
+        let groups = find_duplicates(files, &tx, &AtomicBool::new(false)).expect("not cancelled");
+
+        assert_eq!(groups.len(), 1);
+        assert_eq!(
+            groups[0].paths,
+            vec![temp.path().join("a.bin"), temp.path().join("b.bin")]
+        );
+        assert_eq!(
+            groups[0].wasted_bytes(SizeMetric::Apparent),
+            (PARTIAL_HASH_BYTES * 2) as u64
+        );
+    }
+}
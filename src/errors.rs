@@ -15,4 +15,10 @@ pub enum AppError {
 
     #[error("delete error for {path:?}: {reason}")]
     Delete { path: PathBuf, reason: String },
+
+    #[error("config error in {path:?}: {reason}")]
+    Config { path: PathBuf, reason: String },
+
+    #[error("snapshot error for {path:?}: {reason}")]
+    Snapshot { path: PathBuf, reason: String },
 }
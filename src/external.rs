@@ -0,0 +1,250 @@
+//! Hand-offs of the selected entry to programs outside the TUI: an
+//! interactive shell, the desktop opener, the terminal clipboard (OSC 52) and
+//! user-defined command templates such as `ncdu {path}`.
+
+use std::ffi::OsString;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+
+/// Work queued by a key press and carried out by the event loop, which owns
+/// the terminal and can suspend it for foreground programs.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum ExternalLaunch {
+    /// Interactive `$SHELL` in `dir`.
+    Shell { dir: PathBuf },
+    /// Expanded command template, run through `/bin/sh` in `dir`.
+    Command { line: String, dir: PathBuf },
+    /// Hand `path` to the desktop opener without waiting for it.
+    Open { path: PathBuf },
+    /// Put `path` on the clipboard through the terminal.
+    CopyPath { path: PathBuf },
+}
+
+impl ExternalLaunch {
+    /// The program to run in the foreground, for launches that take over the
+    /// terminal until they exit.
+    pub fn command(&self) -> Option<Command> {
+        match self {
+            Self::Shell { dir } => {
+                let mut command = Command::new(user_shell());
+                command.current_dir(dir);
+                Some(command)
+            }
+            Self::Command { line, dir } => {
+                let mut command = Command::new("/bin/sh");
+                command.arg("-c").arg(line).current_dir(dir);
+                Some(command)
+            }
+            Self::Open { .. } | Self::CopyPath { .. } => None,
+        }
+    }
+}
+
+/// Directory a launch for `path` starts in: the entry itself for directories,
+/// its parent otherwise.
+pub fn working_dir(path: &Path, is_dir: bool) -> PathBuf {
+    if is_dir {
+        return path.to_path_buf();
+    }
+    path.parent().unwrap_or(path).to_path_buf()
+}
+
+/// Replaces `{path}`, `{dir}` and `{name}` in `template` with shell-quoted
+/// values for the selected entry. The template is scanned once, so
+/// placeholders inside substituted values stay literal.
+///
+/// Quoting is POSIX-only, so templates are refused on Windows, where `cmd`
+/// would run them; paths that are not valid UTF-8 are refused rather than
+/// handed to the command with replacement characters.
+pub fn expand_template(template: &str, path: &Path, dir: &Path) -> Result<String, String> {
+    if cfg!(windows) {
+        return Err(
+            "Command templates need a POSIX shell and are not supported on Windows".to_string(),
+        );
+    }
+    let utf8 = |value: &Path| {
+        value.to_str().map(str::to_string).ok_or_else(|| {
+            format!(
+                "{} is not valid UTF-8; command templates need UTF-8 paths",
+                value.display()
+            )
+        })
+    };
+    let path_text = utf8(path)?;
+    let dir_text = utf8(dir)?;
+    let name = path
+        .file_name()
+        .and_then(|name| name.to_str())
+        .unwrap_or_default()
+        .to_string();
+    let placeholders = [("{path}", path_text), ("{dir}", dir_text), ("{name}", name)];
+
+    let mut expanded = String::with_capacity(template.len());
+    let mut rest = template;
+    while let Some(start) = rest.find('{') {
+        expanded.push_str(&rest[..start]);
+        rest = &rest[start..];
+        match placeholders
+            .iter()
+            .find(|(placeholder, _)| rest.starts_with(placeholder))
+        {
+            Some((placeholder, value)) => {
+                expanded.push_str(&shell_quote(value));
+                rest = &rest[placeholder.len()..];
+            }
+            None => {
+                expanded.push('{');
+                rest = &rest[1..];
+            }
+        }
+    }
+    expanded.push_str(rest);
+    Ok(expanded)
+}
+
+/// Quotes `text` as a single POSIX shell word.
+pub fn shell_quote(text: &str) -> String {
+    if !text.is_empty()
+        && text
+            .chars()
+            .all(|ch| ch.is_ascii_alphanumeric() || "/._-+,:@%".contains(ch))
+    {
+        return text.to_string();
+    }
+    format!("'{}'", text.replace('\'', r"'\''"))
+}
+
+fn user_shell() -> OsString {
+    if cfg!(windows) {
+        std::env::var_os("COMSPEC").unwrap_or_else(|| "cmd.exe".into())
+    } else {
+        std::env::var_os("SHELL")
+            .filter(|shell| !shell.is_empty())
+            .unwrap_or_else(|| "/bin/sh".into())
+    }
+}
+
+/// Opens `path` with the desktop's default application in the background.
+pub fn open_detached(path: &Path) -> io::Result<()> {
+    let opener = if cfg!(target_os = "macos") {
+        "open"
+    } else if cfg!(windows) {
+        "explorer"
+    } else {
+        "xdg-open"
+    };
+    let mut child = Command::new(opener)
+        .arg(path)
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()?;
+    // Reap the opener once it hands off, so it does not linger as a zombie.
+    std::thread::spawn(move || child.wait());
+    Ok(())
+}
+
+/// Writes an OSC 52 "set clipboard" sequence for `text` to `out`.
+pub fn copy_to_clipboard(out: &mut impl Write, text: &str) -> io::Result<()> {
+    out.write_all(osc52_sequence(text).as_bytes())?;
+    out.flush()
+}
+
+pub fn osc52_sequence(text: &str) -> String {
+    format!("\x1b]52;c;{}\x07", base64(text.as_bytes()))
+}
+
+fn base64(bytes: &[u8]) -> String {
+    const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+    let mut encoded = String::with_capacity(bytes.len().div_ceil(3) * 4);
+    for chunk in bytes.chunks(3) {
+        let block = match *chunk {
+            [a] => u32::from(a) << 16,
+            [a, b] => u32::from(a) << 16 | u32::from(b) << 8,
+            [a, b, c] => u32::from(a) << 16 | u32::from(b) << 8 | u32::from(c),
+            _ => unreachable!("chunks(3) yields one to three bytes"),
+        };
+        for index in 0..4 {
+            if index <= chunk.len() {
+                let sextet = (block >> (18 - 6 * index)) & 0x3f;
+                encoded.push(char::from(ALPHABET[sextet as usize]));
+            } else {
+                encoded.push('=');
+            }
+        }
+    }
+    encoded
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[cfg(unix)]
+    #[test]
+    fn expands_templates_with_quoted_paths() {
+        let path = Path::new("/srv/it's here/a b.iso");
+        let dir = working_dir(path, false);
+        assert_eq!(dir, PathBuf::from("/srv/it's here"));
+        assert_eq!(
+            expand_template("ncdu {dir} # {name}", path, &dir).as_deref(),
+            Ok(r"ncdu '/srv/it'\''s here' # 'a b.iso'")
+        );
+        assert_eq!(
+            expand_template(
+                "du -sh {path}",
+                Path::new("/srv/media"),
+                Path::new("/srv/media")
+            )
+            .as_deref(),
+            Ok("du -sh /srv/media")
+        );
+        assert_eq!(shell_quote(""), "''");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn never_expands_placeholders_inside_substituted_values() {
+        let path = Path::new("/srv/{dir}/$(touch pwned){name}");
+        let dir = working_dir(path, false);
+        assert_eq!(
+            expand_template("ncdu {path} {name} {dir} {other}", path, &dir).as_deref(),
+            Ok(
+                "ncdu '/srv/{dir}/$(touch pwned){name}' '$(touch pwned){name}' \
+             '/srv/{dir}' {other}"
+            )
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn refuses_paths_that_are_not_utf8() {
+        use std::ffi::OsStr;
+        use std::os::unix::ffi::OsStrExt;
+
+        let path = Path::new("/srv").join(OsStr::from_bytes(b"caf\xe9.iso"));
+        let error = expand_template("ncdu {name}", &path, Path::new("/srv"))
+            .expect_err("lossy path refused");
+        assert!(error.contains("not valid UTF-8"));
+    }
+
+    #[cfg(windows)]
+    #[test]
+    fn refuses_templates_on_windows() {
+        assert!(expand_template("dir {path}", Path::new(r"C:\srv"), Path::new(r"C:\")).is_err());
+    }
+
+    #[test]
+    fn encodes_clipboard_sequences() {
+        assert_eq!(base64(b""), "");
+        assert_eq!(base64(b"f"), "Zg==");
+        assert_eq!(base64(b"fo"), "Zm8=");
+        assert_eq!(base64(b"foo"), "Zm9v");
+        assert_eq!(base64(b"/srv/data"), "L3Nydi9kYXRh");
+
+        let mut out = Vec::new();
+        copy_to_clipboard(&mut out, "/srv").expect("write sequence");
+        assert_eq!(out, b"\x1b]52;c;L3Nydg==\x07");
+    }
+}
@@ -0,0 +1,286 @@
+use std::collections::HashMap;
+use std::fmt::{Display, Formatter};
+use std::path::Path;
+
+use crate::model::SizeMetric;
+
+/// Coarse grouping of file extensions for the breakdown view.
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash, Ord, PartialOrd)]
+pub enum FileCategory {
+    Video,
+    Images,
+    Archives,
+    Source,
+    Logs,
+    VmImages,
+    Other,
+}
+
+impl FileCategory {
+    pub fn for_extension(extension: &str) -> Self {
+        match extension {
+            "mp4" | "mkv" | "mov" | "avi" | "webm" | "m4v" | "wmv" | "flv" | "mpg" | "mpeg"
+            | "m2ts" => Self::Video,
+            "jpg" | "jpeg" | "png" | "gif" | "webp" | "bmp" | "tif" | "tiff" | "heic" | "svg"
+            | "raw" | "cr2" | "nef" | "dng" | "psd" => Self::Images,
+            "zip" | "tar" | "gz" | "tgz" | "bz2" | "xz" | "zst" | "7z" | "rar" | "deb" | "rpm"
+            | "jar" | "whl" => Self::Archives,
+            "rs" | "c" | "h" | "cc" | "cpp" | "hpp" | "py" | "js" | "jsx" | "ts" | "tsx" | "go"
+            | "java" | "kt" | "rb" | "php" | "cs" | "swift" | "sh" | "lua" | "toml" | "json"
+            | "yaml" | "yml" | "html" | "css" | "md" => Self::Source,
+            "log" | "journal" | "out" | "err" => Self::Logs,
+            "iso" | "img" | "qcow2" | "vmdk" | "vdi" | "vhd" | "vhdx" | "ova" => Self::VmImages,
+            _ => Self::Other,
+        }
+    }
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Video => "video",
+            Self::Images => "images",
+            Self::Archives => "archives",
+            Self::Source => "source",
+            Self::Logs => "logs",
+            Self::VmImages => "vm images",
+            Self::Other => "other",
+        }
+    }
+}
+
+impl Display for FileCategory {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// Lowercased extension of a file name; empty for names without one (`Makefile`, `.bashrc`).
+pub fn extension_of(path: &Path) -> String {
+    path.extension()
+        .map(|ext| ext.to_string_lossy().to_lowercase())
+        .unwrap_or_default()
+}
+
+#[derive(Debug, Clone, Copy, Default, Eq, PartialEq)]
+pub struct TypeStat {
+    pub files: u64,
+    pub apparent_bytes: u64,
+    pub allocated_bytes: u64,
+}
+
+impl TypeStat {
+    pub fn metric_bytes(&self, metric: SizeMetric) -> u64 {
+        match metric {
+            SizeMetric::Allocated => self.allocated_bytes,
+            SizeMetric::Apparent => self.apparent_bytes,
+        }
+    }
+
+    fn merge(&mut self, other: &TypeStat) {
+        self.files = self.files.saturating_add(other.files);
+        self.apparent_bytes = self.apparent_bytes.saturating_add(other.apparent_bytes);
+        self.allocated_bytes = self.allocated_bytes.saturating_add(other.allocated_bytes);
+    }
+}
+
+/// Bytes and file counts per extension.
+#[derive(Debug, Clone, Default, Eq, PartialEq)]
+pub struct TypeTally {
+    by_extension: HashMap<String, TypeStat>,
+}
+
+impl TypeTally {
+    pub fn add_file(&mut self, path: &Path, apparent_bytes: u64, allocated_bytes: u64) {
+        self.by_extension
+            .entry(extension_of(path))
+            .or_default()
+            .merge(&TypeStat {
+                files: 1,
+                apparent_bytes,
+                allocated_bytes,
+            });
+    }
+
+    pub fn merge(&mut self, other: &TypeTally) {
+        for (extension, stat) in &other.by_extension {
+            self.by_extension
+                .entry(extension.clone())
+                .or_default()
+                .merge(stat);
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.by_extension.is_empty()
+    }
+
+    pub fn extensions(&self) -> impl Iterator<Item = (&str, &TypeStat)> {
+        self.by_extension
+            .iter()
+            .map(|(extension, stat)| (extension.as_str(), stat))
+    }
+
+    pub fn categories(&self) -> HashMap<FileCategory, TypeStat> {
+        let mut categories: HashMap<FileCategory, TypeStat> = HashMap::new();
+        for (extension, stat) in &self.by_extension {
+            categories
+                .entry(FileCategory::for_extension(extension))
+                .or_default()
+                .merge(stat);
+        }
+        categories
+    }
+}
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum TypeGrouping {
+    Extension,
+    Category,
+}
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum BreakdownSort {
+    SizeDesc,
+    CountDesc,
+    Name,
+}
+
+impl BreakdownSort {
+    pub fn cycle(self) -> Self {
+        match self {
+            Self::SizeDesc => Self::CountDesc,
+            Self::CountDesc => Self::Name,
+            Self::Name => Self::SizeDesc,
+        }
+    }
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::SizeDesc => "size",
+            Self::CountDesc => "count",
+            Self::Name => "name",
+        }
+    }
+}
+
+/// One row of the file-type breakdown.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct BreakdownEntry {
+    pub label: String,
+    pub category: FileCategory,
+    pub stat: TypeStat,
+}
+
+pub fn breakdown(
+    tally: &TypeTally,
+    grouping: TypeGrouping,
+    sort: BreakdownSort,
+    metric: SizeMetric,
+) -> Vec<BreakdownEntry> {
+    let mut entries: Vec<BreakdownEntry> = match grouping {
+        TypeGrouping::Extension => tally
+            .extensions()
+            .map(|(extension, stat)| BreakdownEntry {
+                label: if extension.is_empty() {
+                    "(none)".to_string()
+                } else {
+                    format!(".{extension}")
+                },
+                category: FileCategory::for_extension(extension),
+                stat: *stat,
+            })
+            .collect(),
+        TypeGrouping::Category => tally
+            .categories()
+            .into_iter()
+            .map(|(category, stat)| BreakdownEntry {
+                label: category.to_string(),
+                category,
+                stat,
+            })
+            .collect(),
+    };
+
+    match sort {
+        BreakdownSort::SizeDesc => entries.sort_by(|a, b| {
+            b.stat
+                .metric_bytes(metric)
+                .cmp(&a.stat.metric_bytes(metric))
+                .then_with(|| a.label.cmp(&b.label))
+        }),
+        BreakdownSort::CountDesc => entries.sort_by(|a, b| {
+            b.stat
+                .files
+                .cmp(&a.stat.files)
+                .then_with(|| a.label.cmp(&b.label))
+        }),
+        BreakdownSort::Name => entries.sort_by(|a, b| a.label.cmp(&b.label)),
+    }
+    entries
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use super::*;
+
+    #[test]
+    fn extracts_lowercase_extensions() {
+        assert_eq!(extension_of(Path::new("/srv/Movie.MP4")), "mp4");
+        assert_eq!(extension_of(Path::new("archive.tar.gz")), "gz");
+        assert_eq!(extension_of(Path::new("Makefile")), "");
+        assert_eq!(extension_of(Path::new(".bashrc")), "");
+    }
+
+    #[test]
+    fn merges_tallies_and_groups_categories() {
+        let mut share = TypeTally::default();
+        share.add_file(&PathBuf::from("a.mp4"), 700, 704);
+        share.add_file(&PathBuf::from("b.MKV"), 100, 104);
+
+        let mut nested = TypeTally::default();
+        nested.add_file(&PathBuf::from("c.mp4"), 50, 52);
+        nested.add_file(&PathBuf::from("notes"), 1, 4);
+        share.merge(&nested);
+
+        let mp4 = share
+            .extensions()
+            .find(|(extension, _)| *extension == "mp4")
+            .map(|(_, stat)| *stat)
+            .expect("mp4 tallied");
+        assert_eq!(mp4.files, 2);
+        assert_eq!(mp4.apparent_bytes, 750);
+
+        let categories = share.categories();
+        assert_eq!(categories[&FileCategory::Video].files, 3);
+        assert_eq!(categories[&FileCategory::Video].allocated_bytes, 860);
+        assert_eq!(categories[&FileCategory::Other].files, 1);
+    }
+
+    #[test]
+    fn breakdown_sorts_by_selected_key() {
+        let mut tally = TypeTally::default();
+        tally.add_file(Path::new("big.iso"), 9000, 9000);
+        for name in ["a.log", "b.log", "c.log"] {
+            tally.add_file(Path::new(name), 10, 10);
+        }
+
+        let by_size = breakdown(
+            &tally,
+            TypeGrouping::Extension,
+            BreakdownSort::SizeDesc,
+            SizeMetric::Apparent,
+        );
+        assert_eq!(by_size[0].label, ".iso");
+        assert_eq!(by_size[0].category, FileCategory::VmImages);
+
+        let by_count = breakdown(
+            &tally,
+            TypeGrouping::Category,
+            BreakdownSort::CountDesc,
+            SizeMetric::Apparent,
+        );
+        assert_eq!(by_count[0].label, "logs");
+        assert_eq!(by_count[0].stat.files, 3);
+    }
+}
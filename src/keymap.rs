@@ -0,0 +1,400 @@
+//! Table actions and the keys bound to them; defaults can be replaced from
+//! the `[keys]` table of the config file.
+
+use std::fmt::{Display, Formatter};
+
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+
+use crate::model::TableColumn;
+
+/// Everything the main table and the breakdown panels can be told to do.
+/// Text input (filter, delete confirmation) reads raw keys instead.
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash)]
+pub enum Action {
+    Quit,
+    MoveDown,
+    MoveUp,
+    Open,
+    Parent,
+    Expand,
+    Collapse,
+    CycleSort,
+    ToggleColumn(TableColumn),
+    ToggleMetric,
+    CycleFileTypes,
+    CycleOwners,
+    LargestFiles,
+    Duplicates,
+    TreeMode,
+    Treemap,
+    Rescan,
+    Filter,
+    Help,
+    Clear,
+    Mark,
+    MarkAll,
+    Delete,
+    /// Interactive `$SHELL` in the selected directory.
+    Shell,
+    /// Desktop opener (`xdg-open`) on the selected entry.
+    OpenExternal,
+    /// Selected path to the clipboard via OSC 52.
+    CopyPath,
+    /// Command template `n` from the config file's `[commands]` table.
+    RunCommand(usize),
+}
+
+impl Action {
+    /// Every action with a name; `RunCommand` is bound through `[commands]`.
+    pub const ALL: [Self; 31] = [
+        Self::Quit,
+        Self::MoveDown,
+        Self::MoveUp,
+        Self::Open,
+        Self::Parent,
+        Self::Expand,
+        Self::Collapse,
+        Self::CycleSort,
+        Self::ToggleColumn(TableColumn::Name),
+        Self::ToggleColumn(TableColumn::Kind),
+        Self::ToggleColumn(TableColumn::Size),
+        Self::ToggleColumn(TableColumn::Modified),
+        Self::ToggleColumn(TableColumn::Relative),
+        Self::ToggleColumn(TableColumn::Path),
+        Self::ToggleMetric,
+        Self::CycleFileTypes,
+        Self::CycleOwners,
+        Self::LargestFiles,
+        Self::Duplicates,
+        Self::TreeMode,
+        Self::Treemap,
+        Self::Rescan,
+        Self::Filter,
+        Self::Help,
+        Self::Clear,
+        Self::Mark,
+        Self::MarkAll,
+        Self::Delete,
+        Self::Shell,
+        Self::OpenExternal,
+        Self::CopyPath,
+    ];
+
+    /// Name used in the `[keys]` table of the config file.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Quit => "quit",
+            Self::MoveDown => "move-down",
+            Self::MoveUp => "move-up",
+            Self::Open => "open",
+            Self::Parent => "parent",
+            Self::Expand => "expand",
+            Self::Collapse => "collapse",
+            Self::CycleSort => "sort",
+            Self::ToggleColumn(TableColumn::Name) => "toggle-name-column",
+            Self::ToggleColumn(TableColumn::Kind) => "toggle-kind-column",
+            Self::ToggleColumn(TableColumn::Size) => "toggle-size-column",
+            Self::ToggleColumn(TableColumn::Modified) => "toggle-modified-column",
+            Self::ToggleColumn(TableColumn::Relative) => "toggle-relative-column",
+            Self::ToggleColumn(TableColumn::Path) => "toggle-path-column",
+            Self::ToggleMetric => "metric",
+            Self::CycleFileTypes => "file-types",
+            Self::CycleOwners => "owners",
+            Self::LargestFiles => "largest-files",
+            Self::Duplicates => "duplicates",
+            Self::TreeMode => "tree",
+            Self::Treemap => "treemap",
+            Self::Rescan => "rescan",
+            Self::Filter => "filter",
+            Self::Help => "help",
+            Self::Clear => "clear",
+            Self::Mark => "mark",
+            Self::MarkAll => "mark-all",
+            Self::Delete => "delete",
+            Self::Shell => "shell",
+            Self::OpenExternal => "open-external",
+            Self::CopyPath => "copy-path",
+            Self::RunCommand(_) => "command",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|action| action.as_str() == name)
+    }
+}
+
+/// A key with its modifiers. Shift is folded into the character for
+/// printable keys, so `N` and `Shift+n` are the same binding.
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash)]
+pub struct KeyBinding {
+    pub code: KeyCode,
+    pub modifiers: KeyModifiers,
+}
+
+impl KeyBinding {
+    pub const fn new(code: KeyCode) -> Self {
+        Self {
+            code,
+            modifiers: KeyModifiers::NONE,
+        }
+    }
+
+    pub fn from_event(event: &KeyEvent) -> Self {
+        let mut modifiers = event.modifiers & (KeyModifiers::CONTROL | KeyModifiers::ALT);
+        if !matches!(event.code, KeyCode::Char(_)) {
+            modifiers |= event.modifiers & KeyModifiers::SHIFT;
+        }
+        Self {
+            code: event.code,
+            modifiers,
+        }
+    }
+
+    /// Parses specs such as `j`, `N`, `Shift+n`, `Ctrl+d`, `Space`, `PageDown` or `F1`.
+    pub fn parse(spec: &str) -> Result<Self, String> {
+        let spec = spec.trim();
+        let mut parts: Vec<&str> = if spec.len() > 1 {
+            spec.split('+').collect()
+        } else {
+            vec![spec]
+        };
+        let key = parts.pop().filter(|key| !key.is_empty());
+        let Some(key) = key else {
+            return Err(format!("invalid key `{spec}`"));
+        };
+
+        let mut modifiers = KeyModifiers::NONE;
+        for modifier in parts {
+            modifiers |= match modifier.to_ascii_lowercase().as_str() {
+                "ctrl" | "control" => KeyModifiers::CONTROL,
+                "alt" => KeyModifiers::ALT,
+                "shift" => KeyModifiers::SHIFT,
+                _ => return Err(format!("unknown modifier `{modifier}` in `{spec}`")),
+            };
+        }
+
+        let mut chars = key.chars();
+        let code = match (chars.next(), chars.next()) {
+            (Some(ch), None) => KeyCode::Char(ch),
+            _ => named_key(key).ok_or_else(|| format!("unknown key `{key}` in `{spec}`"))?,
+        };
+
+        let code = match code {
+            KeyCode::Char(ch) if modifiers.contains(KeyModifiers::SHIFT) => {
+                modifiers.remove(KeyModifiers::SHIFT);
+                KeyCode::Char(ch.to_ascii_uppercase())
+            }
+            other => other,
+        };
+        Ok(Self { code, modifiers })
+    }
+
+    /// The character of a plain printable key, for hotkey hints.
+    pub fn as_char(self) -> Option<char> {
+        match self.code {
+            KeyCode::Char(ch) if self.modifiers.is_empty() && ch != ' ' => Some(ch),
+            _ => None,
+        }
+    }
+}
+
+fn named_key(name: &str) -> Option<KeyCode> {
+    let lower = name.to_ascii_lowercase();
+    let code = match lower.as_str() {
+        "enter" | "return" => KeyCode::Enter,
+        "esc" | "escape" => KeyCode::Esc,
+        "backspace" => KeyCode::Backspace,
+        "tab" => KeyCode::Tab,
+        "backtab" => KeyCode::BackTab,
+        "space" => KeyCode::Char(' '),
+        "up" => KeyCode::Up,
+        "down" => KeyCode::Down,
+        "left" => KeyCode::Left,
+        "right" => KeyCode::Right,
+        "home" => KeyCode::Home,
+        "end" => KeyCode::End,
+        "pageup" => KeyCode::PageUp,
+        "pagedown" => KeyCode::PageDown,
+        "delete" | "del" => KeyCode::Delete,
+        "insert" => KeyCode::Insert,
+        function => KeyCode::F(function.strip_prefix('f')?.parse().ok()?),
+    };
+    Some(code)
+}
+
+impl Display for KeyBinding {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        if self.modifiers.contains(KeyModifiers::CONTROL) {
+            f.write_str("Ctrl+")?;
+        }
+        if self.modifiers.contains(KeyModifiers::ALT) {
+            f.write_str("Alt+")?;
+        }
+        if self.modifiers.contains(KeyModifiers::SHIFT) {
+            f.write_str("Shift+")?;
+        }
+        match self.code {
+            KeyCode::Char(' ') => f.write_str("Space"),
+            KeyCode::Char(ch) if ch.is_ascii_uppercase() => write!(f, "Shift+{ch}"),
+            KeyCode::Char(ch) => write!(f, "{ch}"),
+            KeyCode::F(number) => write!(f, "F{number}"),
+            KeyCode::Enter => f.write_str("Enter"),
+            KeyCode::Esc => f.write_str("Esc"),
+            KeyCode::Backspace => f.write_str("Backspace"),
+            KeyCode::Tab => f.write_str("Tab"),
+            KeyCode::BackTab => f.write_str("BackTab"),
+            KeyCode::Up => f.write_str("Up"),
+            KeyCode::Down => f.write_str("Down"),
+            KeyCode::Left => f.write_str("Left"),
+            KeyCode::Right => f.write_str("Right"),
+            KeyCode::Home => f.write_str("Home"),
+            KeyCode::End => f.write_str("End"),
+            KeyCode::PageUp => f.write_str("PageUp"),
+            KeyCode::PageDown => f.write_str("PageDown"),
+            KeyCode::Delete => f.write_str("Delete"),
+            KeyCode::Insert => f.write_str("Insert"),
+            other => write!(f, "{other:?}"),
+        }
+    }
+}
+
+/// Key to action table, in binding order.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct Keymap {
+    bindings: Vec<(KeyBinding, Action)>,
+}
+
+impl Default for Keymap {
+    fn default() -> Self {
+        let char_key = |ch| KeyBinding::new(KeyCode::Char(ch));
+        let bindings = vec![
+            (char_key('q'), Action::Quit),
+            (char_key('j'), Action::MoveDown),
+            (KeyBinding::new(KeyCode::Down), Action::MoveDown),
+            (char_key('k'), Action::MoveUp),
+            (KeyBinding::new(KeyCode::Up), Action::MoveUp),
+            (KeyBinding::new(KeyCode::Enter), Action::Open),
+            (char_key('h'), Action::Parent),
+            (KeyBinding::new(KeyCode::Backspace), Action::Parent),
+            (KeyBinding::new(KeyCode::Right), Action::Expand),
+            (KeyBinding::new(KeyCode::Left), Action::Collapse),
+            (char_key('s'), Action::CycleSort),
+            (char_key('N'), Action::ToggleColumn(TableColumn::Name)),
+            (char_key('K'), Action::ToggleColumn(TableColumn::Kind)),
+            (char_key('S'), Action::ToggleColumn(TableColumn::Size)),
+            (char_key('M'), Action::ToggleColumn(TableColumn::Modified)),
+            (char_key('R'), Action::ToggleColumn(TableColumn::Relative)),
+            (char_key('P'), Action::ToggleColumn(TableColumn::Path)),
+            (char_key('m'), Action::ToggleMetric),
+            (char_key('t'), Action::CycleFileTypes),
+            (char_key('o'), Action::CycleOwners),
+            (char_key('f'), Action::LargestFiles),
+            (char_key('u'), Action::Duplicates),
+            (char_key('e'), Action::TreeMode),
+            (char_key('v'), Action::Treemap),
+            (char_key('r'), Action::Rescan),
+            (char_key('/'), Action::Filter),
+            (char_key('?'), Action::Help),
+            (KeyBinding::new(KeyCode::F(1)), Action::Help),
+            (KeyBinding::new(KeyCode::Esc), Action::Clear),
+            (char_key(' '), Action::Mark),
+            (char_key('a'), Action::MarkAll),
+            (char_key('d'), Action::Delete),
+            (char_key('!'), Action::Shell),
+            (char_key('O'), Action::OpenExternal),
+            (char_key('y'), Action::CopyPath),
+        ];
+        Self { bindings }
+    }
+}
+
+impl Keymap {
+    /// Replaces the default keys of every action named in `overrides`; a key
+    /// taken over this way no longer triggers its default action.
+    pub fn with_overrides(mut self, overrides: &[(Action, KeyBinding)]) -> Self {
+        self.bindings
+            .retain(|(_, action)| !overrides.iter().any(|(overridden, _)| overridden == action));
+        for (action, key) in overrides {
+            self.bindings.retain(|(bound, _)| bound != key);
+            self.bindings.push((*key, *action));
+        }
+        self
+    }
+
+    pub fn action_for(&self, event: &KeyEvent) -> Option<Action> {
+        let key = KeyBinding::from_event(event);
+        self.bindings
+            .iter()
+            .find(|(bound, _)| *bound == key)
+            .map(|(_, action)| *action)
+    }
+
+    pub fn keys(&self, action: Action) -> impl Iterator<Item = KeyBinding> + '_ {
+        self.bindings
+            .iter()
+            .filter(move |(_, bound)| *bound == action)
+            .map(|(key, _)| *key)
+    }
+
+    /// All keys of `action` joined by `separator`, or `unbound`.
+    pub fn label(&self, action: Action, separator: &str) -> String {
+        let keys: Vec<String> = self.keys(action).map(|key| key.to_string()).collect();
+        if keys.is_empty() {
+            "unbound".to_string()
+        } else {
+            keys.join(separator)
+        }
+    }
+
+    /// The first key of `action`, for compact legends.
+    pub fn primary(&self, action: Action) -> String {
+        self.keys(action)
+            .next()
+            .map_or_else(|| "unbound".to_string(), |key| key.to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_and_displays_key_specs() {
+        let parse = |spec| KeyBinding::parse(spec).expect("valid key");
+        assert_eq!(parse("j"), KeyBinding::new(KeyCode::Char('j')));
+        assert_eq!(parse("Shift+n"), parse("N"));
+        assert_eq!(parse("space"), KeyBinding::new(KeyCode::Char(' ')));
+        assert_eq!(parse("+"), KeyBinding::new(KeyCode::Char('+')));
+        assert_eq!(parse("F12"), KeyBinding::new(KeyCode::F(12)));
+        assert_eq!(parse("Ctrl+d").modifiers, KeyModifiers::CONTROL);
+        assert!(KeyBinding::parse("Hyper+x").is_err());
+        assert!(KeyBinding::parse("PageSideways").is_err());
+
+        for spec in ["j", "Shift+N", "Ctrl+d", "Space", "PageDown", "F1", "?"] {
+            assert_eq!(parse(spec).to_string(), spec);
+        }
+    }
+
+    #[test]
+    fn overrides_replace_defaults_and_steal_keys() {
+        let shifted = KeyEvent::new(KeyCode::Char('N'), KeyModifiers::SHIFT);
+        assert_eq!(
+            Keymap::default().action_for(&shifted),
+            Some(Action::ToggleColumn(TableColumn::Name))
+        );
+
+        let keymap = Keymap::default().with_overrides(&[
+            (Action::Delete, KeyBinding::parse("x").unwrap()),
+            (Action::MoveDown, KeyBinding::parse("d").unwrap()),
+        ]);
+        let press = |ch| KeyEvent::new(KeyCode::Char(ch), KeyModifiers::NONE);
+        assert_eq!(keymap.action_for(&press('x')), Some(Action::Delete));
+        assert_eq!(keymap.action_for(&press('d')), Some(Action::MoveDown));
+        assert_eq!(keymap.action_for(&press('j')), None);
+        assert_eq!(keymap.label(Action::MoveDown, " / "), "d");
+        assert_eq!(keymap.label(Action::Help, "/"), "?/F1");
+
+        let ctrl_d = KeyEvent::new(KeyCode::Char('d'), KeyModifiers::CONTROL);
+        assert_eq!(keymap.action_for(&ctrl_d), None);
+    }
+}
@@ -0,0 +1,157 @@
+use std::collections::{BTreeSet, HashMap};
+use std::path::{Path, PathBuf};
+
+use crate::model::{NodeSummary, SizeMetric};
+
+/// Files kept per scan for the largest-files view.
+pub const LARGEST_FILES_CAPACITY: usize = 256;
+
+/// Bounded set of the largest files seen so far.
+///
+/// Files are ranked by the larger of their apparent and allocated size so the
+/// set stays useful whichever metric is displayed; the view re-sorts by the
+/// active metric.
+#[derive(Debug, Clone)]
+pub struct LargestFiles {
+    capacity: usize,
+    ranked: BTreeSet<(u64, PathBuf)>,
+    files: HashMap<PathBuf, NodeSummary>,
+}
+
+impl LargestFiles {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            ranked: BTreeSet::new(),
+            files: HashMap::new(),
+        }
+    }
+
+    fn rank(file: &NodeSummary) -> u64 {
+        file.apparent_bytes.max(file.allocated_bytes)
+    }
+
+    /// Smallest rank a file needs to enter the set once it is full.
+    pub fn floor(&self) -> Option<u64> {
+        (self.ranked.len() >= self.capacity)
+            .then(|| self.ranked.first().map(|(rank, _)| *rank))
+            .flatten()
+    }
+
+    /// Inserts or replaces `file`; returns false when it is too small to be kept.
+    pub fn offer(&mut self, file: &NodeSummary) -> bool {
+        if self.capacity == 0 {
+            return false;
+        }
+        self.remove(&file.path);
+
+        let rank = Self::rank(file);
+        if self.floor().is_some_and(|floor| rank <= floor) {
+            return false;
+        }
+        if self.ranked.len() >= self.capacity
+            && let Some((_, evicted)) = self.ranked.pop_first()
+        {
+            self.files.remove(&evicted);
+        }
+        self.ranked.insert((rank, file.path.clone()));
+        self.files.insert(file.path.clone(), file.clone());
+        true
+    }
+
+    pub fn remove(&mut self, path: &Path) {
+        if let Some(previous) = self.files.remove(path) {
+            self.ranked.remove(&(Self::rank(&previous), previous.path));
+        }
+    }
+
+    /// Drops every file at or below `root`, e.g. after a delete or before a rescan.
+    pub fn remove_subtree(&mut self, root: &Path) {
+        let removed: Vec<PathBuf> = self
+            .files
+            .keys()
+            .filter(|path| path.starts_with(root))
+            .cloned()
+            .collect();
+        for path in removed {
+            self.remove(&path);
+        }
+    }
+
+    /// Kept files strictly below `root`, largest first by `metric`.
+    pub fn below(&self, root: &Path, metric: SizeMetric) -> Vec<&NodeSummary> {
+        let mut files: Vec<&NodeSummary> = self
+            .files
+            .values()
+            .filter(|file| file.path != root && file.path.starts_with(root))
+            .collect();
+        files.sort_by(|a, b| {
+            b.metric_bytes(metric)
+                .cmp(&a.metric_bytes(metric))
+                .then_with(|| a.path.cmp(&b.path))
+        });
+        files
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::SystemTime;
+
+    use super::*;
+    use crate::model::{AgeSummary, FsEntryKind};
+
+    fn file(path: &str, bytes: u64) -> NodeSummary {
+        NodeSummary {
+            path: PathBuf::from(path),
+            kind: FsEntryKind::File,
+            apparent_bytes: bytes,
+            allocated_bytes: bytes,
+            naive_apparent_bytes: bytes,
+            naive_allocated_bytes: bytes,
+            children_count: 0,
+            excluded_entries: 0,
+            age: AgeSummary::default(),
+            owner: None,
+            is_complete: true,
+            last_updated: SystemTime::now(),
+        }
+    }
+
+    #[test]
+    fn keeps_only_the_largest_files() {
+        let mut largest = LargestFiles::new(2);
+        assert!(largest.offer(&file("/srv/a.iso", 300)));
+        assert!(largest.offer(&file("/srv/b.log", 10)));
+        assert_eq!(largest.floor(), Some(10));
+        assert!(largest.offer(&file("/srv/c.mp4", 200)));
+        assert!(!largest.offer(&file("/srv/d.txt", 5)));
+
+        let names: Vec<&Path> = largest
+            .below(Path::new("/srv"), SizeMetric::Apparent)
+            .into_iter()
+            .map(|file| file.path.as_path())
+            .collect();
+        assert_eq!(names, [Path::new("/srv/a.iso"), Path::new("/srv/c.mp4")]);
+    }
+
+    #[test]
+    fn replaces_reoffered_paths_and_filters_by_subtree() {
+        let mut largest = LargestFiles::new(4);
+        largest.offer(&file("/srv/media/a.mkv", 100));
+        largest.offer(&file("/srv/media/a.mkv", 50));
+        largest.offer(&file("/srv/logs/b.log", 70));
+
+        let media = largest.below(Path::new("/srv/media"), SizeMetric::Allocated);
+        assert_eq!(media.len(), 1);
+        assert_eq!(media[0].apparent_bytes, 50);
+
+        largest.remove_subtree(Path::new("/srv/logs"));
+        assert_eq!(
+            largest
+                .below(Path::new("/srv"), SizeMetric::Allocated)
+                .len(),
+            1
+        );
+    }
+}
@@ -1,9 +1,26 @@
 pub mod app;
 pub mod cli;
+pub mod config_file;
 pub mod delete;
+pub mod diff;
+pub mod duplicates;
 pub mod errors;
+pub mod external;
+pub mod filetypes;
+pub mod keymap;
+pub mod largest;
+pub mod listing;
 pub mod model;
+pub mod owners;
+pub mod pattern;
 pub mod platform;
+pub mod report;
 pub mod scanner;
+pub mod snapshot;
+pub mod store;
 pub mod theme;
+pub mod trash;
+pub mod treemap;
 pub mod ui;
+#[cfg(target_os = "linux")]
+pub mod watch;
@@ -0,0 +1,282 @@
+//! Filtered and sorted children of a directory as the children table lists
+//! them, and a per-directory cache of those listings so large directories are
+//! not re-sorted on every frame.
+
+use std::cmp::Reverse;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+use std::time::{Duration, SystemTime};
+
+use crate::diff::Baseline;
+use crate::model::{NodeStats, SizeMetric, SortMode};
+use crate::owners::OwnerId;
+use crate::store::NodeStore;
+
+/// View settings a listing depends on besides the tree itself.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct ListingSettings {
+    pub sort_mode: SortMode,
+    pub metric: SizeMetric,
+    /// Lowercased text the name or full path must contain; empty keeps everything.
+    pub filter: String,
+    /// Keeps only entries last modified longer ago than this.
+    pub older_than: Option<Duration>,
+    pub owner_filter: Option<OwnerId>,
+    /// Generation of the owner tallies `owner_filter` is checked against.
+    pub owners_generation: u64,
+}
+
+/// Children of one directory in display order.
+#[derive(Debug, Clone, Default, Eq, PartialEq)]
+pub struct Listing {
+    pub paths: Vec<PathBuf>,
+    /// Largest current size among `paths` under the listing's metric; `None`
+    /// when none of them exists anymore.
+    pub max_bytes: Option<u64>,
+}
+
+/// Lists the children of `dir` in `nodes`, plus the ones only `baseline` still
+/// has once `dir` finished scanning, filtered and sorted per `settings`.
+/// `owned` tells whether an entry holds bytes of the filtered owner.
+pub fn list_children(
+    nodes: &NodeStore,
+    baseline: Option<&Baseline>,
+    dir: &Path,
+    settings: &ListingSettings,
+    owned: impl Fn(&Path, OwnerId) -> bool,
+) -> Listing {
+    // Each child is resolved once; filters and sort keys only read `current`.
+    let mut entries: Vec<(PathBuf, Option<&NodeStats>)> = nodes
+        .id(dir)
+        .map(|id| {
+            nodes
+                .children(id)
+                .iter()
+                .filter_map(|child| {
+                    Some((dir.join(nodes.name(*child)), Some(nodes.stats(*child)?)))
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+
+    // Removed entries only become certain once the directory finished scanning.
+    if let Some(baseline) = baseline
+        && nodes.get(dir).is_some_and(|node| node.is_complete)
+    {
+        entries.extend(
+            baseline
+                .children(dir)
+                .into_iter()
+                .filter(|path| !nodes.contains(path))
+                .map(|path| (path, None)),
+        );
+    }
+
+    if !settings.filter.is_empty() {
+        entries.retain(|(path, _)| {
+            let name = path
+                .file_name()
+                .map(|n| n.to_string_lossy().to_lowercase())
+                .unwrap_or_default();
+            let full_path = path.to_string_lossy().to_lowercase();
+            name.contains(&settings.filter) || full_path.contains(&settings.filter)
+        });
+    }
+
+    entries.retain(|(path, current)| {
+        current.is_some() || baseline.is_some_and(|baseline| baseline.node(path).is_some())
+    });
+
+    if let Some(cutoff) = settings
+        .older_than
+        .and_then(|age| SystemTime::now().checked_sub(age))
+    {
+        entries.retain(|(path, current)| {
+            current
+                .or_else(|| baseline?.node(path))
+                .and_then(|node| node.age.modified)
+                .is_some_and(|modified| modified < cutoff)
+        });
+    }
+
+    if let Some(owner) = settings.owner_filter {
+        entries.retain(|(path, _)| owned(path, owner));
+    }
+
+    let metric = settings.metric;
+    let bytes = |current: Option<&NodeStats>| current.map_or(0, |node| node.metric_bytes(metric));
+    let max_bytes = entries
+        .iter()
+        .filter_map(|(_, current)| *current)
+        .map(|node| node.metric_bytes(metric))
+        .max();
+
+    let paths = match settings.sort_mode {
+        SortMode::SizeDesc => sort_by_key(entries, |_, current| Reverse(bytes(current))),
+        SortMode::SizeAsc => sort_by_key(entries, |_, current| bytes(current)),
+        SortMode::Name => sort_by_key(entries, |path, _| {
+            path.file_name()
+                .map(|name| name.to_string_lossy().to_lowercase())
+                .unwrap_or_default()
+        }),
+        SortMode::OldestFirst => sort_by_key(entries, |_, current| {
+            // Entries without a known modification time go last.
+            let modified = current.and_then(|node| node.age.modified);
+            (modified.is_none(), modified)
+        }),
+        SortMode::GrowthDesc => sort_by_key(entries, |path, current| {
+            Reverse(baseline.map_or(0, |baseline| baseline.delta(path, current, metric)))
+        }),
+    };
+
+    Listing { paths, max_bytes }
+}
+
+/// Orders entries by `key`, then by path, computing each key only once.
+fn sort_by_key<K: Ord>(
+    entries: Vec<(PathBuf, Option<&NodeStats>)>,
+    key: impl Fn(&Path, Option<&NodeStats>) -> K,
+) -> Vec<PathBuf> {
+    let mut keyed: Vec<(K, PathBuf)> = entries
+        .into_iter()
+        .map(|(path, current)| (key(&path, current), path))
+        .collect();
+    keyed.sort_unstable();
+    keyed.into_iter().map(|(_, path)| path).collect()
+}
+
+/// Listings by directory, kept until the directory or a sibling changes or
+/// the settings they were built with do.
+#[derive(Debug, Default)]
+pub struct ListingCache {
+    settings: Option<ListingSettings>,
+    by_dir: HashMap<PathBuf, Rc<Listing>>,
+}
+
+impl ListingCache {
+    /// The cached listing of `dir` for `settings`, built with `build` when
+    /// there is none.
+    pub fn get_or_build(
+        &mut self,
+        dir: &Path,
+        settings: &ListingSettings,
+        build: impl FnOnce() -> Listing,
+    ) -> Rc<Listing> {
+        if self.settings.as_ref() != Some(settings) {
+            self.by_dir.clear();
+            self.settings = Some(settings.clone());
+        }
+        if let Some(listing) = self.by_dir.get(dir) {
+            return Rc::clone(listing);
+        }
+        let listing = Rc::new(build());
+        self.by_dir.insert(dir.to_path_buf(), Rc::clone(&listing));
+        listing
+    }
+
+    /// Drops the listings an update of `path` can change: its parent's, where
+    /// it is sorted, and its own, which shows baseline entries only once the
+    /// directory finished scanning.
+    pub fn invalidate(&mut self, path: &Path) {
+        self.by_dir.remove(path);
+        if let Some(parent) = path.parent() {
+            self.by_dir.remove(parent);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::SystemTime;
+
+    use super::*;
+    use crate::model::{AgeSummary, FsEntryKind, NodeSummary};
+
+    fn node(path: &str, bytes: u64) -> NodeSummary {
+        NodeSummary {
+            path: PathBuf::from(path),
+            kind: FsEntryKind::File,
+            apparent_bytes: bytes,
+            allocated_bytes: bytes,
+            naive_apparent_bytes: bytes,
+            naive_allocated_bytes: bytes,
+            children_count: 0,
+            excluded_entries: 0,
+            age: AgeSummary::default(),
+            owner: None,
+            is_complete: true,
+            last_updated: SystemTime::now(),
+        }
+    }
+
+    fn settings(sort_mode: SortMode, filter: &str) -> ListingSettings {
+        ListingSettings {
+            sort_mode,
+            metric: SizeMetric::Apparent,
+            filter: filter.to_string(),
+            older_than: None,
+            owner_filter: None,
+            owners_generation: 0,
+        }
+    }
+
+    fn names(listing: &Listing) -> Vec<String> {
+        listing
+            .paths
+            .iter()
+            .map(|path| path.file_name().unwrap().to_string_lossy().into_owned())
+            .collect()
+    }
+
+    #[test]
+    fn filters_and_sorts_children() {
+        let mut nodes = NodeStore::new();
+        for (path, bytes) in [
+            ("/srv", 60),
+            ("/srv/b.log", 10),
+            ("/srv/Archive", 30),
+            ("/srv/c.log", 20),
+        ] {
+            nodes.upsert(node(path, bytes));
+        }
+        let list = |settings: &ListingSettings| {
+            list_children(&nodes, None, Path::new("/srv"), settings, |_, _| true)
+        };
+
+        let by_size = list(&settings(SortMode::SizeDesc, ""));
+        assert_eq!(names(&by_size), ["Archive", "c.log", "b.log"]);
+        assert_eq!(by_size.max_bytes, Some(30));
+        assert_eq!(
+            names(&list(&settings(SortMode::SizeAsc, ".log"))),
+            ["b.log", "c.log"]
+        );
+        assert_eq!(
+            names(&list(&settings(SortMode::Name, ""))),
+            ["Archive", "b.log", "c.log"]
+        );
+    }
+
+    #[test]
+    fn caches_until_invalidated_or_settings_change() {
+        let mut cache = ListingCache::default();
+        let dir = Path::new("/srv");
+        let by_size = settings(SortMode::SizeDesc, "");
+        let built = |paths: &[&str]| Listing {
+            paths: paths.iter().map(PathBuf::from).collect(),
+            max_bytes: None,
+        };
+
+        let first = cache.get_or_build(dir, &by_size, || built(&["/srv/a"]));
+        let cached = cache.get_or_build(dir, &by_size, || unreachable!("listing is cached"));
+        assert!(Rc::ptr_eq(&first, &cached));
+
+        cache.invalidate(Path::new("/srv/b"));
+        let rebuilt = cache.get_or_build(dir, &by_size, || built(&["/srv/a", "/srv/b"]));
+        assert_eq!(rebuilt.paths.len(), 2);
+
+        let by_name = settings(SortMode::Name, "");
+        let renamed = cache.get_or_build(dir, &by_name, || built(&[]));
+        assert!(renamed.paths.is_empty());
+    }
+}
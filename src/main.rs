@@ -1,12 +1,43 @@
 use anyhow::Context;
-use clap::Parser;
+use clap::{CommandFactory, FromArgMatches};
 
 use storagescope::app::App;
 use storagescope::cli::{Cli, Config};
+use storagescope::report::{ScanReport, collect_scan, write_report};
+use storagescope::snapshot::{Snapshot, save_snapshot};
 
 fn main() -> anyhow::Result<()> {
-    let cli = Cli::parse();
-    let config = Config::from_cli(cli).context("failed to build configuration")?;
+    let matches = Cli::command().get_matches();
+    let cli = Cli::from_arg_matches(&matches).unwrap_or_else(|error| error.exit());
+    let config = Config::from_cli(cli, &matches).context("failed to build configuration")?;
+
+    if let Some(report_options) = &config.report {
+        // Like `du --max-depth`, the depth limits what is printed, not what is counted.
+        let mut scan_options = config.scan_options.clone();
+        scan_options.max_depth = None;
+        let report = match &config.snapshot {
+            Some(snapshot) => ScanReport::from_snapshot(snapshot),
+            None => collect_scan(scan_options.clone()).context("headless scan failed")?,
+        };
+        if let Some(path) = &config.save_snapshot {
+            let snapshot = Snapshot::from_tree(scan_options, &report.root, &report.nodes);
+            save_snapshot(path, &snapshot).context("failed to save snapshot")?;
+        }
+        let stdout = std::io::stdout();
+        let mut out = stdout.lock();
+        write_report(
+            &mut out,
+            &report,
+            report_options,
+            config.initial_metric,
+            config.scan_options.max_depth,
+        )
+        .context("failed to write report")?;
+        if report.warnings > 0 {
+            eprintln!("storagescope: {} warnings during scan", report.warnings);
+        }
+        return Ok(());
+    }
 
     let mut app = App::new(config);
     app.run().context("application runtime failed")?;
@@ -1,6 +1,10 @@
+use std::ffi::OsString;
 use std::fmt;
 use std::path::PathBuf;
-use std::time::SystemTime;
+use std::time::{Duration, SystemTime};
+
+use crate::filetypes::TypeTally;
+use crate::owners::{Owner, OwnerTally};
 
 #[derive(Debug, Clone, Copy, Eq, PartialEq)]
 pub enum SizeMetric {
@@ -44,24 +48,196 @@ impl fmt::Display for FsEntryKind {
     }
 }
 
+/// Files not modified or accessed for this long count as stale unless `--older-than` says otherwise.
+pub const DEFAULT_STALE_AFTER: Duration = Duration::from_secs(180 * 86_400);
+
+/// Timestamps and stale-byte totals for an entry and everything below it.
+#[derive(Debug, Clone, Copy, Default, Eq, PartialEq)]
+pub struct AgeSummary {
+    /// Newest modification time of the entry or any descendant.
+    pub modified: Option<SystemTime>,
+    /// Newest access time of any file at or below the entry; directories' own
+    /// access times are skipped because listing them updates it.
+    pub accessed: Option<SystemTime>,
+    /// Bytes of files neither modified nor accessed within `ScanOptions::stale_after`.
+    pub stale_apparent_bytes: u64,
+    pub stale_allocated_bytes: u64,
+}
+
+impl AgeSummary {
+    pub fn stale_metric_bytes(&self, metric: SizeMetric) -> u64 {
+        match metric {
+            SizeMetric::Allocated => self.stale_allocated_bytes,
+            SizeMetric::Apparent => self.stale_apparent_bytes,
+        }
+    }
+
+    /// Most recent modification or access.
+    pub fn last_touched(&self) -> Option<SystemTime> {
+        newest(self.modified, self.accessed)
+    }
+
+    /// Folds a descendant in: newest timestamps win and stale bytes add up.
+    pub fn merge(&mut self, other: &AgeSummary) {
+        self.modified = newest(self.modified, other.modified);
+        self.accessed = newest(self.accessed, other.accessed);
+        self.stale_apparent_bytes = self
+            .stale_apparent_bytes
+            .saturating_add(other.stale_apparent_bytes);
+        self.stale_allocated_bytes = self
+            .stale_allocated_bytes
+            .saturating_add(other.stale_allocated_bytes);
+    }
+
+    /// Removes a descendant's stale bytes; timestamps are kept since an older
+    /// newest time cannot be recovered without rescanning.
+    pub fn subtract(&mut self, other: &AgeSummary) {
+        self.stale_apparent_bytes = self
+            .stale_apparent_bytes
+            .saturating_sub(other.stale_apparent_bytes);
+        self.stale_allocated_bytes = self
+            .stale_allocated_bytes
+            .saturating_sub(other.stale_allocated_bytes);
+    }
+}
+
+fn newest(a: Option<SystemTime>, b: Option<SystemTime>) -> Option<SystemTime> {
+    match (a, b) {
+        (Some(a), Some(b)) => Some(a.max(b)),
+        (a, b) => a.or(b),
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct NodeSummary {
     pub path: PathBuf,
     pub kind: FsEntryKind,
     pub apparent_bytes: u64,
     pub allocated_bytes: u64,
+    /// Apparent size counting every hardlink path separately.
+    pub naive_apparent_bytes: u64,
+    /// Allocated size counting every hardlink path separately.
+    pub naive_allocated_bytes: u64,
     pub children_count: u64,
+    /// Direct children skipped by include/exclude patterns; their size is unknown.
+    pub excluded_entries: u64,
+    pub age: AgeSummary,
+    /// Owner of the entry itself; unknown off Unix and in older snapshots.
+    pub owner: Option<Owner>,
     pub is_complete: bool,
     pub last_updated: SystemTime,
 }
 
 impl NodeSummary {
+    pub fn from_parts(path: PathBuf, stats: NodeStats) -> Self {
+        Self {
+            path,
+            kind: stats.kind,
+            apparent_bytes: stats.apparent_bytes,
+            allocated_bytes: stats.allocated_bytes,
+            naive_apparent_bytes: stats.naive_apparent_bytes,
+            naive_allocated_bytes: stats.naive_allocated_bytes,
+            children_count: stats.children_count,
+            excluded_entries: stats.excluded_entries,
+            age: stats.age,
+            owner: stats.owner,
+            is_complete: stats.is_complete,
+            last_updated: stats.last_updated,
+        }
+    }
+
+    /// Everything but the path, as kept by `NodeStore`.
+    pub fn stats(&self) -> NodeStats {
+        NodeStats {
+            kind: self.kind,
+            apparent_bytes: self.apparent_bytes,
+            allocated_bytes: self.allocated_bytes,
+            naive_apparent_bytes: self.naive_apparent_bytes,
+            naive_allocated_bytes: self.naive_allocated_bytes,
+            children_count: self.children_count,
+            excluded_entries: self.excluded_entries,
+            age: self.age,
+            owner: self.owner,
+            is_complete: self.is_complete,
+            last_updated: self.last_updated,
+        }
+    }
+
     pub fn metric_bytes(&self, metric: SizeMetric) -> u64 {
         match metric {
             SizeMetric::Allocated => self.allocated_bytes,
             SizeMetric::Apparent => self.apparent_bytes,
         }
     }
+
+    /// Bytes reachable through this node that were already counted via another hardlink.
+    pub fn shared_bytes(&self, metric: SizeMetric) -> u64 {
+        self.stats().shared_bytes(metric)
+    }
+}
+
+/// A `NodeSummary` without its path; the tree it lives in knows where it is.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub struct NodeStats {
+    pub kind: FsEntryKind,
+    pub apparent_bytes: u64,
+    pub allocated_bytes: u64,
+    pub naive_apparent_bytes: u64,
+    pub naive_allocated_bytes: u64,
+    pub children_count: u64,
+    pub excluded_entries: u64,
+    pub age: AgeSummary,
+    pub owner: Option<Owner>,
+    pub is_complete: bool,
+    pub last_updated: SystemTime,
+}
+
+impl NodeStats {
+    pub fn metric_bytes(&self, metric: SizeMetric) -> u64 {
+        match metric {
+            SizeMetric::Allocated => self.allocated_bytes,
+            SizeMetric::Apparent => self.apparent_bytes,
+        }
+    }
+
+    pub fn naive_metric_bytes(&self, metric: SizeMetric) -> u64 {
+        match metric {
+            SizeMetric::Allocated => self.naive_allocated_bytes,
+            SizeMetric::Apparent => self.naive_apparent_bytes,
+        }
+    }
+
+    /// Bytes reachable through this node that were already counted via another hardlink.
+    pub fn shared_bytes(&self, metric: SizeMetric) -> u64 {
+        self.naive_metric_bytes(metric)
+            .saturating_sub(self.metric_bytes(metric))
+    }
+
+    /// Adds a descendant's byte totals, e.g. after it grew or appeared.
+    pub fn add_totals(&mut self, added: &NodeStats) {
+        self.apparent_bytes = self.apparent_bytes.saturating_add(added.apparent_bytes);
+        self.allocated_bytes = self.allocated_bytes.saturating_add(added.allocated_bytes);
+        self.naive_apparent_bytes = self
+            .naive_apparent_bytes
+            .saturating_add(added.naive_apparent_bytes);
+        self.naive_allocated_bytes = self
+            .naive_allocated_bytes
+            .saturating_add(added.naive_allocated_bytes);
+        self.age.merge(&added.age);
+    }
+
+    /// Removes a descendant's byte totals, e.g. after it was deleted.
+    pub fn subtract_totals(&mut self, removed: &NodeStats) {
+        self.apparent_bytes = self.apparent_bytes.saturating_sub(removed.apparent_bytes);
+        self.allocated_bytes = self.allocated_bytes.saturating_sub(removed.allocated_bytes);
+        self.naive_apparent_bytes = self
+            .naive_apparent_bytes
+            .saturating_sub(removed.naive_apparent_bytes);
+        self.naive_allocated_bytes = self
+            .naive_allocated_bytes
+            .saturating_sub(removed.naive_allocated_bytes);
+        self.age.subtract(&removed.age);
+    }
 }
 
 #[derive(Debug, Clone, Copy, Eq, PartialEq)]
@@ -69,6 +245,10 @@ pub enum SortMode {
     SizeDesc,
     SizeAsc,
     Name,
+    /// Least recently modified first.
+    OldestFirst,
+    /// Largest growth relative to a baseline snapshot first.
+    GrowthDesc,
 }
 
 impl SortMode {
@@ -76,7 +256,9 @@ impl SortMode {
         match self {
             Self::SizeDesc => Self::SizeAsc,
             Self::SizeAsc => Self::Name,
-            Self::Name => Self::SizeDesc,
+            Self::Name => Self::OldestFirst,
+            Self::OldestFirst => Self::GrowthDesc,
+            Self::GrowthDesc => Self::SizeDesc,
         }
     }
 
@@ -85,6 +267,118 @@ impl SortMode {
             Self::SizeDesc => "size-desc",
             Self::SizeAsc => "size-asc",
             Self::Name => "name",
+            Self::OldestFirst => "oldest",
+            Self::GrowthDesc => "growth",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        [
+            Self::SizeDesc,
+            Self::SizeAsc,
+            Self::Name,
+            Self::OldestFirst,
+            Self::GrowthDesc,
+        ]
+        .into_iter()
+        .find(|mode| mode.as_str() == name)
+    }
+}
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash)]
+pub enum TableColumn {
+    Name,
+    Kind,
+    Size,
+    Modified,
+    Relative,
+    Path,
+}
+
+impl TableColumn {
+    pub const ALL: [Self; 6] = [
+        Self::Name,
+        Self::Kind,
+        Self::Size,
+        Self::Modified,
+        Self::Relative,
+        Self::Path,
+    ];
+
+    /// Looks a column up by its lowercase label, as used in the config file.
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL
+            .into_iter()
+            .find(|column| ColumnVisibility::label(*column).eq_ignore_ascii_case(name))
+    }
+}
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub struct ColumnVisibility {
+    pub name: bool,
+    pub kind: bool,
+    pub size: bool,
+    pub modified: bool,
+    pub relative: bool,
+    pub path: bool,
+}
+
+impl ColumnVisibility {
+    pub fn all_visible() -> Self {
+        Self {
+            name: true,
+            kind: true,
+            size: true,
+            modified: true,
+            relative: true,
+            path: true,
+        }
+    }
+
+    pub fn is_visible(self, column: TableColumn) -> bool {
+        match column {
+            TableColumn::Name => self.name,
+            TableColumn::Kind => self.kind,
+            TableColumn::Size => self.size,
+            TableColumn::Modified => self.modified,
+            TableColumn::Relative => self.relative,
+            TableColumn::Path => self.path,
+        }
+    }
+
+    pub fn set_visible(&mut self, column: TableColumn, visible: bool) {
+        match column {
+            TableColumn::Name => self.name = visible,
+            TableColumn::Kind => self.kind = visible,
+            TableColumn::Size => self.size = visible,
+            TableColumn::Modified => self.modified = visible,
+            TableColumn::Relative => self.relative = visible,
+            TableColumn::Path => self.path = visible,
+        }
+    }
+
+    pub fn visible_count(self) -> usize {
+        [
+            self.name,
+            self.kind,
+            self.size,
+            self.modified,
+            self.relative,
+            self.path,
+        ]
+        .into_iter()
+        .filter(|visible| *visible)
+        .count()
+    }
+
+    pub fn label(column: TableColumn) -> &'static str {
+        match column {
+            TableColumn::Name => "Name",
+            TableColumn::Kind => "Kind",
+            TableColumn::Size => "Size",
+            TableColumn::Modified => "Modified",
+            TableColumn::Relative => "Relative",
+            TableColumn::Path => "Path",
         }
     }
 }
@@ -97,6 +391,14 @@ pub struct ScanOptions {
     pub show_hidden: bool,
     pub show_files: bool,
     pub max_depth: Option<usize>,
+    /// Number of traversal threads; 1 keeps the single-threaded walk.
+    pub threads: usize,
+    pub exclude_patterns: Vec<String>,
+    pub include_patterns: Vec<String>,
+    /// Count filtered entries on their parent instead of dropping them silently.
+    pub report_excluded: bool,
+    /// Files untouched for longer than this are counted as stale bytes.
+    pub stale_after: Duration,
 }
 
 #[derive(Debug, Clone, Default)]
@@ -107,12 +409,45 @@ pub struct ScanProgress {
     pub allocated_bytes_seen: u64,
 }
 
+/// Updates from a scan or the watcher. Entries are named by full path; the app
+/// interns each one into its `NodeStore` as the event arrives.
 #[derive(Debug, Clone)]
 pub enum ScanEvent {
-    Reset { root: PathBuf },
+    Reset {
+        root: PathBuf,
+    },
     NodeUpdated(NodeSummary),
+    /// Extension totals of the regular files directly inside a finished directory.
+    FileTypes {
+        path: PathBuf,
+        tally: TypeTally,
+    },
+    /// Per-user and per-group totals of a finished directory's own inode and
+    /// its non-directory children.
+    Owners {
+        path: PathBuf,
+        tally: OwnerTally,
+    },
+    /// Regular files directly inside a finished directory that duplicate search
+    /// may hash; only sent when `show_files` keeps them out of `NodeUpdated`.
+    DuplicateCandidates {
+        path: PathBuf,
+        files: Vec<NodeSummary>,
+    },
+    /// A regular file that entered the scan's largest-files set; reported even
+    /// when `show_files` keeps files out of the directory view.
+    LargeFile(NodeSummary),
+    /// A directory was listed again; its previously reported children that
+    /// are missing from `children` no longer exist.
+    Relisted {
+        path: PathBuf,
+        children: Vec<OsString>,
+    },
     Progress(ScanProgress),
-    Warning { path: PathBuf, message: String },
+    Warning {
+        path: PathBuf,
+        message: String,
+    },
     Complete(ScanProgress),
     Error(String),
     Cancelled,
@@ -128,10 +463,37 @@ mod tests {
         assert_eq!(SizeMetric::Apparent.toggle(), SizeMetric::Allocated);
     }
 
+    #[test]
+    fn age_merge_keeps_newest_times_and_sums_stale_bytes() {
+        let old = SystemTime::UNIX_EPOCH + Duration::from_secs(1_000);
+        let new = old + Duration::from_secs(500);
+        let mut dir = AgeSummary {
+            modified: Some(old),
+            ..AgeSummary::default()
+        };
+        let file = AgeSummary {
+            modified: Some(new),
+            accessed: Some(old),
+            stale_apparent_bytes: 10,
+            stale_allocated_bytes: 12,
+        };
+
+        dir.merge(&file);
+        assert_eq!(dir.modified, Some(new));
+        assert_eq!(dir.last_touched(), Some(new));
+        assert_eq!(dir.stale_metric_bytes(SizeMetric::Allocated), 12);
+
+        dir.subtract(&file);
+        assert_eq!(dir.stale_apparent_bytes, 0);
+        assert_eq!(dir.modified, Some(new));
+    }
+
     #[test]
     fn sort_mode_cycles() {
         assert_eq!(SortMode::SizeDesc.cycle(), SortMode::SizeAsc);
         assert_eq!(SortMode::SizeAsc.cycle(), SortMode::Name);
-        assert_eq!(SortMode::Name.cycle(), SortMode::SizeDesc);
+        assert_eq!(SortMode::Name.cycle(), SortMode::OldestFirst);
+        assert_eq!(SortMode::OldestFirst.cycle(), SortMode::GrowthDesc);
+        assert_eq!(SortMode::GrowthDesc.cycle(), SortMode::SizeDesc);
     }
 }
@@ -0,0 +1,317 @@
+//! Per-owner usage: uid/gid tallies collected by the scanner and name lookup
+//! through `/etc/passwd` and `/etc/group`.
+
+use std::collections::HashMap;
+use std::fmt::{Display, Formatter};
+use std::fs;
+use std::path::Path;
+
+use crate::filetypes::BreakdownSort;
+use crate::model::SizeMetric;
+
+const PASSWD_PATH: &str = "/etc/passwd";
+const GROUP_PATH: &str = "/etc/group";
+
+/// Numeric owner of a filesystem entry.
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash)]
+pub struct Owner {
+    pub uid: u32,
+    pub gid: u32,
+}
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash)]
+pub enum OwnerKind {
+    User,
+    Group,
+}
+
+impl OwnerKind {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::User => "user",
+            Self::Group => "group",
+        }
+    }
+}
+
+impl Display for OwnerKind {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// A single user or group, e.g. the one the main table is filtered to.
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash)]
+pub struct OwnerId {
+    pub kind: OwnerKind,
+    pub id: u32,
+}
+
+impl OwnerId {
+    pub fn matches(self, owner: Owner) -> bool {
+        match self.kind {
+            OwnerKind::User => owner.uid == self.id,
+            OwnerKind::Group => owner.gid == self.id,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, Eq, PartialEq)]
+pub struct OwnerStat {
+    pub entries: u64,
+    pub apparent_bytes: u64,
+    pub allocated_bytes: u64,
+}
+
+impl OwnerStat {
+    pub fn metric_bytes(&self, metric: SizeMetric) -> u64 {
+        match metric {
+            SizeMetric::Allocated => self.allocated_bytes,
+            SizeMetric::Apparent => self.apparent_bytes,
+        }
+    }
+
+    fn merge(&mut self, other: &OwnerStat) {
+        self.entries = self.entries.saturating_add(other.entries);
+        self.apparent_bytes = self.apparent_bytes.saturating_add(other.apparent_bytes);
+        self.allocated_bytes = self.allocated_bytes.saturating_add(other.allocated_bytes);
+    }
+}
+
+/// Bytes and entry counts per uid and per gid.
+#[derive(Debug, Clone, Default, Eq, PartialEq)]
+pub struct OwnerTally {
+    by_user: HashMap<u32, OwnerStat>,
+    by_group: HashMap<u32, OwnerStat>,
+}
+
+impl OwnerTally {
+    pub fn add_entry(&mut self, owner: Owner, apparent_bytes: u64, allocated_bytes: u64) {
+        let stat = OwnerStat {
+            entries: 1,
+            apparent_bytes,
+            allocated_bytes,
+        };
+        self.by_user.entry(owner.uid).or_default().merge(&stat);
+        self.by_group.entry(owner.gid).or_default().merge(&stat);
+    }
+
+    /// Takes back an entry counted by `add_entry`, e.g. after it was deleted.
+    pub fn remove_entry(&mut self, owner: Owner, apparent_bytes: u64, allocated_bytes: u64) {
+        for (owners, id) in [
+            (&mut self.by_user, owner.uid),
+            (&mut self.by_group, owner.gid),
+        ] {
+            if let Some(stat) = owners.get_mut(&id) {
+                stat.entries = stat.entries.saturating_sub(1);
+                stat.apparent_bytes = stat.apparent_bytes.saturating_sub(apparent_bytes);
+                stat.allocated_bytes = stat.allocated_bytes.saturating_sub(allocated_bytes);
+                if stat.entries == 0 {
+                    owners.remove(&id);
+                }
+            }
+        }
+    }
+
+    pub fn merge(&mut self, other: &OwnerTally) {
+        for (uid, stat) in &other.by_user {
+            self.by_user.entry(*uid).or_default().merge(stat);
+        }
+        for (gid, stat) in &other.by_group {
+            self.by_group.entry(*gid).or_default().merge(stat);
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.by_user.is_empty()
+    }
+
+    pub fn stat(&self, owner: OwnerId) -> OwnerStat {
+        self.owners(owner.kind)
+            .get(&owner.id)
+            .copied()
+            .unwrap_or_default()
+    }
+
+    pub fn len(&self, kind: OwnerKind) -> usize {
+        self.owners(kind).len()
+    }
+
+    fn owners(&self, kind: OwnerKind) -> &HashMap<u32, OwnerStat> {
+        match kind {
+            OwnerKind::User => &self.by_user,
+            OwnerKind::Group => &self.by_group,
+        }
+    }
+}
+
+/// User and group names by id; ids without an entry are displayed numerically.
+#[derive(Debug, Clone, Default)]
+pub struct OwnerNames {
+    users: HashMap<u32, String>,
+    groups: HashMap<u32, String>,
+}
+
+impl OwnerNames {
+    /// Reads the local account databases; missing or unreadable files leave ids unnamed.
+    pub fn load() -> Self {
+        let read = |path: &str| fs::read_to_string(Path::new(path)).unwrap_or_default();
+        Self {
+            users: parse_id_database(&read(PASSWD_PATH)),
+            groups: parse_id_database(&read(GROUP_PATH)),
+        }
+    }
+
+    pub fn name(&self, owner: OwnerId) -> String {
+        let names = match owner.kind {
+            OwnerKind::User => &self.users,
+            OwnerKind::Group => &self.groups,
+        };
+        names
+            .get(&owner.id)
+            .cloned()
+            .unwrap_or_else(|| owner.id.to_string())
+    }
+}
+
+/// Parses `name:password:id:...` lines as found in both `/etc/passwd` and
+/// `/etc/group`; the first name listed for an id wins.
+pub fn parse_id_database(text: &str) -> HashMap<u32, String> {
+    let mut names = HashMap::new();
+    for line in text.lines() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let mut fields = line.split(':');
+        let (Some(name), Some(_), Some(id)) = (fields.next(), fields.next(), fields.next()) else {
+            continue;
+        };
+        if let Ok(id) = id.parse::<u32>() {
+            names.entry(id).or_insert_with(|| name.to_string());
+        }
+    }
+    names
+}
+
+/// One row of the owner breakdown.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct OwnerEntry {
+    pub owner: OwnerId,
+    pub label: String,
+    pub stat: OwnerStat,
+}
+
+pub fn owner_breakdown(
+    tally: &OwnerTally,
+    kind: OwnerKind,
+    sort: BreakdownSort,
+    metric: SizeMetric,
+    names: &OwnerNames,
+) -> Vec<OwnerEntry> {
+    let mut entries: Vec<OwnerEntry> = tally
+        .owners(kind)
+        .iter()
+        .map(|(id, stat)| {
+            let owner = OwnerId { kind, id: *id };
+            OwnerEntry {
+                owner,
+                label: names.name(owner),
+                stat: *stat,
+            }
+        })
+        .collect();
+
+    match sort {
+        BreakdownSort::SizeDesc => entries.sort_by(|a, b| {
+            b.stat
+                .metric_bytes(metric)
+                .cmp(&a.stat.metric_bytes(metric))
+                .then_with(|| a.label.cmp(&b.label))
+        }),
+        BreakdownSort::CountDesc => entries.sort_by(|a, b| {
+            b.stat
+                .entries
+                .cmp(&a.stat.entries)
+                .then_with(|| a.label.cmp(&b.label))
+        }),
+        BreakdownSort::Name => entries.sort_by(|a, b| a.label.cmp(&b.label)),
+    }
+    entries
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_passwd_and_group_lines() {
+        let passwd = "root:x:0:0:root:/root:/bin/bash\n\
+                      # comment\n\
+                      alice:x:1000:1000:Alice:/home/alice:/bin/zsh\n\
+                      broken-line\n\
+                      toor:x:0:0::/root:/bin/sh\n";
+        let users = parse_id_database(passwd);
+        assert_eq!(users[&0], "root");
+        assert_eq!(users[&1000], "alice");
+        assert_eq!(users.len(), 2);
+
+        let groups = parse_id_database("wheel:x:10:alice,bob\nbuild:x:2000:\n");
+        assert_eq!(groups[&2000], "build");
+    }
+
+    #[test]
+    fn tallies_users_and_groups_and_sorts_breakdown() {
+        let mut tally = OwnerTally::default();
+        tally.add_entry(Owner { uid: 0, gid: 0 }, 10, 12);
+        tally.add_entry(
+            Owner {
+                uid: 1000,
+                gid: 2000,
+            },
+            500,
+            512,
+        );
+
+        let mut nested = OwnerTally::default();
+        nested.add_entry(
+            Owner {
+                uid: 1001,
+                gid: 2000,
+            },
+            30,
+            32,
+        );
+        tally.merge(&nested);
+
+        let names = OwnerNames {
+            users: HashMap::from([(0, "root".to_string()), (1000, "alice".to_string())]),
+            groups: HashMap::new(),
+        };
+        let users = owner_breakdown(
+            &tally,
+            OwnerKind::User,
+            BreakdownSort::SizeDesc,
+            SizeMetric::Apparent,
+            &names,
+        );
+        let labels: Vec<&str> = users.iter().map(|entry| entry.label.as_str()).collect();
+        assert_eq!(labels, ["alice", "1001", "root"]);
+
+        let build = OwnerId {
+            kind: OwnerKind::Group,
+            id: 2000,
+        };
+        assert_eq!(tally.stat(build).entries, 2);
+        assert_eq!(tally.stat(build).allocated_bytes, 544);
+        assert!(build.matches(Owner {
+            uid: 1001,
+            gid: 2000
+        }));
+        assert_eq!(tally.len(OwnerKind::Group), 2);
+
+        tally.remove_entry(Owner { uid: 0, gid: 0 }, 10, 12);
+        assert_eq!(tally.len(OwnerKind::User), 2);
+        assert_eq!(tally.len(OwnerKind::Group), 1);
+    }
+}
@@ -0,0 +1,269 @@
+use std::path::Path;
+
+/// A shell-style glob: `*` and `?` stay within one path segment, `**` crosses
+/// separators and `[a-z]` / `[!x]` match character classes.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct GlobPattern {
+    source: String,
+    tokens: Vec<Token>,
+    match_full_path: bool,
+}
+
+#[derive(Debug, Clone, Eq, PartialEq)]
+enum Token {
+    Literal(char),
+    AnyChar,
+    AnySegment,
+    AnyPath,
+    AnyDirs,
+    Class {
+        negated: bool,
+        ranges: Vec<(char, char)>,
+    },
+}
+
+impl GlobPattern {
+    pub fn new(source: &str) -> Self {
+        let trimmed = source.trim_end_matches('/');
+        let trimmed = if trimmed.is_empty() { source } else { trimmed };
+        Self {
+            source: source.to_string(),
+            tokens: tokenize(trimmed),
+            match_full_path: trimmed.contains('/'),
+        }
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.source
+    }
+
+    /// Patterns without a `/` match the entry name; others match the absolute path
+    /// or the path relative to the scan root.
+    pub fn matches(&self, path: &Path, scan_root: &Path) -> bool {
+        if !self.match_full_path {
+            let name = path
+                .file_name()
+                .map(|name| name.to_string_lossy())
+                .unwrap_or_default();
+            return self.matches_str(&name);
+        }
+
+        if self.matches_str(&path.to_string_lossy()) {
+            return true;
+        }
+
+        path.strip_prefix(scan_root)
+            .map(|relative| self.matches_str(&relative.to_string_lossy()))
+            .unwrap_or(false)
+    }
+
+    fn matches_str(&self, text: &str) -> bool {
+        let chars: Vec<char> = text.chars().collect();
+        match_tokens(&self.tokens, &chars)
+    }
+}
+
+fn tokenize(source: &str) -> Vec<Token> {
+    let chars: Vec<char> = source.chars().collect();
+    let mut tokens = Vec::new();
+    let mut idx = 0;
+
+    while idx < chars.len() {
+        match chars[idx] {
+            '*' if chars.get(idx + 1) == Some(&'*') => {
+                idx += 2;
+                // `**/` matches zero or more whole directories.
+                if chars.get(idx) == Some(&'/') {
+                    idx += 1;
+                    tokens.push(Token::AnyDirs);
+                } else {
+                    tokens.push(Token::AnyPath);
+                }
+                continue;
+            }
+            '*' => tokens.push(Token::AnySegment),
+            '?' => tokens.push(Token::AnyChar),
+            '[' => {
+                if let Some((token, next)) = parse_class(&chars, idx) {
+                    tokens.push(token);
+                    idx = next;
+                    continue;
+                }
+                tokens.push(Token::Literal('['));
+            }
+            '\\' if idx + 1 < chars.len() => {
+                idx += 1;
+                tokens.push(Token::Literal(chars[idx]));
+            }
+            other => tokens.push(Token::Literal(other)),
+        }
+        idx += 1;
+    }
+
+    tokens
+}
+
+fn parse_class(chars: &[char], start: usize) -> Option<(Token, usize)> {
+    let mut idx = start + 1;
+    let negated = matches!(chars.get(idx), Some('!') | Some('^'));
+    if negated {
+        idx += 1;
+    }
+
+    let mut ranges = Vec::new();
+    let mut first = true;
+    while idx < chars.len() {
+        let ch = chars[idx];
+        if ch == ']' && !first {
+            return Some((Token::Class { negated, ranges }, idx + 1));
+        }
+        first = false;
+
+        if chars.get(idx + 1) == Some(&'-') && chars.get(idx + 2).is_some_and(|end| *end != ']') {
+            ranges.push((ch, chars[idx + 2]));
+            idx += 3;
+        } else {
+            ranges.push((ch, ch));
+            idx += 1;
+        }
+    }
+
+    // Unterminated classes are treated as a literal `[`.
+    None
+}
+
+fn match_tokens(tokens: &[Token], text: &[char]) -> bool {
+    let Some((token, rest)) = tokens.split_first() else {
+        return text.is_empty();
+    };
+
+    match token {
+        Token::Literal(expected) => {
+            text.first() == Some(expected) && match_tokens(rest, &text[1..])
+        }
+        Token::AnyChar => {
+            text.first().is_some_and(|ch| *ch != '/') && match_tokens(rest, &text[1..])
+        }
+        Token::Class { negated, ranges } => text.first().is_some_and(|ch| {
+            *ch != '/'
+                && ranges.iter().any(|(low, high)| low <= ch && ch <= high) != *negated
+                && match_tokens(rest, &text[1..])
+        }),
+        Token::AnySegment => {
+            for split in 0..=text.len() {
+                if match_tokens(rest, &text[split..]) {
+                    return true;
+                }
+                if text.get(split) == Some(&'/') {
+                    return false;
+                }
+            }
+            false
+        }
+        Token::AnyPath => (0..=text.len()).any(|split| match_tokens(rest, &text[split..])),
+        Token::AnyDirs => {
+            match_tokens(rest, text)
+                || text
+                    .iter()
+                    .enumerate()
+                    .any(|(idx, ch)| *ch == '/' && match_tokens(rest, &text[idx + 1..]))
+        }
+    }
+}
+
+/// Compiled include/exclude rules applied to directory entries before they are stat-ed.
+#[derive(Debug, Clone, Default)]
+pub struct PathFilter {
+    excludes: Vec<GlobPattern>,
+    includes: Vec<GlobPattern>,
+}
+
+impl PathFilter {
+    pub fn new(excludes: &[String], includes: &[String]) -> Self {
+        Self {
+            excludes: excludes.iter().map(|p| GlobPattern::new(p)).collect(),
+            includes: includes.iter().map(|p| GlobPattern::new(p)).collect(),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.excludes.is_empty() && self.includes.is_empty()
+    }
+
+    /// Excludes always win. Include patterns only restrict leaf entries so that
+    /// directories stay traversable while looking for matching files.
+    pub fn allows(&self, path: &Path, is_dir: bool, scan_root: &Path) -> bool {
+        if self
+            .excludes
+            .iter()
+            .any(|pattern| pattern.matches(path, scan_root))
+        {
+            return false;
+        }
+
+        is_dir
+            || self.includes.is_empty()
+            || self
+                .includes
+                .iter()
+                .any(|pattern| pattern.matches(path, scan_root))
+    }
+}
+
+/// Reads newline-separated patterns, ignoring blank lines and `#` comments.
+pub fn parse_pattern_file(content: &str) -> Vec<String> {
+    content
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .map(str::to_string)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use super::{GlobPattern, PathFilter, parse_pattern_file};
+
+    fn matches(pattern: &str, path: &str) -> bool {
+        GlobPattern::new(pattern).matches(Path::new(path), Path::new("/scan"))
+    }
+
+    #[test]
+    fn name_patterns_match_last_segment() {
+        assert!(matches("node_modules", "/scan/app/node_modules"));
+        assert!(matches("*.iso", "/scan/images/debian.iso"));
+        assert!(!matches("*.iso", "/scan/images/debian.iso.part"));
+        assert!(matches("cache-?", "/scan/cache-1"));
+        assert!(matches("[a-c]*.log", "/scan/b-server.log"));
+        assert!(!matches("[!a-c]*.log", "/scan/b-server.log"));
+    }
+
+    #[test]
+    fn path_patterns_match_absolute_or_relative() {
+        assert!(matches("/proc", "/proc"));
+        assert!(matches("build/cache", "/scan/build/cache"));
+        assert!(!matches("build/*", "/scan/build/cache/deep"));
+        assert!(matches("build/**", "/scan/build/cache/deep"));
+        assert!(matches("**/target", "/scan/a/b/target"));
+        assert!(matches("**/target", "/scan/target"));
+        assert!(!matches("**/target", "/scan/mytarget"));
+    }
+
+    #[test]
+    fn includes_only_restrict_files() {
+        let filter = PathFilter::new(&["*.tmp".to_string()], &["*.mp4".to_string()]);
+        let root = Path::new("/scan");
+        assert!(filter.allows(Path::new("/scan/videos"), true, root));
+        assert!(filter.allows(Path::new("/scan/videos/a.mp4"), false, root));
+        assert!(!filter.allows(Path::new("/scan/videos/a.txt"), false, root));
+        assert!(!filter.allows(Path::new("/scan/videos/a.tmp"), false, root));
+    }
+
+    #[test]
+    fn pattern_file_skips_comments_and_blanks() {
+        let parsed = parse_pattern_file("# caches\nnode_modules\n\n  .git  \n");
+        assert_eq!(parsed, vec!["node_modules".to_string(), ".git".to_string()]);
+    }
+}
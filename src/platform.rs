@@ -1,15 +1,24 @@
-use std::fs::Metadata;
-use std::path::Path;
+use std::ffi::{OsStr, OsString};
+use std::fs::{self, Metadata};
+use std::io;
+use std::path::{Path, PathBuf};
+#[cfg(target_os = "linux")]
+use std::sync::Arc;
+use std::time::SystemTime;
 
 #[cfg(windows)]
 use std::collections::hash_map::DefaultHasher;
 #[cfg(windows)]
-use std::ffi::OsStr;
-#[cfg(windows)]
 use std::hash::{Hash, Hasher};
 
+use crate::model::FsEntryKind;
+use crate::owners::Owner;
+
 pub type FilesystemId = u64;
 
+/// Device and inode pair identifying a file that has more than one hardlink.
+pub type InodeKey = (u64, u64);
+
 #[derive(Debug, Clone, Copy, Eq, PartialEq)]
 pub struct DiskUsage {
     pub total_bytes: u64,
@@ -17,7 +26,265 @@ pub struct DiskUsage {
     pub available_bytes: u64,
 }
 
-pub fn allocated_size(path: &Path, metadata: &Metadata) -> u64 {
+/// How the scanner lists directories and stats their entries.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum WalkBackend {
+    /// `std::fs::read_dir` plus a stat by full path for every entry.
+    Std,
+    /// Directory file descriptors: subdirectories opened with `openat`
+    /// relative to their parent, `getdents64` for the listing and `fstatat`
+    /// relative to the open directory, so nothing below the root resolves a
+    /// full path. Each admitted entry still takes one `fstatat`; the listing's
+    /// `d_type` only spares the stat of entries the filters reject.
+    #[cfg(target_os = "linux")]
+    Fd,
+    /// Like `Fd`, but stats through `statx` asking only for the fields the
+    /// scanner reads.
+    #[cfg(all(target_os = "linux", feature = "statx"))]
+    Statx,
+    /// `Statx` with each directory's stats submitted in batches through
+    /// io_uring, so high-latency filesystems (NFS, FUSE) work on many at once.
+    /// Falls back to one `statx` call per entry where io_uring is unavailable.
+    #[cfg(all(target_os = "linux", feature = "statx"))]
+    StatxBatch,
+}
+
+impl WalkBackend {
+    /// The backend scans use unless told otherwise: the batched `statx`
+    /// backend when built with the `statx` feature, the fastest portable one
+    /// for the platform otherwise.
+    pub fn preferred() -> Self {
+        #[cfg(all(target_os = "linux", feature = "statx"))]
+        return Self::StatxBatch;
+        #[cfg(all(target_os = "linux", not(feature = "statx")))]
+        return Self::Fd;
+        #[cfg(not(target_os = "linux"))]
+        return Self::Std;
+    }
+}
+
+impl Default for WalkBackend {
+    fn default() -> Self {
+        Self::preferred()
+    }
+}
+
+/// The parts of a stat result the scanner aggregates, independent of the
+/// backend that read them.
+#[derive(Debug, Clone)]
+pub struct EntryStat {
+    /// Type of the entry itself; never `Symlink` once a link was followed.
+    pub kind: FsEntryKind,
+    pub apparent_bytes: u64,
+    pub allocated_bytes: u64,
+    pub filesystem: Option<FilesystemId>,
+    /// Set for non-directories with more than one hardlink.
+    pub hardlink: Option<InodeKey>,
+    pub owner: Option<Owner>,
+    pub modified: Option<SystemTime>,
+    pub accessed: Option<SystemTime>,
+}
+
+impl EntryStat {
+    pub fn from_metadata(path: &Path, metadata: &Metadata) -> Self {
+        let file_type = metadata.file_type();
+        Self {
+            kind: kind_of(file_type),
+            apparent_bytes: metadata.len(),
+            allocated_bytes: allocated_size(metadata),
+            filesystem: filesystem_id(path, metadata),
+            hardlink: hardlink_key(metadata),
+            owner: owner(metadata),
+            modified: metadata.modified().ok(),
+            accessed: metadata.accessed().ok(),
+        }
+    }
+
+    pub fn is_dir(&self) -> bool {
+        self.kind == FsEntryKind::Dir
+    }
+
+    pub fn is_symlink(&self) -> bool {
+        self.kind == FsEntryKind::Symlink
+    }
+}
+
+fn kind_of(file_type: fs::FileType) -> FsEntryKind {
+    if file_type.is_dir() {
+        FsEntryKind::Dir
+    } else if file_type.is_symlink() {
+        FsEntryKind::Symlink
+    } else if file_type.is_file() {
+        FsEntryKind::File
+    } else {
+        FsEntryKind::Other
+    }
+}
+
+/// One name from a directory listing, before any stat.
+#[derive(Debug, Clone)]
+pub struct ListedEntry {
+    pub name: OsString,
+    /// Type hint from the listing itself; `None` when the filesystem leaves
+    /// `d_type` unset.
+    pub file_type: Option<FsEntryKind>,
+}
+
+/// A listed directory its subdirectories are opened relative to, so the walk
+/// resolves no full paths below the root and is not bounded by `PATH_MAX`.
+/// Only the descriptor backends hand these out.
+#[derive(Clone)]
+pub struct DirHandle {
+    #[cfg(target_os = "linux")]
+    lease: Arc<linux::Lease>,
+}
+
+/// A directory's device and inode, or its canonical path where the platform
+/// has no inode numbers.
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
+pub enum DirIdentity {
+    Inode(InodeKey),
+    Path(PathBuf),
+}
+
+/// An open directory that yields its entries and stats them by name.
+pub struct DirReader {
+    inner: DirReaderInner,
+}
+
+enum DirReaderInner {
+    Std {
+        path: PathBuf,
+        entries: fs::ReadDir,
+    },
+    #[cfg(target_os = "linux")]
+    Fd(linux::FdDir),
+}
+
+impl DirReader {
+    pub fn open(path: &Path, backend: WalkBackend) -> io::Result<Self> {
+        let inner = match backend {
+            WalkBackend::Std => DirReaderInner::Std {
+                path: path.to_path_buf(),
+                entries: fs::read_dir(path)?,
+            },
+            #[cfg(target_os = "linux")]
+            WalkBackend::Fd => {
+                DirReaderInner::Fd(linux::FdDir::open(path, linux::StatCall::Fstatat)?)
+            }
+            #[cfg(all(target_os = "linux", feature = "statx"))]
+            WalkBackend::Statx => {
+                DirReaderInner::Fd(linux::FdDir::open(path, linux::StatCall::Statx)?)
+            }
+            #[cfg(all(target_os = "linux", feature = "statx"))]
+            WalkBackend::StatxBatch => {
+                DirReaderInner::Fd(linux::FdDir::open(path, linux::StatCall::StatxBatch)?)
+            }
+        };
+        Ok(Self { inner })
+    }
+
+    /// Opens the subdirectory `path` relative to `parent`, the handle of the
+    /// directory it was listed in, or by its full path without one. A symlink
+    /// at `path` is only resolved when `follow` is set.
+    pub fn open_in(
+        parent: Option<&DirHandle>,
+        path: &Path,
+        follow: bool,
+        backend: WalkBackend,
+    ) -> io::Result<Self> {
+        #[cfg(target_os = "linux")]
+        if let (Some(parent), Some(name)) = (parent, path.file_name()) {
+            let inner = linux::FdDir::open_in(&parent.lease, name, follow)?;
+            return Ok(Self {
+                inner: DirReaderInner::Fd(inner),
+            });
+        }
+        let _ = (parent, follow);
+        Self::open(path, backend)
+    }
+
+    /// Handle to open this directory's subdirectories relative to; keeps the
+    /// directory open until every clone is dropped.
+    pub fn handle(&self) -> Option<DirHandle> {
+        match &self.inner {
+            DirReaderInner::Std { .. } => None,
+            #[cfg(target_os = "linux")]
+            DirReaderInner::Fd(dir) => Some(DirHandle { lease: dir.lease() }),
+        }
+    }
+
+    /// What this directory resolves to, for telling whether a followed
+    /// symlink leads back into a directory already walked. Read from the open
+    /// handle where there is one, so it works below `PATH_MAX` too.
+    pub fn identity(&self) -> io::Result<DirIdentity> {
+        match &self.inner {
+            #[cfg(unix)]
+            DirReaderInner::Std { path, .. } => {
+                use std::os::unix::fs::MetadataExt;
+                let metadata = fs::metadata(path)?;
+                Ok(DirIdentity::Inode((metadata.dev(), metadata.ino())))
+            }
+            #[cfg(not(unix))]
+            DirReaderInner::Std { path, .. } => fs::canonicalize(path).map(DirIdentity::Path),
+            #[cfg(target_os = "linux")]
+            DirReaderInner::Fd(dir) => dir.identity().map(DirIdentity::Inode),
+        }
+    }
+
+    /// Next entry, skipping `.` and `..`.
+    pub fn next_entry(&mut self) -> Option<io::Result<ListedEntry>> {
+        match &mut self.inner {
+            DirReaderInner::Std { entries, .. } => entries.next().map(|entry| {
+                entry.map(|entry| ListedEntry {
+                    file_type: entry.file_type().ok().map(kind_of),
+                    name: entry.file_name(),
+                })
+            }),
+            #[cfg(target_os = "linux")]
+            DirReaderInner::Fd(dir) => dir.next_entry(),
+        }
+    }
+
+    /// Stats the entry `name` of this directory, resolving it when it is a
+    /// symlink and `follow` is set.
+    pub fn stat(&self, name: &OsStr, follow: bool) -> io::Result<EntryStat> {
+        match &self.inner {
+            DirReaderInner::Std { path, .. } => {
+                let child = path.join(name);
+                let metadata = if follow {
+                    fs::metadata(&child)?
+                } else {
+                    fs::symlink_metadata(&child)?
+                };
+                Ok(EntryStat::from_metadata(&child, &metadata))
+            }
+            #[cfg(target_os = "linux")]
+            DirReaderInner::Fd(dir) => dir.stat(name, follow),
+        }
+    }
+
+    /// Stats several entries of this directory without following symlinks; the
+    /// results line up with `names`.
+    pub fn stat_many(&self, names: &[&OsStr]) -> Vec<io::Result<EntryStat>> {
+        match &self.inner {
+            DirReaderInner::Std { .. } => names.iter().map(|name| self.stat(name, false)).collect(),
+            #[cfg(target_os = "linux")]
+            DirReaderInner::Fd(dir) => dir.stat_many(names),
+        }
+    }
+
+    /// The listing's type hint for `entry`, or the type from a stat when the
+    /// filesystem gave none.
+    pub fn file_type(&self, entry: &ListedEntry) -> io::Result<FsEntryKind> {
+        match entry.file_type {
+            Some(kind) => Ok(kind),
+            None => self.stat(&entry.name, false).map(|stat| stat.kind),
+        }
+    }
+}
+
+fn allocated_size(metadata: &Metadata) -> u64 {
     #[cfg(unix)]
     {
         use std::os::unix::fs::MetadataExt;
@@ -32,13 +299,10 @@ pub fn allocated_size(path: &Path, metadata: &Metadata) -> u64 {
         use std::os::windows::fs::MetadataExt;
         let len = metadata.file_size();
         const CLUSTER: u64 = 4096;
-        if len == 0 {
-            return 0;
-        }
-        return ((len + CLUSTER - 1) / CLUSTER) * CLUSTER;
+        len.div_ceil(CLUSTER) * CLUSTER
     }
 
-    let _ = path;
+    #[cfg(not(windows))]
     metadata.len()
 }
 
@@ -75,13 +339,30 @@ pub fn disk_usage(_path: &Path) -> Option<DiskUsage> {
 }
 
 #[cfg(unix)]
-pub fn filesystem_id(_path: &Path, metadata: &Metadata) -> Option<FilesystemId> {
+pub fn hostname() -> Option<String> {
+    let mut buf = [0_u8; 256];
+    // SAFETY: `buf` is valid writable memory of the length passed to libc.
+    let rc = unsafe { libc::gethostname(buf.as_mut_ptr().cast(), buf.len()) };
+    if rc != 0 {
+        return None;
+    }
+    let len = buf.iter().position(|byte| *byte == 0).unwrap_or(buf.len());
+    Some(String::from_utf8_lossy(&buf[..len]).into_owned())
+}
+
+#[cfg(not(unix))]
+pub fn hostname() -> Option<String> {
+    std::env::var("COMPUTERNAME").ok()
+}
+
+#[cfg(unix)]
+fn filesystem_id(_path: &Path, metadata: &Metadata) -> Option<FilesystemId> {
     use std::os::unix::fs::MetadataExt;
     Some(metadata.dev())
 }
 
 #[cfg(windows)]
-pub fn filesystem_id(path: &Path, _metadata: &Metadata) -> Option<FilesystemId> {
+fn filesystem_id(path: &Path, _metadata: &Metadata) -> Option<FilesystemId> {
     use std::path::Component;
 
     let prefix = path.components().find_map(|component| {
@@ -96,14 +377,953 @@ pub fn filesystem_id(path: &Path, _metadata: &Metadata) -> Option<FilesystemId>
 }
 
 #[cfg(not(any(unix, windows)))]
-pub fn filesystem_id(path: &Path, metadata: &Metadata) -> Option<FilesystemId> {
+fn filesystem_id(path: &Path, metadata: &Metadata) -> Option<FilesystemId> {
     let _ = (path, metadata);
     None
 }
 
+#[cfg(unix)]
+fn hardlink_key(metadata: &Metadata) -> Option<InodeKey> {
+    use std::os::unix::fs::MetadataExt;
+    if metadata.is_dir() || metadata.nlink() <= 1 {
+        return None;
+    }
+    Some((metadata.dev(), metadata.ino()))
+}
+
+#[cfg(not(unix))]
+fn hardlink_key(_metadata: &Metadata) -> Option<InodeKey> {
+    None
+}
+
+#[cfg(unix)]
+fn owner(metadata: &Metadata) -> Option<Owner> {
+    use std::os::unix::fs::MetadataExt;
+    Some(Owner {
+        uid: metadata.uid(),
+        gid: metadata.gid(),
+    })
+}
+
+#[cfg(not(unix))]
+fn owner(_metadata: &Metadata) -> Option<Owner> {
+    None
+}
+
 #[cfg(windows)]
 fn hash_os_str(value: &OsStr) -> u64 {
     let mut hasher = DefaultHasher::new();
     value.hash(&mut hasher);
     hasher.finish()
 }
+
+#[cfg(target_os = "linux")]
+mod linux {
+    use std::ffi::{CStr, CString, OsStr};
+    use std::io;
+    use std::os::fd::{AsRawFd, FromRawFd, OwnedFd};
+    use std::os::unix::ffi::OsStrExt;
+    use std::path::Path;
+    use std::sync::Arc;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::time::{Duration, SystemTime};
+
+    use parking_lot::Mutex;
+
+    use super::{EntryStat, InodeKey, ListedEntry};
+    use crate::model::FsEntryKind;
+    use crate::owners::Owner;
+
+    const DIRENT_BUFFER_LEN: usize = 32 * 1024;
+    /// Offsets into `struct linux_dirent64`: inode, offset, record length, type, name.
+    const RECLEN_OFFSET: usize = 16;
+    const TYPE_OFFSET: usize = 18;
+    const NAME_OFFSET: usize = 19;
+    /// Directories kept open for their unvisited subdirectories, across all
+    /// scans. Past this, a directory is closed once listed and opened again
+    /// through its ancestors when a subdirectory needs it.
+    const KEEP_OPEN: usize = 256;
+
+    static KEPT_OPEN: AtomicUsize = AtomicUsize::new(0);
+
+    /// The call `FdDir` stats its entries with.
+    #[derive(Debug, Clone, Copy, Eq, PartialEq)]
+    pub(super) enum StatCall {
+        Fstatat,
+        #[cfg(feature = "statx")]
+        Statx,
+        #[cfg(feature = "statx")]
+        StatxBatch,
+    }
+
+    /// A directory by its name in its parent, which can be opened again after
+    /// its descriptor was closed.
+    pub(super) struct FdHandle {
+        parent: Option<Arc<FdHandle>>,
+        /// Name in `parent`, or the full path when there is no parent.
+        name: CString,
+        /// Whether `name` may be a symlink to the directory.
+        follow: bool,
+        /// Open while the directory is listed or has leases out.
+        fd: Mutex<Option<OwnedFd>>,
+    }
+
+    impl FdHandle {
+        fn keep(&self, fd: OwnedFd) {
+            let mut slot = self.fd.lock();
+            if slot.is_none() {
+                KEPT_OPEN.fetch_add(1, Ordering::Relaxed);
+                *slot = Some(fd);
+            }
+        }
+
+        fn close(&self) {
+            if self.fd.lock().take().is_some() {
+                KEPT_OPEN.fetch_sub(1, Ordering::Relaxed);
+            }
+        }
+
+        /// Opens the subdirectory `name`, reopening this directory first when
+        /// it was closed.
+        fn open_child(&self, name: &CStr, follow: bool) -> io::Result<OwnedFd> {
+            if let Some(fd) = &*self.fd.lock() {
+                return open_at(fd.as_raw_fd(), name, follow);
+            }
+            let fd = self.reopen()?;
+            let child = open_at(fd.as_raw_fd(), name, follow);
+            if KEPT_OPEN.load(Ordering::Relaxed) < KEEP_OPEN {
+                self.keep(fd);
+            }
+            child
+        }
+
+        /// Opens this directory again, relative to the nearest ancestor that is
+        /// still open.
+        fn reopen(&self) -> io::Result<OwnedFd> {
+            // Closed handles from this one up, innermost first.
+            let mut closed = vec![self];
+            let mut top = self;
+            let mut fd = loop {
+                let Some(parent) = top.parent.as_deref() else {
+                    break open_at(libc::AT_FDCWD, &top.name, top.follow)?;
+                };
+                if let Some(fd) = &*parent.fd.lock() {
+                    break open_at(fd.as_raw_fd(), &top.name, top.follow)?;
+                }
+                closed.push(parent);
+                top = parent;
+            };
+            closed.pop();
+            for handle in closed.into_iter().rev() {
+                fd = open_at(fd.as_raw_fd(), &handle.name, handle.follow)?;
+            }
+            Ok(fd)
+        }
+    }
+
+    impl Drop for FdHandle {
+        // Unlinks ancestors one at a time, so dropping the last handle of a
+        // deep chain does not recurse once per level.
+        fn drop(&mut self) {
+            if self.fd.get_mut().take().is_some() {
+                KEPT_OPEN.fetch_sub(1, Ordering::Relaxed);
+            }
+            let mut parent = self.parent.take();
+            while let Some(handle) = parent {
+                parent = Arc::into_inner(handle).and_then(|mut handle| handle.parent.take());
+            }
+        }
+    }
+
+    /// Keeps a directory open for its reader and its unvisited subdirectories;
+    /// the last lease closes it.
+    pub(super) struct Lease {
+        handle: Arc<FdHandle>,
+        /// How this directory and the subdirectories opened through the lease
+        /// stat their entries.
+        stat_call: StatCall,
+    }
+
+    impl Drop for Lease {
+        fn drop(&mut self) {
+            self.handle.close();
+        }
+    }
+
+    fn open_at(dirfd: i32, name: &CStr, follow: bool) -> io::Result<OwnedFd> {
+        let mut flags = libc::O_RDONLY | libc::O_DIRECTORY | libc::O_CLOEXEC;
+        if !follow {
+            flags |= libc::O_NOFOLLOW;
+        }
+        // SAFETY: `dirfd` is an open directory or `AT_FDCWD` and `name` is
+        // nul-terminated.
+        let fd = unsafe { libc::openat(dirfd, name.as_ptr(), flags) };
+        if fd < 0 {
+            return Err(io::Error::last_os_error());
+        }
+        // SAFETY: `fd` was just opened and nothing else owns it.
+        Ok(unsafe { OwnedFd::from_raw_fd(fd) })
+    }
+
+    pub(super) struct FdDir {
+        lease: Arc<Lease>,
+        /// The lease's descriptor, which stays open while the reader exists.
+        fd: i32,
+        buf: Vec<u8>,
+        offset: usize,
+        filled: usize,
+        exhausted: bool,
+    }
+
+    impl FdDir {
+        pub(super) fn open(path: &Path, stat_call: StatCall) -> io::Result<Self> {
+            let c_path = CString::new(path.as_os_str().as_bytes())?;
+            let fd = open_at(libc::AT_FDCWD, &c_path, true)?;
+            Ok(Self::with_handle(None, c_path, true, fd, stat_call))
+        }
+
+        /// Opens the subdirectory `name` of the directory `parent` leases.
+        pub(super) fn open_in(parent: &Lease, name: &OsStr, follow: bool) -> io::Result<Self> {
+            let c_name = CString::new(name.as_bytes())?;
+            let fd = parent.handle.open_child(&c_name, follow)?;
+            let stat_call = parent.stat_call;
+            Ok(Self::with_handle(
+                Some(Arc::clone(&parent.handle)),
+                c_name,
+                follow,
+                fd,
+                stat_call,
+            ))
+        }
+
+        fn with_handle(
+            parent: Option<Arc<FdHandle>>,
+            name: CString,
+            follow: bool,
+            fd: OwnedFd,
+            stat_call: StatCall,
+        ) -> Self {
+            let raw_fd = fd.as_raw_fd();
+            let handle = Arc::new(FdHandle {
+                parent,
+                name,
+                follow,
+                fd: Mutex::new(None),
+            });
+            handle.keep(fd);
+            Self {
+                lease: Arc::new(Lease { handle, stat_call }),
+                fd: raw_fd,
+                buf: vec![0; DIRENT_BUFFER_LEN],
+                offset: 0,
+                filled: 0,
+                exhausted: false,
+            }
+        }
+
+        pub(super) fn next_entry(&mut self) -> Option<io::Result<ListedEntry>> {
+            loop {
+                if self.offset >= self.filled {
+                    if self.exhausted {
+                        return None;
+                    }
+                    // SAFETY: `buf` is valid writable memory of the length passed in.
+                    let len = unsafe {
+                        libc::syscall(
+                            libc::SYS_getdents64,
+                            self.fd,
+                            self.buf.as_mut_ptr(),
+                            self.buf.len(),
+                        )
+                    };
+                    if len <= 0 {
+                        self.exhausted = true;
+                        return (len < 0).then(|| Err(io::Error::last_os_error()));
+                    }
+                    self.offset = 0;
+                    self.filled = len as usize;
+                }
+
+                // The kernel only writes whole records.
+                let record = &self.buf[self.offset..self.filled];
+                let reclen = usize::from(u16::from_ne_bytes([
+                    record[RECLEN_OFFSET],
+                    record[RECLEN_OFFSET + 1],
+                ]));
+                let d_type = record[TYPE_OFFSET];
+                let name = &record[NAME_OFFSET..reclen];
+                let name = &name[..name
+                    .iter()
+                    .position(|byte| *byte == 0)
+                    .unwrap_or(name.len())];
+                self.offset += reclen;
+
+                if name == b"." || name == b".." {
+                    continue;
+                }
+                return Some(Ok(ListedEntry {
+                    name: OsStr::from_bytes(name).to_os_string(),
+                    file_type: kind_from_d_type(d_type),
+                }));
+            }
+        }
+
+        pub(super) fn stat(&self, name: &OsStr, follow: bool) -> io::Result<EntryStat> {
+            let c_name = CString::new(name.as_bytes())?;
+            #[cfg(feature = "statx")]
+            if self.lease.stat_call != StatCall::Fstatat {
+                return statx::stat(self.fd, &c_name, follow);
+            }
+            let flags = if follow { 0 } else { libc::AT_SYMLINK_NOFOLLOW };
+            // SAFETY: `stat` is plain old data the kernel fills in.
+            let mut stat: libc::stat = unsafe { std::mem::zeroed() };
+            // SAFETY: `fd` is an open directory and `c_name` is nul-terminated.
+            let rc = unsafe { libc::fstatat(self.fd, c_name.as_ptr(), &mut stat, flags) };
+            if rc != 0 {
+                return Err(io::Error::last_os_error());
+            }
+            Ok(entry_stat(&stat))
+        }
+    }
+
+    impl FdDir {
+        pub(super) fn stat_many(&self, names: &[&OsStr]) -> Vec<io::Result<EntryStat>> {
+            #[cfg(feature = "statx")]
+            if self.lease.stat_call == StatCall::StatxBatch {
+                return statx::stat_batch(self.fd, names);
+            }
+            names.iter().map(|name| self.stat(name, false)).collect()
+        }
+    }
+
+    impl FdDir {
+        pub(super) fn lease(&self) -> Arc<Lease> {
+            Arc::clone(&self.lease)
+        }
+
+        /// Device and inode of the open directory.
+        pub(super) fn identity(&self) -> io::Result<InodeKey> {
+            // SAFETY: `stat` is plain old data the kernel fills in.
+            let mut stat: libc::stat = unsafe { std::mem::zeroed() };
+            // SAFETY: `fd` is an open directory.
+            if unsafe { libc::fstat(self.fd, &mut stat) } != 0 {
+                return Err(io::Error::last_os_error());
+            }
+            Ok((stat.st_dev, stat.st_ino))
+        }
+    }
+
+    impl Drop for FdDir {
+        fn drop(&mut self) {
+            // Subdirectories still to visit reopen it through its ancestors.
+            if KEPT_OPEN.load(Ordering::Relaxed) > KEEP_OPEN {
+                self.lease.handle.close();
+            }
+        }
+    }
+
+    fn kind_from_d_type(d_type: u8) -> Option<FsEntryKind> {
+        match d_type {
+            libc::DT_UNKNOWN => None,
+            libc::DT_DIR => Some(FsEntryKind::Dir),
+            libc::DT_LNK => Some(FsEntryKind::Symlink),
+            libc::DT_REG => Some(FsEntryKind::File),
+            _ => Some(FsEntryKind::Other),
+        }
+    }
+
+    /// Same values `EntryStat::from_metadata` reads through `std::fs`.
+    // `stat` field widths vary between architectures, so the casts stay.
+    #[allow(clippy::unnecessary_cast)]
+    fn entry_stat(stat: &libc::stat) -> EntryStat {
+        let kind = match stat.st_mode & libc::S_IFMT {
+            libc::S_IFDIR => FsEntryKind::Dir,
+            libc::S_IFLNK => FsEntryKind::Symlink,
+            libc::S_IFREG => FsEntryKind::File,
+            _ => FsEntryKind::Other,
+        };
+        let apparent_bytes = stat.st_size as u64;
+        let blocks = stat.st_blocks as u64;
+        let (dev, ino) = (stat.st_dev as u64, stat.st_ino as u64);
+        EntryStat {
+            kind,
+            apparent_bytes,
+            allocated_bytes: if blocks > 0 {
+                blocks.saturating_mul(512)
+            } else {
+                apparent_bytes
+            },
+            filesystem: Some(dev),
+            hardlink: (kind != FsEntryKind::Dir && stat.st_nlink > 1).then_some((dev, ino)),
+            owner: Some(Owner {
+                uid: stat.st_uid,
+                gid: stat.st_gid,
+            }),
+            modified: system_time(stat.st_mtime as i64, stat.st_mtime_nsec as i64),
+            accessed: system_time(stat.st_atime as i64, stat.st_atime_nsec as i64),
+        }
+    }
+
+    fn system_time(secs: i64, nanos: i64) -> Option<SystemTime> {
+        let nanos = Duration::from_nanos(u64::try_from(nanos).ok()?);
+        let whole = Duration::from_secs(secs.unsigned_abs());
+        let base = if secs >= 0 {
+            SystemTime::UNIX_EPOCH.checked_add(whole)?
+        } else {
+            SystemTime::UNIX_EPOCH.checked_sub(whole)?
+        };
+        base.checked_add(nanos)
+    }
+
+    /// `statx`, alone or batched through io_uring, for the `statx` feature.
+    #[cfg(feature = "statx")]
+    mod statx {
+        use std::cell::RefCell;
+        use std::ffi::{CStr, CString, OsStr};
+        use std::io;
+        use std::os::unix::ffi::OsStrExt;
+        use std::sync::atomic::{AtomicU32, Ordering};
+
+        use super::system_time;
+        use crate::model::FsEntryKind;
+        use crate::owners::Owner;
+        use crate::platform::EntryStat;
+
+        /// Only what `EntryStat` carries; the device is always filled in.
+        const MASK: u32 = libc::STATX_TYPE
+            | libc::STATX_NLINK
+            | libc::STATX_UID
+            | libc::STATX_GID
+            | libc::STATX_ATIME
+            | libc::STATX_MTIME
+            | libc::STATX_INO
+            | libc::STATX_SIZE
+            | libc::STATX_BLOCKS;
+
+        pub(in crate::platform) fn stat(
+            dirfd: i32,
+            name: &CStr,
+            follow: bool,
+        ) -> io::Result<EntryStat> {
+            // SAFETY: `statx` is plain old data the kernel fills in.
+            let mut buf: libc::statx = unsafe { std::mem::zeroed() };
+            // SAFETY: `dirfd` is an open directory, `name` is nul-terminated and
+            // `buf` is valid writable memory.
+            let rc = unsafe { libc::statx(dirfd, name.as_ptr(), flags(follow), MASK, &mut buf) };
+            if rc != 0 {
+                return Err(io::Error::last_os_error());
+            }
+            Ok(entry_stat(&buf))
+        }
+
+        fn flags(follow: bool) -> i32 {
+            if follow { 0 } else { libc::AT_SYMLINK_NOFOLLOW }
+        }
+
+        /// Stats `names` without following symlinks, a ring's worth at a time.
+        pub(in crate::platform) fn stat_batch(
+            dirfd: i32,
+            names: &[&OsStr],
+        ) -> Vec<io::Result<EntryStat>> {
+            let mut results = Vec::with_capacity(names.len());
+            for chunk in names.chunks(RING_ENTRIES as usize) {
+                let c_names: Vec<io::Result<CString>> = chunk
+                    .iter()
+                    .map(|name| Ok(CString::new(name.as_bytes())?))
+                    .collect();
+                let submitted = RING.with(|slot| slot.borrow_mut().submit(dirfd, &c_names));
+                match submitted {
+                    Some(stats) => results.extend(stats),
+                    None => results.extend(
+                        c_names
+                            .into_iter()
+                            .map(|c_name| c_name.and_then(|c_name| stat(dirfd, &c_name, false))),
+                    ),
+                }
+            }
+            results
+        }
+
+        fn entry_stat(buf: &libc::statx) -> EntryStat {
+            let has = |field: u32| buf.stx_mask & field != 0;
+            let kind = match u32::from(buf.stx_mode) & libc::S_IFMT {
+                libc::S_IFDIR => FsEntryKind::Dir,
+                libc::S_IFLNK => FsEntryKind::Symlink,
+                libc::S_IFREG => FsEntryKind::File,
+                _ => FsEntryKind::Other,
+            };
+            let dev = libc::makedev(buf.stx_dev_major, buf.stx_dev_minor);
+            let apparent_bytes = buf.stx_size;
+            let time =
+                |stamp: libc::statx_timestamp| system_time(stamp.tv_sec, i64::from(stamp.tv_nsec));
+            EntryStat {
+                kind,
+                apparent_bytes,
+                allocated_bytes: if has(libc::STATX_BLOCKS) && buf.stx_blocks > 0 {
+                    buf.stx_blocks.saturating_mul(512)
+                } else {
+                    apparent_bytes
+                },
+                filesystem: Some(dev),
+                hardlink: (kind != FsEntryKind::Dir && buf.stx_nlink > 1)
+                    .then_some((dev, buf.stx_ino)),
+                owner: Some(Owner {
+                    uid: buf.stx_uid,
+                    gid: buf.stx_gid,
+                }),
+                modified: has(libc::STATX_MTIME)
+                    .then(|| time(buf.stx_mtime))
+                    .flatten(),
+                accessed: has(libc::STATX_ATIME)
+                    .then(|| time(buf.stx_atime))
+                    .flatten(),
+            }
+        }
+
+        thread_local! {
+            /// One ring per scan thread, set up on first use.
+            static RING: RefCell<RingSlot> = const { RefCell::new(RingSlot::Untried) };
+        }
+
+        enum RingSlot {
+            Untried,
+            Ready(Ring),
+            /// io_uring is missing or forbidden (e.g. by a container's seccomp
+            /// profile); stats go through plain `statx`.
+            Unavailable,
+        }
+
+        impl RingSlot {
+            /// Stats `names` through the ring, or returns `None` when the
+            /// caller has to fall back to plain `statx`.
+            fn submit(
+                &mut self,
+                dirfd: i32,
+                names: &[io::Result<CString>],
+            ) -> Option<Vec<io::Result<EntryStat>>> {
+                if matches!(self, Self::Untried) {
+                    *self = Ring::new().map_or(Self::Unavailable, Self::Ready);
+                }
+                let Self::Ready(ring) = self else {
+                    return None;
+                };
+                match ring.stat_all(dirfd, names) {
+                    Ok(stats) => Some(stats),
+                    Err(_) => {
+                        // Requests may still be in flight, so the ring and the
+                        // buffers it points into must outlive them.
+                        if let Self::Ready(ring) = std::mem::replace(self, Self::Unavailable) {
+                            std::mem::forget(ring);
+                        }
+                        None
+                    }
+                }
+            }
+        }
+
+        const RING_ENTRIES: u32 = 64;
+        const IORING_OP_STATX: u8 = 21;
+        const IORING_ENTER_GETEVENTS: u32 = 1;
+        const IORING_REGISTER_PROBE: u32 = 8;
+        const IO_URING_OP_SUPPORTED: u16 = 1;
+        /// Opcodes a probe reports on, `IORING_OP_STATX` among them.
+        const PROBE_OPS: usize = 32;
+        const IORING_OFF_SQ_RING: i64 = 0;
+        const IORING_OFF_CQ_RING: i64 = 0x800_0000;
+        const IORING_OFF_SQES: i64 = 0x1000_0000;
+
+        /// `struct io_sqring_offsets`.
+        #[repr(C)]
+        #[derive(Default)]
+        struct SqRingOffsets {
+            head: u32,
+            tail: u32,
+            ring_mask: u32,
+            ring_entries: u32,
+            flags: u32,
+            dropped: u32,
+            array: u32,
+            resv1: u32,
+            user_addr: u64,
+        }
+
+        /// `struct io_cqring_offsets`.
+        #[repr(C)]
+        #[derive(Default)]
+        struct CqRingOffsets {
+            head: u32,
+            tail: u32,
+            ring_mask: u32,
+            ring_entries: u32,
+            overflow: u32,
+            cqes: u32,
+            flags: u32,
+            resv1: u32,
+            user_addr: u64,
+        }
+
+        /// `struct io_uring_params`.
+        #[repr(C)]
+        #[derive(Default)]
+        struct Params {
+            sq_entries: u32,
+            cq_entries: u32,
+            flags: u32,
+            sq_thread_cpu: u32,
+            sq_thread_idle: u32,
+            features: u32,
+            wq_fd: u32,
+            resv: [u32; 3],
+            sq_off: SqRingOffsets,
+            cq_off: CqRingOffsets,
+        }
+
+        /// `struct io_uring_sqe` with the fields `IORING_OP_STATX` uses named.
+        #[repr(C)]
+        #[derive(Default)]
+        struct Sqe {
+            opcode: u8,
+            flags: u8,
+            ioprio: u16,
+            fd: i32,
+            /// Where the kernel writes the `statx` result.
+            buf: u64,
+            /// The path, relative to `fd`.
+            path: u64,
+            /// The `statx` field mask.
+            mask: u32,
+            statx_flags: u32,
+            user_data: u64,
+            buf_index: u16,
+            personality: u16,
+            file_index: u32,
+            addr3: u64,
+            pad: u64,
+        }
+
+        /// `struct io_uring_probe_op`.
+        #[repr(C)]
+        #[derive(Clone, Copy, Default)]
+        struct ProbeOp {
+            op: u8,
+            resv: u8,
+            flags: u16,
+            resv2: u32,
+        }
+
+        /// `struct io_uring_probe` with room for `PROBE_OPS` opcodes.
+        #[repr(C)]
+        #[derive(Default)]
+        struct Probe {
+            last_op: u8,
+            ops_len: u8,
+            resv: u16,
+            resv2: [u32; 3],
+            ops: [ProbeOp; PROBE_OPS],
+        }
+
+        /// `struct io_uring_cqe`.
+        #[repr(C)]
+        struct Cqe {
+            user_data: u64,
+            res: i32,
+            flags: u32,
+        }
+
+        struct Mapping {
+            ptr: *mut u8,
+            len: usize,
+        }
+
+        impl Mapping {
+            fn new(fd: i32, len: usize, offset: i64) -> io::Result<Self> {
+                // SAFETY: maps a fresh shared region of the ring; no existing
+                // memory is affected.
+                let ptr = unsafe {
+                    libc::mmap(
+                        std::ptr::null_mut(),
+                        len,
+                        libc::PROT_READ | libc::PROT_WRITE,
+                        libc::MAP_SHARED | libc::MAP_POPULATE,
+                        fd,
+                        offset,
+                    )
+                };
+                if ptr == libc::MAP_FAILED {
+                    return Err(io::Error::last_os_error());
+                }
+                Ok(Self {
+                    ptr: ptr.cast(),
+                    len,
+                })
+            }
+
+            /// # Safety
+            /// `offset` must come from the kernel's ring offsets for this mapping.
+            unsafe fn at<T>(&self, offset: u32) -> *mut T {
+                // SAFETY: the caller guarantees `offset` lies inside the mapping.
+                unsafe { self.ptr.add(offset as usize).cast() }
+            }
+        }
+
+        impl Drop for Mapping {
+            fn drop(&mut self) {
+                // SAFETY: `ptr` and `len` describe a mapping made by `Mapping::new`.
+                unsafe {
+                    libc::munmap(self.ptr.cast(), self.len);
+                }
+            }
+        }
+
+        /// A minimal io_uring instance that only ever runs `IORING_OP_STATX`,
+        /// one batch at a time.
+        struct Ring {
+            fd: i32,
+            sq_ring: Mapping,
+            cq_ring: Mapping,
+            sqes: Mapping,
+            params: Params,
+            /// Result buffers, one per submission slot.
+            bufs: Box<[libc::statx]>,
+        }
+
+        impl Ring {
+            fn new() -> io::Result<Self> {
+                let mut params = Params::default();
+                // SAFETY: `params` is valid writable memory of the expected layout.
+                let fd =
+                    unsafe { libc::syscall(libc::SYS_io_uring_setup, RING_ENTRIES, &mut params) };
+                if fd < 0 {
+                    return Err(io::Error::last_os_error());
+                }
+                let fd = fd as i32;
+                let close_on_error = |error: io::Error| {
+                    // SAFETY: `fd` was just returned by io_uring_setup.
+                    unsafe {
+                        libc::close(fd);
+                    }
+                    error
+                };
+                // io_uring predates `IORING_OP_STATX` (Linux 5.6), where every
+                // stat would fail with `EINVAL`. Probing arrived in the same
+                // release, so an older kernel fails the probe itself.
+                supports_statx(fd).map_err(close_on_error)?;
+
+                let sq_len =
+                    params.sq_off.array as usize + params.sq_entries as usize * size_of::<u32>();
+                let cq_len =
+                    params.cq_off.cqes as usize + params.cq_entries as usize * size_of::<Cqe>();
+                let sqes_len = params.sq_entries as usize * size_of::<Sqe>();
+                let sq_ring =
+                    Mapping::new(fd, sq_len, IORING_OFF_SQ_RING).map_err(close_on_error)?;
+                let cq_ring =
+                    Mapping::new(fd, cq_len, IORING_OFF_CQ_RING).map_err(close_on_error)?;
+                let sqes = Mapping::new(fd, sqes_len, IORING_OFF_SQES).map_err(close_on_error)?;
+
+                // SAFETY: `statx` is plain old data.
+                let empty: libc::statx = unsafe { std::mem::zeroed() };
+                Ok(Self {
+                    fd,
+                    sq_ring,
+                    cq_ring,
+                    sqes,
+                    bufs: vec![empty; params.sq_entries as usize].into_boxed_slice(),
+                    params,
+                })
+            }
+
+            /// Submits one `statx` per name (at most a ring's worth) and waits
+            /// for all of them. An `Err` means the ring itself failed.
+            fn stat_all(
+                &mut self,
+                dirfd: i32,
+                names: &[io::Result<CString>],
+            ) -> io::Result<Vec<io::Result<EntryStat>>> {
+                debug_assert!(names.len() <= self.bufs.len());
+                let sq = &self.params.sq_off;
+                let cq = &self.params.cq_off;
+                // SAFETY: every offset below was reported by io_uring_setup for
+                // these mappings, and this thread is the ring's only user.
+                let (sq_tail, sq_mask, sq_array, cq_head, cq_tail, cq_mask, cqes) = unsafe {
+                    (
+                        &*self.sq_ring.at::<AtomicU32>(sq.tail),
+                        *self.sq_ring.at::<u32>(sq.ring_mask),
+                        self.sq_ring.at::<u32>(sq.array),
+                        &*self.cq_ring.at::<AtomicU32>(cq.head),
+                        &*self.cq_ring.at::<AtomicU32>(cq.tail),
+                        *self.cq_ring.at::<u32>(cq.ring_mask),
+                        self.cq_ring.at::<Cqe>(cq.cqes),
+                    )
+                };
+
+                let mut results: Vec<Option<io::Result<EntryStat>>> =
+                    names.iter().map(|_| None).collect();
+                let mut queued = 0;
+                let tail = sq_tail.load(Ordering::Relaxed);
+                for (slot, name) in names.iter().enumerate() {
+                    let c_name = match name {
+                        Ok(c_name) => c_name,
+                        Err(error) => {
+                            results[slot] =
+                                Some(Err(io::Error::new(error.kind(), error.to_string())));
+                            continue;
+                        }
+                    };
+                    let index = tail.wrapping_add(queued) & sq_mask;
+                    let sqe = Sqe {
+                        opcode: IORING_OP_STATX,
+                        fd: dirfd,
+                        buf: (&raw mut self.bufs[slot]) as u64,
+                        path: c_name.as_ptr() as u64,
+                        mask: MASK,
+                        statx_flags: flags(false) as u32,
+                        user_data: slot as u64,
+                        ..Sqe::default()
+                    };
+                    // SAFETY: `index` is masked into the submission arrays.
+                    unsafe {
+                        self.sqes.at::<Sqe>(0).add(index as usize).write(sqe);
+                        sq_array.add(index as usize).write(index);
+                    }
+                    queued += 1;
+                }
+                sq_tail.store(tail.wrapping_add(queued), Ordering::Release);
+
+                let mut to_submit = queued;
+                let mut pending = queued;
+                while pending > 0 {
+                    // SAFETY: `fd` is this ring; no signal mask is passed.
+                    let rc = unsafe {
+                        libc::syscall(
+                            libc::SYS_io_uring_enter,
+                            self.fd,
+                            to_submit,
+                            1,
+                            IORING_ENTER_GETEVENTS,
+                            std::ptr::null::<libc::sigset_t>(),
+                            0,
+                        )
+                    };
+                    if rc < 0 {
+                        let error = io::Error::last_os_error();
+                        match error.raw_os_error() {
+                            Some(libc::EINTR | libc::EAGAIN | libc::EBUSY) => {}
+                            _ => return Err(error),
+                        }
+                    } else {
+                        to_submit -= rc as u32;
+                    }
+
+                    let mut head = cq_head.load(Ordering::Relaxed);
+                    let tail = cq_tail.load(Ordering::Acquire);
+                    while head != tail {
+                        // SAFETY: entries between head and tail are completed CQEs.
+                        let cqe = unsafe { &*cqes.add((head & cq_mask) as usize) };
+                        let slot = cqe.user_data as usize;
+                        results[slot] = Some(if cqe.res < 0 {
+                            Err(io::Error::from_raw_os_error(-cqe.res))
+                        } else {
+                            Ok(entry_stat(&self.bufs[slot]))
+                        });
+                        head = head.wrapping_add(1);
+                        pending -= 1;
+                    }
+                    cq_head.store(head, Ordering::Release);
+                }
+
+                Ok(results
+                    .into_iter()
+                    .map(|result| result.expect("every queued stat completed"))
+                    .collect())
+            }
+        }
+
+        fn supports_statx(fd: i32) -> io::Result<()> {
+            let mut probe = Probe::default();
+            // SAFETY: `probe` is valid writable memory with room for
+            // `PROBE_OPS` entries, as passed in `nr_args`.
+            let rc = unsafe {
+                libc::syscall(
+                    libc::SYS_io_uring_register,
+                    fd,
+                    IORING_REGISTER_PROBE,
+                    &mut probe,
+                    PROBE_OPS as u32,
+                )
+            };
+            if rc < 0 {
+                return Err(io::Error::last_os_error());
+            }
+            let supported = probe
+                .ops
+                .get(usize::from(IORING_OP_STATX))
+                .filter(|_| IORING_OP_STATX <= probe.last_op)
+                .is_some_and(|op| op.flags & IO_URING_OP_SUPPORTED != 0);
+            if !supported {
+                return Err(io::Error::from_raw_os_error(libc::EOPNOTSUPP));
+            }
+            Ok(())
+        }
+
+        impl Drop for Ring {
+            fn drop(&mut self) {
+                // SAFETY: `fd` came from io_uring_setup and is closed exactly once;
+                // the mappings are released by their own destructors.
+                unsafe {
+                    libc::close(self.fd);
+                }
+            }
+        }
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use std::fs;
+
+        use tempfile::TempDir;
+
+        use super::*;
+
+        fn names(dir: &mut FdDir) -> Vec<String> {
+            let mut names: Vec<String> = std::iter::from_fn(|| dir.next_entry())
+                .map(|entry| entry.expect("entry").name.to_string_lossy().into_owned())
+                .collect();
+            names.sort();
+            names
+        }
+
+        #[test]
+        fn opens_subdirectories_through_closed_ancestors() {
+            let temp = TempDir::new().expect("temp dir");
+            fs::create_dir_all(temp.path().join("a/b/c")).expect("dirs");
+            fs::write(temp.path().join("a/b/c/file"), b"data").expect("file");
+            std::os::unix::fs::symlink("b", temp.path().join("a/link")).expect("symlink");
+
+            let root = FdDir::open(temp.path(), StatCall::Fstatat).expect("open root");
+            let a = FdDir::open_in(&root.lease(), OsStr::new("a"), false).expect("open a");
+            let b = FdDir::open_in(&a.lease(), OsStr::new("b"), false).expect("open b");
+            let b_lease = b.lease();
+            drop(b);
+            // Both closed, as past the open budget: `c` reopens `a` and `b` from the root.
+            a.lease.handle.close();
+            b_lease.handle.close();
+            let mut c = FdDir::open_in(&b_lease, OsStr::new("c"), false).expect("open c");
+            assert_eq!(names(&mut c), ["file"]);
+            assert_eq!(
+                c.stat(OsStr::new("file"), false)
+                    .expect("stat")
+                    .apparent_bytes,
+                4
+            );
+
+            let refused = FdDir::open_in(&a.lease(), OsStr::new("link"), false);
+            assert_eq!(
+                refused.err().and_then(|error| error.raw_os_error()),
+                Some(libc::ENOTDIR)
+            );
+            let mut followed =
+                FdDir::open_in(&a.lease(), OsStr::new("link"), true).expect("follow");
+            assert_eq!(names(&mut followed), ["c"]);
+        }
+    }
+}
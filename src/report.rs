@@ -0,0 +1,465 @@
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+
+use crate::errors::AppError;
+use crate::model::{NodeStats, NodeSummary, ScanEvent, ScanOptions, SizeMetric};
+use crate::scanner::start_scan;
+use crate::snapshot::Snapshot;
+use crate::store::NodeStore;
+use crate::ui::format_bytes;
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum ReportFormat {
+    Json,
+    Csv,
+    Tree,
+}
+
+#[derive(Debug, Clone)]
+pub struct ReportOptions {
+    pub format: ReportFormat,
+    /// Largest entries to list (per directory for trees, overall for flat formats).
+    pub top: Option<usize>,
+}
+
+/// Completed scan results kept by path, ready to be rendered in any report format.
+#[derive(Debug, Clone, Default)]
+pub struct ScanReport {
+    pub root: PathBuf,
+    pub nodes: NodeStore,
+    pub warnings: u64,
+}
+
+impl ScanReport {
+    pub fn from_snapshot(snapshot: &Snapshot) -> Self {
+        let mut report = Self {
+            root: snapshot.root().to_path_buf(),
+            ..Self::default()
+        };
+        for node in &snapshot.nodes {
+            report.insert(node.clone());
+        }
+        report
+    }
+
+    pub fn insert(&mut self, node: NodeSummary) {
+        self.nodes.upsert(node);
+    }
+
+    fn sorted_children(&self, path: &Path, metric: SizeMetric) -> Vec<Entry<'_>> {
+        let Some(id) = self.nodes.id(path) else {
+            return Vec::new();
+        };
+        let mut children: Vec<Entry<'_>> = self
+            .nodes
+            .children(id)
+            .iter()
+            .filter_map(|child| {
+                let node = self.nodes.stats(*child)?;
+                Some((path.join(self.nodes.name(*child)), node))
+            })
+            .collect();
+        sort_by_size(&mut children, metric);
+        children
+    }
+
+    fn depth_of(&self, path: &Path) -> usize {
+        path.strip_prefix(&self.root)
+            .map(|relative| relative.components().count())
+            .unwrap_or(0)
+    }
+}
+
+/// A summarized entry and its path.
+type Entry<'a> = (PathBuf, &'a NodeStats);
+
+fn sort_by_size(entries: &mut [Entry<'_>], metric: SizeMetric) {
+    entries.sort_by(|(a_path, a), (b_path, b)| {
+        b.metric_bytes(metric)
+            .cmp(&a.metric_bytes(metric))
+            .then_with(|| a_path.cmp(b_path))
+    });
+}
+
+/// Runs a scan to completion without a terminal and collects every final node summary.
+pub fn collect_scan(options: ScanOptions) -> Result<ScanReport, AppError> {
+    let mut report = ScanReport {
+        root: options.root.clone(),
+        ..ScanReport::default()
+    };
+    let session = start_scan(options);
+
+    for event in session.receiver().iter() {
+        match event {
+            ScanEvent::NodeUpdated(node) => report.insert(node),
+            ScanEvent::Warning { .. } => report.warnings = report.warnings.saturating_add(1),
+            ScanEvent::Complete(_) => return Ok(report),
+            ScanEvent::Error(message) => return Err(AppError::Scan(message)),
+            ScanEvent::Cancelled => return Err(AppError::Scan("scan cancelled".to_string())),
+            ScanEvent::Reset { .. }
+            | ScanEvent::Relisted { .. }
+            | ScanEvent::FileTypes { .. }
+            | ScanEvent::Owners { .. }
+            | ScanEvent::DuplicateCandidates { .. }
+            | ScanEvent::LargeFile(_)
+            | ScanEvent::Progress(_) => {}
+        }
+    }
+
+    Err(AppError::Scan(
+        "scanner stopped before completing".to_string(),
+    ))
+}
+
+pub fn write_report(
+    out: &mut impl Write,
+    report: &ScanReport,
+    options: &ReportOptions,
+    metric: SizeMetric,
+    max_depth: Option<usize>,
+) -> io::Result<()> {
+    match options.format {
+        ReportFormat::Tree => write_tree(out, report, options.top, metric, max_depth),
+        ReportFormat::Json => {
+            let entries = flat_entries(report, options.top, metric, max_depth);
+            write_json(out, report, &entries, metric)
+        }
+        ReportFormat::Csv => {
+            let entries = flat_entries(report, options.top, metric, max_depth);
+            write_csv(out, report, &entries, metric)
+        }
+    }
+}
+
+fn flat_entries(
+    report: &ScanReport,
+    top: Option<usize>,
+    metric: SizeMetric,
+    max_depth: Option<usize>,
+) -> Vec<Entry<'_>> {
+    let mut entries: Vec<Entry<'_>> = report
+        .nodes
+        .subtree(&report.root)
+        .filter(|(path, _)| *path != report.root)
+        .filter(|(path, _)| max_depth.is_none_or(|max| report.depth_of(path) <= max))
+        .collect();
+    sort_by_size(&mut entries, metric);
+    if let Some(top) = top {
+        entries.truncate(top);
+    }
+    entries
+}
+
+fn write_tree(
+    out: &mut impl Write,
+    report: &ScanReport,
+    top: Option<usize>,
+    metric: SizeMetric,
+    max_depth: Option<usize>,
+) -> io::Result<()> {
+    let Some(root) = report.nodes.get(&report.root) else {
+        return writeln!(out, "{}: no data", report.root.display());
+    };
+
+    // An explicit stack keeps very deep trees from exhausting the call stack.
+    let mut stack = vec![TreeLine {
+        text: format!(
+            "{:>10}  {}",
+            format_bytes(root.metric_bytes(metric)),
+            report.root.display()
+        ),
+        expand: Some((report.root.clone(), String::new(), 0)),
+    }];
+
+    while let Some(line) = stack.pop() {
+        writeln!(out, "{}", line.text)?;
+
+        let Some((path, prefix, depth)) = line.expand else {
+            continue;
+        };
+        if max_depth.is_some_and(|max| depth >= max) {
+            continue;
+        }
+
+        let children = report.sorted_children(&path, metric);
+        let shown = top.unwrap_or(children.len()).min(children.len());
+        let hidden = children.len() - shown;
+        let mut level = Vec::with_capacity(shown + 1);
+
+        for (idx, (child_path, child)) in children.iter().take(shown).enumerate() {
+            let is_last = idx + 1 == shown && hidden == 0;
+            let (branch, indent) = if is_last {
+                ("└── ", "    ")
+            } else {
+                ("├── ", "│   ")
+            };
+            let name = child_path
+                .file_name()
+                .map(|n| n.to_string_lossy().into_owned())
+                .unwrap_or_else(|| child_path.to_string_lossy().into_owned());
+            level.push(TreeLine {
+                text: format!(
+                    "{:>10}  {prefix}{branch}{name}",
+                    format_bytes(child.metric_bytes(metric))
+                ),
+                expand: Some((child_path.clone(), format!("{prefix}{indent}"), depth + 1)),
+            });
+        }
+
+        if hidden > 0 {
+            let rest: u64 = children
+                .iter()
+                .skip(shown)
+                .map(|(_, child)| child.metric_bytes(metric))
+                .sum();
+            level.push(TreeLine {
+                text: format!("{:>10}  {prefix}└── … {hidden} more", format_bytes(rest)),
+                expand: None,
+            });
+        }
+
+        stack.extend(level.into_iter().rev());
+    }
+
+    Ok(())
+}
+
+struct TreeLine {
+    text: String,
+    /// Node to list below this line: path, prefix for its children and its depth.
+    expand: Option<(PathBuf, String, usize)>,
+}
+
+fn write_json(
+    out: &mut impl Write,
+    report: &ScanReport,
+    entries: &[Entry<'_>],
+    metric: SizeMetric,
+) -> io::Result<()> {
+    let total = report
+        .nodes
+        .get(&report.root)
+        .map(|node| node.metric_bytes(metric))
+        .unwrap_or_default();
+
+    writeln!(out, "{{")?;
+    writeln!(
+        out,
+        "  \"root\": {},",
+        json_string(&report.root.to_string_lossy())
+    )?;
+    writeln!(out, "  \"metric\": \"{}\",", metric.as_str())?;
+    writeln!(out, "  \"total_bytes\": {total},")?;
+    writeln!(out, "  \"warnings\": {},", report.warnings)?;
+    writeln!(out, "  \"entries\": [")?;
+    for (idx, (path, node)) in entries.iter().enumerate() {
+        let separator = if idx + 1 == entries.len() { "" } else { "," };
+        // JSON strings cannot carry raw bytes; flag names that lost some instead.
+        let lossy = if path.to_str().is_none() {
+            ", \"lossy_path\": true"
+        } else {
+            ""
+        };
+        writeln!(
+            out,
+            "    {{\"path\": {}{lossy}, \"kind\": \"{}\", \"depth\": {}, \"bytes\": {}, \"apparent_bytes\": {}, \"allocated_bytes\": {}, \"children_count\": {}}}{separator}",
+            json_string(&path.to_string_lossy()),
+            node.kind,
+            report.depth_of(path),
+            node.metric_bytes(metric),
+            node.apparent_bytes,
+            node.allocated_bytes,
+            node.children_count,
+        )?;
+    }
+    writeln!(out, "  ]")?;
+    writeln!(out, "}}")
+}
+
+fn write_csv(
+    out: &mut impl Write,
+    report: &ScanReport,
+    entries: &[Entry<'_>],
+    metric: SizeMetric,
+) -> io::Result<()> {
+    writeln!(
+        out,
+        "path,kind,depth,bytes,apparent_bytes,allocated_bytes,children_count"
+    )?;
+    for (path, node) in entries {
+        writeln!(
+            out,
+            "{},{},{},{},{},{},{}",
+            csv_field(&path.to_string_lossy()),
+            node.kind,
+            report.depth_of(path),
+            node.metric_bytes(metric),
+            node.apparent_bytes,
+            node.allocated_bytes,
+            node.children_count,
+        )?;
+    }
+    Ok(())
+}
+
+fn json_string(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len() + 2);
+    escaped.push('"');
+    for ch in value.chars() {
+        match ch {
+            '"' => escaped.push_str("\\\""),
+            '\\' => escaped.push_str("\\\\"),
+            '\n' => escaped.push_str("\\n"),
+            '\r' => escaped.push_str("\\r"),
+            '\t' => escaped.push_str("\\t"),
+            ch if ch.is_control() => escaped.push_str(&format!("\\u{:04x}", ch as u32)),
+            ch => escaped.push(ch),
+        }
+    }
+    escaped.push('"');
+    escaped
+}
+
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use tempfile::TempDir;
+
+    use super::*;
+    use crate::model::DEFAULT_STALE_AFTER;
+
+    fn scan(root: &Path) -> ScanReport {
+        collect_scan(ScanOptions {
+            root: root.to_path_buf(),
+            one_file_system: true,
+            follow_symlinks: false,
+            show_hidden: true,
+            show_files: true,
+            max_depth: None,
+            threads: 1,
+            exclude_patterns: Vec::new(),
+            include_patterns: Vec::new(),
+            report_excluded: true,
+            stale_after: DEFAULT_STALE_AFTER,
+        })
+        .expect("scan completes")
+    }
+
+    fn render(report: &ScanReport, format: ReportFormat, top: Option<usize>) -> String {
+        let mut out = Vec::new();
+        write_report(
+            &mut out,
+            report,
+            &ReportOptions { format, top },
+            SizeMetric::Apparent,
+            None,
+        )
+        .expect("write report");
+        String::from_utf8(out).expect("utf8")
+    }
+
+    fn sample_tree() -> TempDir {
+        let temp = TempDir::new().expect("temp dir");
+        fs::create_dir(temp.path().join("big")).expect("create big");
+        fs::write(temp.path().join("big").join("blob.bin"), vec![0_u8; 4000]).expect("blob");
+        fs::write(temp.path().join("small, \"quoted\".txt"), vec![0_u8; 10]).expect("small");
+        fs::write(temp.path().join("mid.txt"), vec![0_u8; 500]).expect("mid");
+        temp
+    }
+
+    #[test]
+    fn csv_is_sorted_and_honors_top() {
+        let temp = sample_tree();
+        let csv = render(&scan(temp.path()), ReportFormat::Csv, Some(2));
+        let lines: Vec<&str> = csv.lines().collect();
+
+        assert_eq!(
+            lines[0],
+            "path,kind,depth,bytes,apparent_bytes,allocated_bytes,children_count"
+        );
+        assert_eq!(lines.len(), 3);
+        assert!(lines[1].ends_with(",1") && lines[1].contains("big,dir,1,"));
+        assert!(lines[2].contains("blob.bin,file,2,4000,"));
+    }
+
+    #[test]
+    fn json_escapes_paths() {
+        let temp = sample_tree();
+        let json = render(&scan(temp.path()), ReportFormat::Json, None);
+
+        assert!(json.contains("\"metric\": \"apparent\""));
+        assert!(json.contains("small, \\\"quoted\\\".txt"));
+        assert_eq!(json.matches("\"path\":").count(), 4);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn json_flags_paths_that_are_not_utf8() {
+        use std::ffi::OsStr;
+        use std::os::unix::ffi::OsStrExt;
+
+        let temp = sample_tree();
+        fs::write(temp.path().join(OsStr::from_bytes(b"caf\xe9.txt")), b"x").expect("latin-1");
+        let json = render(&scan(temp.path()), ReportFormat::Json, None);
+
+        let flagged: Vec<&str> = json
+            .lines()
+            .filter(|line| line.contains("lossy_path"))
+            .collect();
+        assert_eq!(flagged.len(), 1);
+        assert!(flagged[0].contains("caf\u{fffd}.txt\", \"lossy_path\": true"));
+    }
+
+    #[test]
+    fn depth_limit_keeps_deeper_bytes_in_the_totals() {
+        let temp = sample_tree();
+        let mut out = Vec::new();
+        write_report(
+            &mut out,
+            &scan(temp.path()),
+            &ReportOptions {
+                format: ReportFormat::Csv,
+                top: None,
+            },
+            SizeMetric::Apparent,
+            Some(1),
+        )
+        .expect("write report");
+        let csv = String::from_utf8(out).expect("utf8");
+
+        let big = csv
+            .lines()
+            .find(|line| line.contains("big,dir,1,"))
+            .expect("big listed");
+        let bytes: u64 = big.split(',').nth(3).unwrap().parse().expect("bytes");
+        assert!(bytes >= 4000);
+        assert!(!csv.contains("blob.bin"));
+    }
+
+    #[test]
+    fn tree_collapses_entries_beyond_top() {
+        let temp = sample_tree();
+        let tree = render(&scan(temp.path()), ReportFormat::Tree, Some(1));
+        let lines: Vec<&str> = tree.lines().collect();
+
+        assert!(lines[1].ends_with("├── big"));
+        assert!(lines[2].ends_with("│   └── blob.bin"));
+        assert!(lines[3].ends_with("└── … 2 more"));
+        assert_eq!(lines.len(), 4);
+    }
+
+    #[test]
+    fn csv_quotes_special_characters() {
+        assert_eq!(csv_field("plain"), "plain");
+        assert_eq!(csv_field("a,\"b\""), "\"a,\"\"b\"\"\"");
+    }
+}
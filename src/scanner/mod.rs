@@ -1,3 +1,11 @@
+mod parallel;
 mod worker;
 
-pub use worker::{ScanSession, run_scan_blocking, start_scan};
+pub use worker::{ScanSession, refresh_dir, run_scan_blocking, run_scan_blocking_with, start_scan};
+
+/// Default traversal thread count: one per available CPU.
+pub fn default_thread_count() -> usize {
+    std::thread::available_parallelism()
+        .map(std::num::NonZeroUsize::get)
+        .unwrap_or(1)
+}
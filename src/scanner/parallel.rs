@@ -0,0 +1,295 @@
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering, fence};
+use std::thread;
+use std::time::Duration;
+
+use crossbeam_deque::{Injector, Stealer, Worker};
+use parking_lot::{Condvar, Mutex};
+
+use crate::model::NodeSummary;
+use crate::platform::{DirHandle, EntryStat};
+
+use super::worker::{
+    DirTotals, EntryClass, ListControl, PendingDir, ScanControl, ScannerState, classify_entry,
+    finish_dir, list_dir,
+};
+
+/// A directory whose size is still being aggregated from its children.
+///
+/// `pending` counts unfinished subdirectories plus one for the directory's own
+/// listing; whichever thread drops it to zero emits the final summary and
+/// forwards the totals to the parent.
+struct DirNode {
+    path: PathBuf,
+    depth: usize,
+    is_symlink_dir: bool,
+    metadata: EntryStat,
+    parent: Option<Arc<DirNode>>,
+    totals: Mutex<DirTotals>,
+    pending: AtomicUsize,
+}
+
+impl Drop for DirNode {
+    // Unlinks ancestors one at a time: letting the last reference to a deep
+    // chain drop its parent in turn would recurse once per level, e.g. when a
+    // cancelled scan abandons its queued tasks.
+    fn drop(&mut self) {
+        let mut parent = self.parent.take();
+        while let Some(node) = parent {
+            parent = Arc::into_inner(node).and_then(|mut node| node.parent.take());
+        }
+    }
+}
+
+/// Longest an idle worker sleeps before looking at the cancel flag again.
+const PARK_TIMEOUT: Duration = Duration::from_millis(50);
+
+struct DirTask {
+    node: Arc<DirNode>,
+    /// The parent's directory, to open this one relative to.
+    parent_dir: Option<DirHandle>,
+    emit_initial: bool,
+}
+
+struct SharedQueue<'s, 'a> {
+    state: &'s ScannerState<'a>,
+    injector: Injector<DirTask>,
+    stealers: Vec<Stealer<DirTask>>,
+    outstanding: AtomicUsize,
+    root_summary: Mutex<Option<NodeSummary>>,
+    /// Workers waiting in `park` for a task to show up.
+    sleepers: AtomicUsize,
+    idle: Mutex<()>,
+    wake: Condvar,
+}
+
+impl SharedQueue<'_, '_> {
+    fn push(&self, local: &Worker<DirTask>, task: DirTask) {
+        self.outstanding.fetch_add(1, Ordering::SeqCst);
+        local.push(task);
+        self.wake(false);
+    }
+
+    /// Wakes one parked worker, or all of them once the walk is done.
+    fn wake(&self, all: bool) {
+        // Pairs with the fence in `park`: either the parking worker sees the
+        // new task or the count, or this sees the parking worker.
+        fence(Ordering::SeqCst);
+        if self.sleepers.load(Ordering::SeqCst) == 0 {
+            return;
+        }
+        let _idle = self.idle.lock();
+        if all {
+            self.wake.notify_all();
+        } else {
+            self.wake.notify_one();
+        }
+    }
+
+    /// Sleeps until a task is pushed or the walk ends, instead of spinning
+    /// while other workers are busy with slow directories.
+    fn park(&self) {
+        let mut idle = self.idle.lock();
+        self.sleepers.fetch_add(1, Ordering::SeqCst);
+        fence(Ordering::SeqCst);
+        let queued =
+            !self.injector.is_empty() || self.stealers.iter().any(|stealer| !stealer.is_empty());
+        if !queued && self.outstanding.load(Ordering::SeqCst) != 0 {
+            self.wake.wait_for(&mut idle, PARK_TIMEOUT);
+        }
+        self.sleepers.fetch_sub(1, Ordering::SeqCst);
+    }
+
+    fn find_task(&self, local: &Worker<DirTask>) -> Option<DirTask> {
+        local.pop().or_else(|| {
+            std::iter::repeat_with(|| {
+                self.injector
+                    .steal_batch_and_pop(local)
+                    .or_else(|| self.stealers.iter().map(Stealer::steal).collect())
+            })
+            .find(|steal| !steal.is_retry())
+            .and_then(|steal| steal.success())
+        })
+    }
+
+    fn run_worker(&self, local: Worker<DirTask>) {
+        loop {
+            if self.state.should_cancel() {
+                return;
+            }
+
+            match self.find_task(&local) {
+                Some(task) => {
+                    self.process(&local, task);
+                    if self.outstanding.fetch_sub(1, Ordering::SeqCst) == 1 {
+                        self.wake(true);
+                    }
+                }
+                None => {
+                    if self.outstanding.load(Ordering::SeqCst) == 0 {
+                        return;
+                    }
+                    self.park();
+                }
+            }
+        }
+    }
+
+    fn process(&self, local: &Worker<DirTask>, task: DirTask) {
+        let node = task.node;
+        let listing = match list_dir(
+            &node.path,
+            task.parent_dir,
+            node.depth,
+            node.is_symlink_dir,
+            &node.metadata,
+            task.emit_initial,
+            self.state,
+        ) {
+            ListControl::Listed(listing) => listing,
+            ListControl::Finished(summary) => {
+                self.complete_child(node.parent.clone(), Some(summary));
+                return;
+            }
+            ListControl::Skipped => {
+                self.complete_child(node.parent.clone(), None);
+                return;
+            }
+            ListControl::Cancelled => return,
+        };
+
+        *node.totals.lock() = listing.totals;
+        node.pending
+            .store(listing.pending_dirs.len() + 1, Ordering::SeqCst);
+
+        for PendingDir {
+            path,
+            parent: parent_dir,
+            is_symlink_dir,
+            metadata,
+            emitted_initial,
+        } in listing.pending_dirs
+        {
+            let child = Arc::new(DirNode {
+                path,
+                depth: node.depth + 1,
+                is_symlink_dir,
+                metadata,
+                parent: Some(Arc::clone(&node)),
+                totals: Mutex::new(DirTotals::default()),
+                pending: AtomicUsize::new(0),
+            });
+            self.push(
+                local,
+                DirTask {
+                    node: child,
+                    parent_dir,
+                    emit_initial: !emitted_initial,
+                },
+            );
+        }
+
+        self.release(node);
+    }
+
+    /// Drops one pending reference and walks up finishing every ancestor that became complete.
+    fn release(&self, node: Arc<DirNode>) {
+        let mut current = node;
+        loop {
+            if current.pending.fetch_sub(1, Ordering::SeqCst) != 1 {
+                return;
+            }
+
+            let totals = std::mem::take(&mut *current.totals.lock());
+            let summary = finish_dir(
+                &current.path,
+                current.is_symlink_dir,
+                &current.metadata,
+                totals,
+                self.state,
+            );
+
+            match current.parent.clone() {
+                Some(parent) => {
+                    parent.totals.lock().add_child(&summary);
+                    current = parent;
+                }
+                None => {
+                    *self.root_summary.lock() = Some(summary);
+                    return;
+                }
+            }
+        }
+    }
+
+    fn complete_child(&self, parent: Option<Arc<DirNode>>, summary: Option<NodeSummary>) {
+        match parent {
+            Some(parent) => {
+                if let Some(summary) = &summary {
+                    parent.totals.lock().add_child(summary);
+                }
+                self.release(parent);
+            }
+            None => *self.root_summary.lock() = summary,
+        }
+    }
+}
+
+/// Traverses `root` with a work-stealing pool of `threads` workers.
+///
+/// Produces the same event stream and totals as the single-threaded walk; only the
+/// order in which sibling subtrees complete differs.
+pub(super) fn scan_tree(root: &Path, threads: usize, state: &ScannerState<'_>) -> ScanControl {
+    if state.should_cancel() {
+        return ScanControl::Cancelled;
+    }
+
+    let (is_symlink, metadata) = match classify_entry(root, 0, state) {
+        EntryClass::Skip => return ScanControl::Continue(None),
+        EntryClass::Leaf(summary) => return ScanControl::Continue(Some(summary)),
+        EntryClass::Dir {
+            is_symlink,
+            metadata,
+        } => (is_symlink, metadata),
+    };
+
+    let workers: Vec<Worker<DirTask>> = (0..threads).map(|_| Worker::new_lifo()).collect();
+    let queue = SharedQueue {
+        state,
+        injector: Injector::new(),
+        stealers: workers.iter().map(Worker::stealer).collect(),
+        outstanding: AtomicUsize::new(1),
+        root_summary: Mutex::new(None),
+        sleepers: AtomicUsize::new(0),
+        idle: Mutex::new(()),
+        wake: Condvar::new(),
+    };
+
+    queue.injector.push(DirTask {
+        node: Arc::new(DirNode {
+            path: root.to_path_buf(),
+            depth: 0,
+            is_symlink_dir: is_symlink,
+            metadata,
+            parent: None,
+            totals: Mutex::new(DirTotals::default()),
+            pending: AtomicUsize::new(0),
+        }),
+        parent_dir: None,
+        emit_initial: true,
+    });
+
+    thread::scope(|scope| {
+        for local in workers {
+            let queue = &queue;
+            scope.spawn(move || queue.run_worker(local));
+        }
+    });
+
+    if state.should_cancel() {
+        return ScanControl::Cancelled;
+    }
+
+    ScanControl::Continue(queue.root_summary.into_inner())
+}
@@ -1,18 +1,32 @@
 use std::collections::HashSet;
+use std::ffi::{OsStr, OsString};
 use std::fs;
+use std::io;
 use std::path::{Path, PathBuf};
 use std::sync::Arc;
-use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
 use std::thread::{self, JoinHandle};
 use std::time::SystemTime;
 
 use crossbeam_channel::{Receiver, Sender, bounded, unbounded};
+use parking_lot::Mutex;
 
-use crate::model::{FsEntryKind, NodeSummary, ScanEvent, ScanOptions, ScanProgress};
-use crate::platform::{FilesystemId, allocated_size, filesystem_id};
+use crate::filetypes::TypeTally;
+use crate::largest::{LARGEST_FILES_CAPACITY, LargestFiles};
+use crate::model::{AgeSummary, FsEntryKind, NodeSummary, ScanEvent, ScanOptions, ScanProgress};
+use crate::owners::OwnerTally;
+use crate::pattern::PathFilter;
+use crate::platform::{
+    DirHandle, DirIdentity, DirReader, EntryStat, FilesystemId, InodeKey, ListedEntry, WalkBackend,
+};
+use crate::store::NodeStore;
+
+use super::parallel;
 
 const EVENT_QUEUE_CAPACITY: usize = 8192;
 const PROGRESS_EMIT_EVERY: u64 = 512;
+/// Entries of one directory stat-ed together.
+const STAT_BATCH_LEN: usize = 256;
 
 pub struct ScanSession {
     receiver: Receiver<ScanEvent>,
@@ -50,7 +64,7 @@ pub fn start_scan(options: ScanOptions) -> ScanSession {
     let cancel_for_thread = Arc::clone(&cancel);
 
     let join = thread::spawn(move || {
-        run_scan(options, tx, &cancel_for_thread);
+        run_scan(options, WalkBackend::preferred(), tx, &cancel_for_thread);
     });
 
     ScanSession {
@@ -61,75 +75,172 @@ pub fn start_scan(options: ScanOptions) -> ScanSession {
 }
 
 pub fn run_scan_blocking(options: ScanOptions) -> Vec<ScanEvent> {
+    run_scan_blocking_with(options, WalkBackend::preferred())
+}
+
+/// Like `run_scan_blocking`, with an explicit directory walking backend.
+pub fn run_scan_blocking_with(options: ScanOptions, backend: WalkBackend) -> Vec<ScanEvent> {
     let (tx, rx) = unbounded();
     let cancel = Arc::new(AtomicBool::new(false));
-    run_scan(options, tx, &cancel);
+    run_scan(options, backend, tx, &cancel);
     rx.try_iter().collect()
 }
 
-struct ScannerState<'a> {
-    options: &'a ScanOptions,
+/// Scan-wide state shared by every traversal thread.
+pub(super) struct ScannerState<'a> {
+    pub(super) options: &'a ScanOptions,
+    backend: WalkBackend,
     tx: &'a Sender<ScanEvent>,
     cancel: &'a AtomicBool,
-    progress: ScanProgress,
     root_fs: Option<FilesystemId>,
-    emitted_progress_entries: u64,
-    visited_symlink_dirs: HashSet<PathBuf>,
+    filter: PathFilter,
+    /// Files last touched before this instant count as stale.
+    stale_cutoff: Option<SystemTime>,
+    visited_entries: AtomicU64,
+    warnings: AtomicU64,
+    apparent_bytes_seen: AtomicU64,
+    allocated_bytes_seen: AtomicU64,
+    emitted_progress_entries: AtomicU64,
+    visited_symlink_dirs: Mutex<HashSet<DirIdentity>>,
+    seen_hardlinks: Mutex<HashSet<InodeKey>>,
+    largest_files: Mutex<LargestFiles>,
+    /// Mirror of `largest_files.floor()` so small files skip the lock.
+    largest_files_floor: AtomicU64,
 }
 
 impl<'a> ScannerState<'a> {
-    fn should_cancel(&self) -> bool {
+    fn new(
+        options: &'a ScanOptions,
+        backend: WalkBackend,
+        tx: &'a Sender<ScanEvent>,
+        cancel: &'a AtomicBool,
+        root_meta: &EntryStat,
+    ) -> Self {
+        let root_fs = if options.one_file_system {
+            root_meta.filesystem
+        } else {
+            None
+        };
+
+        Self {
+            options,
+            backend,
+            tx,
+            cancel,
+            root_fs,
+            filter: PathFilter::new(&options.exclude_patterns, &options.include_patterns),
+            stale_cutoff: SystemTime::now().checked_sub(options.stale_after),
+            visited_entries: AtomicU64::new(0),
+            warnings: AtomicU64::new(0),
+            apparent_bytes_seen: AtomicU64::new(0),
+            allocated_bytes_seen: AtomicU64::new(0),
+            emitted_progress_entries: AtomicU64::new(0),
+            visited_symlink_dirs: Mutex::new(HashSet::new()),
+            seen_hardlinks: Mutex::new(HashSet::new()),
+            largest_files: Mutex::new(LargestFiles::new(LARGEST_FILES_CAPACITY)),
+            largest_files_floor: AtomicU64::new(0),
+        }
+    }
+
+    pub(super) fn should_cancel(&self) -> bool {
         self.cancel.load(Ordering::Relaxed)
     }
 
-    fn send_event(&self, event: ScanEvent) {
+    pub(super) fn send_event(&self, event: ScanEvent) {
         let _ = self.tx.send(event);
     }
 
-    fn bump_warning(&mut self, path: &Path, message: impl Into<String>) {
-        self.progress.warnings = self.progress.warnings.saturating_add(1);
+    fn progress(&self) -> ScanProgress {
+        ScanProgress {
+            visited_entries: self.visited_entries.load(Ordering::Relaxed),
+            warnings: self.warnings.load(Ordering::Relaxed),
+            apparent_bytes_seen: self.apparent_bytes_seen.load(Ordering::Relaxed),
+            allocated_bytes_seen: self.allocated_bytes_seen.load(Ordering::Relaxed),
+        }
+    }
+
+    pub(super) fn bump_warning(&self, path: &Path, message: impl Into<String>) {
+        self.warnings.fetch_add(1, Ordering::Relaxed);
         self.send_event(ScanEvent::Warning {
             path: path.to_path_buf(),
             message: message.into(),
         });
     }
 
-    fn bump_entry(&mut self, apparent: u64, allocated: u64) {
-        self.progress.visited_entries = self.progress.visited_entries.saturating_add(1);
-        self.progress.apparent_bytes_seen =
-            self.progress.apparent_bytes_seen.saturating_add(apparent);
-        self.progress.allocated_bytes_seen =
-            self.progress.allocated_bytes_seen.saturating_add(allocated);
+    /// Returns false when another path to the same hardlinked inode was already counted.
+    ///
+    /// With several threads the first path to claim an inode depends on visit order, so
+    /// the directory its bytes are attributed to can differ between scans; root totals
+    /// and naive totals do not.
+    fn claim_inode(&self, metadata: &EntryStat) -> bool {
+        match metadata.hardlink {
+            Some(key) => self.seen_hardlinks.lock().insert(key),
+            None => true,
+        }
+    }
 
-        if self
-            .progress
-            .visited_entries
-            .saturating_sub(self.emitted_progress_entries)
-            >= PROGRESS_EMIT_EVERY
+    /// Reports `file` to the UI when it is among the largest files seen so far.
+    fn offer_largest_file(&self, file: &NodeSummary) {
+        let rank = file.apparent_bytes.max(file.allocated_bytes);
+        if rank == 0 || rank <= self.largest_files_floor.load(Ordering::Relaxed) {
+            return;
+        }
+
+        let mut largest = self.largest_files.lock();
+        if largest.offer(file) {
+            if let Some(floor) = largest.floor() {
+                self.largest_files_floor.store(floor, Ordering::Relaxed);
+            }
+            drop(largest);
+            self.send_event(ScanEvent::LargeFile(file.clone()));
+        }
+    }
+
+    fn bump_entry(&self, apparent: u64, allocated: u64) {
+        self.apparent_bytes_seen
+            .fetch_add(apparent, Ordering::Relaxed);
+        self.allocated_bytes_seen
+            .fetch_add(allocated, Ordering::Relaxed);
+        let visited = self.visited_entries.fetch_add(1, Ordering::Relaxed) + 1;
+
+        let emitted = self.emitted_progress_entries.load(Ordering::Relaxed);
+        // Only the thread that wins the exchange emits, so parallel scans do not
+        // flood the channel with duplicate progress snapshots.
+        if visited.saturating_sub(emitted) >= PROGRESS_EMIT_EVERY
+            && self
+                .emitted_progress_entries
+                .compare_exchange(emitted, visited, Ordering::Relaxed, Ordering::Relaxed)
+                .is_ok()
         {
-            self.emitted_progress_entries = self.progress.visited_entries;
-            self.send_event(ScanEvent::Progress(self.progress.clone()));
+            self.send_event(ScanEvent::Progress(self.progress()));
         }
     }
 
-    fn emit_progress_now(&mut self) {
-        self.emitted_progress_entries = self.progress.visited_entries;
-        self.send_event(ScanEvent::Progress(self.progress.clone()));
+    fn emit_progress_now(&self) {
+        let progress = self.progress();
+        self.emitted_progress_entries
+            .store(progress.visited_entries, Ordering::Relaxed);
+        self.send_event(ScanEvent::Progress(progress));
     }
 }
 
-enum ScanControl {
+pub(super) enum ScanControl {
     Continue(Option<NodeSummary>),
     Cancelled,
 }
 
-pub fn run_scan(options: ScanOptions, tx: Sender<ScanEvent>, cancel: &AtomicBool) {
+pub fn run_scan(
+    options: ScanOptions,
+    backend: WalkBackend,
+    tx: Sender<ScanEvent>,
+    cancel: &AtomicBool,
+) {
     let _ = tx.send(ScanEvent::Reset {
         root: options.root.clone(),
     });
 
     let root_meta = match fs::symlink_metadata(&options.root) {
-        Ok(meta) => meta,
+        Ok(meta) => EntryStat::from_metadata(&options.root, &meta),
         Err(error) => {
             let _ = tx.send(ScanEvent::Error(format!(
                 "failed to stat {}: {error}",
@@ -139,32 +250,18 @@ pub fn run_scan(options: ScanOptions, tx: Sender<ScanEvent>, cancel: &AtomicBool
         }
     };
 
-    let root_fs = if options.one_file_system {
-        filesystem_id(&options.root, &root_meta)
-    } else {
-        None
-    };
+    let state = ScannerState::new(&options, backend, &tx, cancel, &root_meta);
 
-    let mut state = ScannerState {
-        options: &options,
-        tx: &tx,
-        cancel,
-        progress: ScanProgress::default(),
-        root_fs,
-        emitted_progress_entries: 0,
-        visited_symlink_dirs: HashSet::new(),
+    let control = if options.threads > 1 {
+        parallel::scan_tree(&options.root, options.threads, &state)
+    } else {
+        scan_entry(&options.root, 0, &state)
     };
 
-    match scan_entry(&options.root, 0, &mut state) {
-        ScanControl::Continue(Some(_)) => {
+    match control {
+        ScanControl::Continue(_) => {
             state.emit_progress_now();
-            let progress = state.progress.clone();
-            state.send_event(ScanEvent::Complete(progress));
-        }
-        ScanControl::Continue(None) => {
-            state.emit_progress_now();
-            let progress = state.progress.clone();
-            state.send_event(ScanEvent::Complete(progress));
+            state.send_event(ScanEvent::Complete(state.progress()));
         }
         ScanControl::Cancelled => {
             state.send_event(ScanEvent::Cancelled);
@@ -172,25 +269,86 @@ pub fn run_scan(options: ScanOptions, tx: Sender<ScanEvent>, cancel: &AtomicBool
     }
 }
 
-fn scan_entry(path: &Path, depth: usize, state: &mut ScannerState<'_>) -> ScanControl {
-    if state.should_cancel() {
-        return ScanControl::Cancelled;
+/// Re-lists one already scanned directory after a filesystem change and returns its new
+/// summary. Subdirectories found in `known` keep their cached totals; unknown ones are
+/// scanned in full. Hardlinks are only deduplicated within the refreshed directory.
+pub fn refresh_dir(
+    options: &ScanOptions,
+    dir: &Path,
+    known: &NodeStore,
+    tx: &Sender<ScanEvent>,
+) -> Option<NodeSummary> {
+    let root_meta = fs::symlink_metadata(&options.root)
+        .map(|meta| EntryStat::from_metadata(&options.root, &meta))
+        .ok()?;
+    let metadata = fs::metadata(dir)
+        .map(|meta| EntryStat::from_metadata(dir, &meta))
+        .ok()
+        .filter(EntryStat::is_dir)?;
+    let is_symlink_dir = known
+        .get(dir)
+        .is_some_and(|node| node.kind == FsEntryKind::Symlink);
+    let depth = dir
+        .strip_prefix(&options.root)
+        .map(|relative| relative.components().count())
+        .unwrap_or(0);
+
+    let cancel = AtomicBool::new(false);
+    let state = ScannerState::new(options, WalkBackend::preferred(), tx, &cancel, &root_meta);
+    let listing = match list_dir(dir, None, depth, is_symlink_dir, &metadata, false, &state) {
+        ListControl::Listed(listing) => listing,
+        ListControl::Finished(summary) => return Some(summary),
+        ListControl::Skipped | ListControl::Cancelled => return None,
+    };
+
+    let mut totals = listing.totals;
+    for pending in listing.pending_dirs {
+        if let Some(cached) = known.summary(&pending.path).filter(|node| node.is_complete) {
+            // Replaces the placeholder `list_dir` just emitted for this child.
+            totals.add_child(&cached);
+            state.send_event(ScanEvent::NodeUpdated(cached));
+            continue;
+        }
+        if let ScanControl::Continue(Some(child)) = scan_dir(
+            &pending.path,
+            pending.parent,
+            depth + 1,
+            pending.is_symlink_dir,
+            &pending.metadata,
+            !pending.emitted_initial,
+            &state,
+        ) {
+            totals.add_child(&child);
+        }
     }
 
+    Some(finish_dir(dir, is_symlink_dir, &metadata, totals, &state))
+}
+
+/// Outcome of stat-ing a single entry before any directory traversal happens.
+pub(super) enum EntryClass {
+    Skip,
+    Leaf(NodeSummary),
+    Dir {
+        is_symlink: bool,
+        metadata: EntryStat,
+    },
+}
+
+pub(super) fn classify_entry(path: &Path, depth: usize, state: &ScannerState<'_>) -> EntryClass {
     if depth > 0 && matches!(state.options.max_depth, Some(max_depth) if depth > max_depth) {
-        return ScanControl::Continue(None);
+        return EntryClass::Skip;
     }
 
     let symlink_meta = match fs::symlink_metadata(path) {
-        Ok(meta) => meta,
+        Ok(meta) => EntryStat::from_metadata(path, &meta),
         Err(error) => {
             state.bump_warning(path, format!("cannot stat path: {error}"));
-            return ScanControl::Continue(None);
+            return EntryClass::Skip;
         }
     };
 
-    let symlink_type = symlink_meta.file_type();
-    let is_symlink = symlink_type.is_symlink();
+    let is_symlink = symlink_meta.is_symlink();
 
     if is_symlink && !state.options.follow_symlinks {
         let summary = summarize_non_dir(
@@ -200,297 +358,600 @@ fn scan_entry(path: &Path, depth: usize, state: &mut ScannerState<'_>) -> ScanCo
             state.options.show_files,
             state,
         );
-        return ScanControl::Continue(Some(summary));
+        return EntryClass::Leaf(summary);
     }
 
     let resolved_meta = if is_symlink {
         match fs::metadata(path) {
-            Ok(meta) => meta,
+            Ok(meta) => EntryStat::from_metadata(path, &meta),
             Err(error) => {
                 state.bump_warning(path, format!("cannot follow symlink target: {error}"));
-                return ScanControl::Continue(None);
+                return EntryClass::Skip;
             }
         }
     } else {
         symlink_meta
     };
 
-    if state.options.one_file_system && depth > 0 && resolved_meta.is_dir() {
-        if let Some(root_id) = state.root_fs {
-            if let Some(this_id) = filesystem_id(path, &resolved_meta) {
-                if root_id != this_id {
-                    return ScanControl::Continue(None);
-                }
-            }
-        }
-    }
-
-    if resolved_meta.is_file() {
-        let kind = kind_from_non_dir(&resolved_meta, is_symlink);
-        let summary =
-            summarize_non_dir(path, kind, &resolved_meta, state.options.show_files, state);
-        return ScanControl::Continue(Some(summary));
+    if state.options.one_file_system
+        && depth > 0
+        && resolved_meta.is_dir()
+        && crosses_filesystem(&resolved_meta, state)
+    {
+        return EntryClass::Skip;
     }
 
     if resolved_meta.is_dir() {
-        return scan_dir(path, depth, is_symlink, &resolved_meta, true, state);
+        return EntryClass::Dir {
+            is_symlink,
+            metadata: resolved_meta,
+        };
     }
 
     let kind = kind_from_non_dir(&resolved_meta, is_symlink);
     let summary = summarize_non_dir(path, kind, &resolved_meta, state.options.show_files, state);
-    ScanControl::Continue(Some(summary))
+    EntryClass::Leaf(summary)
 }
 
-struct PendingDir {
-    path: PathBuf,
-    is_symlink_dir: bool,
-    metadata: fs::Metadata,
-    emitted_initial: bool,
+fn scan_entry(path: &Path, depth: usize, state: &ScannerState<'_>) -> ScanControl {
+    if state.should_cancel() {
+        return ScanControl::Cancelled;
+    }
+
+    match classify_entry(path, depth, state) {
+        EntryClass::Skip => ScanControl::Continue(None),
+        EntryClass::Leaf(summary) => ScanControl::Continue(Some(summary)),
+        EntryClass::Dir {
+            is_symlink,
+            metadata,
+        } => scan_dir(path, None, depth, is_symlink, &metadata, true, state),
+    }
 }
 
-fn scan_dir(
+pub(super) struct PendingDir {
+    pub(super) path: PathBuf,
+    /// The directory it was listed in, to open it relative to.
+    pub(super) parent: Option<DirHandle>,
+    pub(super) is_symlink_dir: bool,
+    pub(super) metadata: EntryStat,
+    pub(super) emitted_initial: bool,
+}
+
+/// Running size totals for a directory while its children are aggregated.
+#[derive(Debug, Clone, Default)]
+pub(super) struct DirTotals {
+    apparent: u64,
+    allocated: u64,
+    naive_apparent: u64,
+    naive_allocated: u64,
+    children_count: u64,
+    excluded_entries: u64,
+    age: AgeSummary,
+    /// Direct regular files only; subdirectories report their own tallies.
+    file_types: TypeTally,
+    /// The directory's own inode plus its non-directory children.
+    owners: OwnerTally,
+    /// Direct regular files with bytes of their own, for duplicate search
+    /// when `show_files` keeps them out of the tree.
+    duplicate_candidates: Vec<NodeSummary>,
+}
+
+impl DirTotals {
+    fn for_dir(apparent: u64, allocated: u64, metadata: &EntryStat) -> Self {
+        let mut owners = OwnerTally::default();
+        if let Some(owner) = metadata.owner {
+            owners.add_entry(owner, apparent, allocated);
+        }
+        Self {
+            apparent,
+            allocated,
+            naive_apparent: apparent,
+            naive_allocated: allocated,
+            children_count: 0,
+            excluded_entries: 0,
+            age: dir_age(metadata),
+            file_types: TypeTally::default(),
+            owners,
+            duplicate_candidates: Vec::new(),
+        }
+    }
+
+    /// Adds a non-directory child, which is also counted towards its owner.
+    fn add_leaf(&mut self, child: &NodeSummary) {
+        if let Some(owner) = child.owner {
+            self.owners
+                .add_entry(owner, child.apparent_bytes, child.allocated_bytes);
+        }
+        self.add_child(child);
+    }
+
+    pub(super) fn add_child(&mut self, child: &NodeSummary) {
+        self.children_count = self.children_count.saturating_add(1);
+        self.apparent = self.apparent.saturating_add(child.apparent_bytes);
+        self.allocated = self.allocated.saturating_add(child.allocated_bytes);
+        self.naive_apparent = self
+            .naive_apparent
+            .saturating_add(child.naive_apparent_bytes);
+        self.naive_allocated = self
+            .naive_allocated
+            .saturating_add(child.naive_allocated_bytes);
+        self.age.merge(&child.age);
+    }
+}
+
+pub(super) struct DirListing {
+    pub(super) totals: DirTotals,
+    pub(super) pending_dirs: Vec<PendingDir>,
+}
+
+pub(super) enum ListControl {
+    Listed(DirListing),
+    Finished(NodeSummary),
+    Skipped,
+    Cancelled,
+}
+
+fn dir_kind(is_symlink_dir: bool) -> FsEntryKind {
+    if is_symlink_dir {
+        FsEntryKind::Symlink
+    } else {
+        FsEntryKind::Dir
+    }
+}
+
+/// Reads one directory level: leaf children are summarized into the returned totals and
+/// subdirectories are handed back for the caller to traverse. The directory is opened
+/// relative to `parent` when given.
+pub(super) fn list_dir(
     path: &Path,
+    parent: Option<DirHandle>,
     depth: usize,
     is_symlink_dir: bool,
-    metadata: &fs::Metadata,
+    metadata: &EntryStat,
     emit_initial: bool,
-    state: &mut ScannerState<'_>,
-) -> ScanControl {
+    state: &ScannerState<'_>,
+) -> ListControl {
     if state.should_cancel() {
-        return ScanControl::Cancelled;
+        return ListControl::Cancelled;
     }
 
-    if is_symlink_dir {
-        match fs::canonicalize(path) {
-            Ok(canonical) => {
-                if !state.visited_symlink_dirs.insert(canonical.clone()) {
+    let opened = DirReader::open_in(parent.as_ref(), path, is_symlink_dir, state.backend);
+    // Lets the parent close once its last subdirectory is open.
+    #[cfg_attr(not(target_os = "linux"), allow(clippy::drop_non_drop))]
+    drop(parent);
+
+    if is_symlink_dir && let Ok(reader) = &opened {
+        match reader.identity() {
+            Ok(identity) => {
+                if !state.visited_symlink_dirs.lock().insert(identity) {
                     state.bump_warning(path, "detected symlink cycle, skipping traversal");
-                    return ScanControl::Continue(None);
+                    return ListControl::Skipped;
                 }
             }
             Err(error) => {
-                state.bump_warning(path, format!("cannot canonicalize symlink dir: {error}"));
-                return ScanControl::Continue(None);
+                state.bump_warning(path, format!("cannot identify symlink dir: {error}"));
+                return ListControl::Skipped;
             }
         }
     }
 
-    let dir_apparent = metadata.len();
-    let dir_allocated = allocated_size(path, metadata);
+    let dir_apparent = metadata.apparent_bytes;
+    let dir_allocated = metadata.allocated_bytes;
 
     if emit_initial {
         let initial_summary = NodeSummary {
             path: path.to_path_buf(),
-            kind: if is_symlink_dir {
-                FsEntryKind::Symlink
-            } else {
-                FsEntryKind::Dir
-            },
+            kind: dir_kind(is_symlink_dir),
             apparent_bytes: dir_apparent,
             allocated_bytes: dir_allocated,
+            naive_apparent_bytes: dir_apparent,
+            naive_allocated_bytes: dir_allocated,
             children_count: 0,
+            excluded_entries: 0,
+            age: dir_age(metadata),
+            owner: metadata.owner,
             is_complete: false,
             last_updated: SystemTime::now(),
         };
         state.send_event(ScanEvent::NodeUpdated(initial_summary));
     }
 
-    let read_dir = match fs::read_dir(path) {
-        Ok(rd) => rd,
+    let mut reader = match opened {
+        Ok(reader) => reader,
         Err(error) => {
             state.bump_warning(path, format!("cannot read directory: {error}"));
             let summary = NodeSummary {
                 path: path.to_path_buf(),
-                kind: if is_symlink_dir {
-                    FsEntryKind::Symlink
-                } else {
-                    FsEntryKind::Dir
-                },
+                kind: dir_kind(is_symlink_dir),
                 apparent_bytes: dir_apparent,
                 allocated_bytes: dir_allocated,
+                naive_apparent_bytes: dir_apparent,
+                naive_allocated_bytes: dir_allocated,
                 children_count: 0,
+                excluded_entries: 0,
+                age: dir_age(metadata),
+                owner: metadata.owner,
                 is_complete: true,
                 last_updated: SystemTime::now(),
             };
             state.bump_entry(summary.apparent_bytes, summary.allocated_bytes);
             state.send_event(ScanEvent::NodeUpdated(summary.clone()));
-            return ScanControl::Continue(Some(summary));
+            return ListControl::Finished(summary);
         }
     };
 
-    let mut apparent_total = dir_apparent;
-    let mut allocated_total = dir_allocated;
-    let mut children_count = 0_u64;
-    let mut pending_dirs: Vec<PendingDir> = Vec::new();
+    let mut listing = DirListing {
+        totals: DirTotals::for_dir(dir_apparent, dir_allocated, metadata),
+        pending_dirs: Vec::new(),
+    };
+    // Entries are stat-ed a batch at a time so batching backends can have the
+    // filesystem work on many of them at once.
+    let mut batch: Vec<(OsString, PathBuf)> = Vec::with_capacity(STAT_BATCH_LEN);
 
-    for entry_result in read_dir {
+    loop {
         if state.should_cancel() {
-            return ScanControl::Cancelled;
+            return ListControl::Cancelled;
         }
 
-        let entry = match entry_result {
-            Ok(entry) => entry,
-            Err(error) => {
+        let next = reader.next_entry();
+        let exhausted = next.is_none();
+        match next {
+            Some(Ok(entry)) => {
+                if let Some(child_path) =
+                    admit_child(path, depth, &reader, &entry, &mut listing.totals, state)
+                {
+                    batch.push((entry.name, child_path));
+                }
+            }
+            Some(Err(error)) => {
                 state.bump_warning(path, format!("cannot read entry: {error}"));
-                continue;
             }
-        };
-
-        let child_name = entry.file_name();
-        if !state.options.show_hidden && is_hidden(&child_name) {
-            continue;
+            None => {}
         }
 
-        let child_path = entry.path();
-        let child_depth = depth + 1;
-        if matches!(state.options.max_depth, Some(max_depth) if child_depth > max_depth) {
-            continue;
-        }
-
-        let child_symlink_meta = match fs::symlink_metadata(&child_path) {
-            Ok(meta) => meta,
-            Err(error) => {
-                state.bump_warning(&child_path, format!("cannot stat path: {error}"));
-                continue;
+        if batch.len() >= STAT_BATCH_LEN || (exhausted && !batch.is_empty()) {
+            let names: Vec<&OsStr> = batch.iter().map(|(name, _)| name.as_os_str()).collect();
+            let stats = reader.stat_many(&names);
+            drop(names);
+            for ((name, child_path), stat) in batch.drain(..).zip(stats) {
+                add_child(&reader, &name, child_path, stat, &mut listing, state);
             }
-        };
-
-        let child_is_symlink = child_symlink_meta.file_type().is_symlink();
-        if child_is_symlink && !state.options.follow_symlinks {
-            let child = summarize_non_dir(
-                &child_path,
-                FsEntryKind::Symlink,
-                &child_symlink_meta,
-                state.options.show_files,
-                state,
-            );
-            children_count = children_count.saturating_add(1);
-            apparent_total = apparent_total.saturating_add(child.apparent_bytes);
-            allocated_total = allocated_total.saturating_add(child.allocated_bytes);
-            continue;
         }
+        if exhausted {
+            return ListControl::Listed(listing);
+        }
+    }
+}
 
-        let child_resolved_meta = if child_is_symlink {
-            match fs::metadata(&child_path) {
-                Ok(meta) => meta,
-                Err(error) => {
-                    state.bump_warning(
-                        &child_path,
-                        format!("cannot follow symlink target: {error}"),
-                    );
-                    continue;
-                }
-            }
-        } else {
-            child_symlink_meta
-        };
+/// Applies the checks that need no stat (hidden names, depth, patterns) and
+/// returns the path of an entry that should be stat-ed.
+fn admit_child(
+    path: &Path,
+    depth: usize,
+    reader: &DirReader,
+    entry: &ListedEntry,
+    totals: &mut DirTotals,
+    state: &ScannerState<'_>,
+) -> Option<PathBuf> {
+    if !state.options.show_hidden && is_hidden(&entry.name) {
+        return None;
+    }
 
-        if state.options.one_file_system && child_resolved_meta.is_dir() {
-            if let Some(root_id) = state.root_fs {
-                if let Some(this_id) = filesystem_id(&child_path, &child_resolved_meta) {
-                    if root_id != this_id {
-                        continue;
-                    }
-                }
+    let child_path = path.join(&entry.name);
+    let child_depth = depth + 1;
+    if matches!(state.options.max_depth, Some(max_depth) if child_depth > max_depth) {
+        return None;
+    }
+
+    if !state.filter.is_empty() {
+        // d_type hint from the directory listing; avoids a stat for filtered
+        // entries only. Every admitted entry still costs one unfollowed stat for
+        // its size, and only symlinks get a second, following one.
+        let may_be_dir = reader.file_type(entry).is_ok_and(|kind| {
+            kind == FsEntryKind::Dir
+                || (kind == FsEntryKind::Symlink && state.options.follow_symlinks)
+        });
+        if !state
+            .filter
+            .allows(&child_path, may_be_dir, &state.options.root)
+        {
+            if state.options.report_excluded {
+                totals.excluded_entries = totals.excluded_entries.saturating_add(1);
             }
+            return None;
         }
+    }
 
-        if child_resolved_meta.is_dir() {
-            let emitted_initial = if child_is_symlink {
-                false
-            } else {
-                state.send_event(ScanEvent::NodeUpdated(NodeSummary {
-                    path: child_path.clone(),
-                    kind: FsEntryKind::Dir,
-                    apparent_bytes: child_resolved_meta.len(),
-                    allocated_bytes: allocated_size(&child_path, &child_resolved_meta),
-                    children_count: 0,
-                    is_complete: false,
-                    last_updated: SystemTime::now(),
-                }));
-                true
-            };
+    Some(child_path)
+}
 
-            pending_dirs.push(PendingDir {
-                path: child_path,
-                is_symlink_dir: child_is_symlink,
-                metadata: child_resolved_meta,
-                emitted_initial,
-            });
-            continue;
+/// Summarizes a leaf child into `listing.totals`, or queues a subdirectory on
+/// `listing.pending_dirs`, given the child's own (unfollowed) stat.
+fn add_child(
+    reader: &DirReader,
+    name: &OsStr,
+    child_path: PathBuf,
+    symlink_stat: io::Result<EntryStat>,
+    listing: &mut DirListing,
+    state: &ScannerState<'_>,
+) {
+    let totals = &mut listing.totals;
+    let child_symlink_meta = match symlink_stat {
+        Ok(meta) => meta,
+        Err(error) => {
+            state.bump_warning(&child_path, format!("cannot stat path: {error}"));
+            return;
         }
+    };
 
-        let kind = kind_from_non_dir(&child_resolved_meta, child_is_symlink);
+    let child_is_symlink = child_symlink_meta.is_symlink();
+    if child_is_symlink && !state.options.follow_symlinks {
         let child = summarize_non_dir(
             &child_path,
-            kind,
-            &child_resolved_meta,
+            FsEntryKind::Symlink,
+            &child_symlink_meta,
             state.options.show_files,
             state,
         );
-        children_count = children_count.saturating_add(1);
-        apparent_total = apparent_total.saturating_add(child.apparent_bytes);
-        allocated_total = allocated_total.saturating_add(child.allocated_bytes);
+        totals.add_leaf(&child);
+        return;
     }
 
-    for pending in pending_dirs {
-        match scan_dir(
-            &pending.path,
-            depth + 1,
-            pending.is_symlink_dir,
-            &pending.metadata,
-            !pending.emitted_initial,
-            state,
-        ) {
-            ScanControl::Continue(Some(child)) => {
-                children_count = children_count.saturating_add(1);
-                apparent_total = apparent_total.saturating_add(child.apparent_bytes);
-                allocated_total = allocated_total.saturating_add(child.allocated_bytes);
+    let child_resolved_meta = if child_is_symlink {
+        match reader.stat(name, true) {
+            Ok(meta) => meta,
+            Err(error) => {
+                state.bump_warning(
+                    &child_path,
+                    format!("cannot follow symlink target: {error}"),
+                );
+                return;
             }
-            ScanControl::Continue(None) => {}
-            ScanControl::Cancelled => return ScanControl::Cancelled,
         }
+    } else {
+        child_symlink_meta
+    };
+
+    if state.options.one_file_system
+        && child_resolved_meta.is_dir()
+        && crosses_filesystem(&child_resolved_meta, state)
+    {
+        return;
+    }
+
+    if child_resolved_meta.is_dir() {
+        let emitted_initial = if child_is_symlink {
+            false
+        } else {
+            let apparent = child_resolved_meta.apparent_bytes;
+            let allocated = child_resolved_meta.allocated_bytes;
+            state.send_event(ScanEvent::NodeUpdated(NodeSummary {
+                path: child_path.clone(),
+                kind: FsEntryKind::Dir,
+                apparent_bytes: apparent,
+                allocated_bytes: allocated,
+                naive_apparent_bytes: apparent,
+                naive_allocated_bytes: allocated,
+                children_count: 0,
+                excluded_entries: 0,
+                age: dir_age(&child_resolved_meta),
+                owner: child_resolved_meta.owner,
+                is_complete: false,
+                last_updated: SystemTime::now(),
+            }));
+            true
+        };
+
+        listing.pending_dirs.push(PendingDir {
+            path: child_path,
+            parent: reader.handle(),
+            is_symlink_dir: child_is_symlink,
+            metadata: child_resolved_meta,
+            emitted_initial,
+        });
+        return;
+    }
+
+    let kind = kind_from_non_dir(&child_resolved_meta, child_is_symlink);
+    let child = summarize_non_dir(
+        &child_path,
+        kind,
+        &child_resolved_meta,
+        state.options.show_files,
+        state,
+    );
+    if kind == FsEntryKind::File {
+        totals
+            .file_types
+            .add_file(&child_path, child.apparent_bytes, child.allocated_bytes);
+    }
+    totals.add_leaf(&child);
+    // Extra links to an already counted inode carry no bytes and are no copies.
+    if kind == FsEntryKind::File && !state.options.show_files && child.apparent_bytes > 0 {
+        totals.duplicate_candidates.push(child);
     }
+}
 
+/// Emits the final summary of a directory once all of its children were aggregated.
+pub(super) fn finish_dir(
+    path: &Path,
+    is_symlink_dir: bool,
+    metadata: &EntryStat,
+    totals: DirTotals,
+    state: &ScannerState<'_>,
+) -> NodeSummary {
     let summary = NodeSummary {
         path: path.to_path_buf(),
-        kind: if is_symlink_dir {
-            FsEntryKind::Symlink
-        } else {
-            FsEntryKind::Dir
-        },
-        apparent_bytes: apparent_total,
-        allocated_bytes: allocated_total,
-        children_count,
+        kind: dir_kind(is_symlink_dir),
+        apparent_bytes: totals.apparent,
+        allocated_bytes: totals.allocated,
+        naive_apparent_bytes: totals.naive_apparent,
+        naive_allocated_bytes: totals.naive_allocated,
+        children_count: totals.children_count,
+        excluded_entries: totals.excluded_entries,
+        age: totals.age,
+        owner: metadata.owner,
         is_complete: true,
         last_updated: SystemTime::now(),
     };
 
-    state.bump_entry(dir_apparent, dir_allocated);
+    state.bump_entry(metadata.apparent_bytes, metadata.allocated_bytes);
+    state.send_event(ScanEvent::FileTypes {
+        path: path.to_path_buf(),
+        tally: totals.file_types,
+    });
+    state.send_event(ScanEvent::Owners {
+        path: path.to_path_buf(),
+        tally: totals.owners,
+    });
+    if !state.options.show_files {
+        state.send_event(ScanEvent::DuplicateCandidates {
+            path: path.to_path_buf(),
+            files: totals.duplicate_candidates,
+        });
+    }
     state.send_event(ScanEvent::NodeUpdated(summary.clone()));
-    ScanControl::Continue(Some(summary))
+    summary
+}
+
+/// A directory on the walk's current path whose subdirectories are still
+/// being visited.
+struct DirFrame {
+    dir: PendingDir,
+    totals: DirTotals,
+    /// Subdirectories not yet visited, in reverse listing order.
+    pending: Vec<PendingDir>,
+}
+
+/// Walks the tree below `path` depth-first with an explicit stack of frames
+/// rather than recursion, so arbitrarily deep trees cannot exhaust the scan
+/// thread's stack. Each directory is finished once all of its subdirectories
+/// are, and its summary is folded into the parent's totals.
+fn scan_dir(
+    path: &Path,
+    parent: Option<DirHandle>,
+    depth: usize,
+    is_symlink_dir: bool,
+    metadata: &EntryStat,
+    emit_initial: bool,
+    state: &ScannerState<'_>,
+) -> ScanControl {
+    let mut stack: Vec<DirFrame> = Vec::new();
+    let mut entering = Some(PendingDir {
+        path: path.to_path_buf(),
+        parent,
+        is_symlink_dir,
+        metadata: metadata.clone(),
+        emitted_initial: !emit_initial,
+    });
+
+    loop {
+        let finished = if let Some(mut dir) = entering.take() {
+            match list_dir(
+                &dir.path,
+                dir.parent.take(),
+                depth + stack.len(),
+                dir.is_symlink_dir,
+                &dir.metadata,
+                !dir.emitted_initial,
+                state,
+            ) {
+                ListControl::Listed(listing) => {
+                    let mut pending = listing.pending_dirs;
+                    pending.reverse();
+                    stack.push(DirFrame {
+                        dir,
+                        totals: listing.totals,
+                        pending,
+                    });
+                    continue;
+                }
+                ListControl::Finished(summary) => Some(summary),
+                ListControl::Skipped => None,
+                ListControl::Cancelled => return ScanControl::Cancelled,
+            }
+        } else {
+            let frame = stack
+                .last_mut()
+                .expect("walk stack holds the directory being visited");
+            if let Some(child) = frame.pending.pop() {
+                entering = Some(child);
+                continue;
+            }
+            let frame = stack.pop().expect("walk stack is not empty");
+            Some(finish_dir(
+                &frame.dir.path,
+                frame.dir.is_symlink_dir,
+                &frame.dir.metadata,
+                frame.totals,
+                state,
+            ))
+        };
+
+        match stack.last_mut() {
+            Some(parent) => {
+                if let Some(child) = &finished {
+                    parent.totals.add_child(child);
+                }
+            }
+            None => return ScanControl::Continue(finished),
+        }
+    }
+}
+
+fn crosses_filesystem(metadata: &EntryStat, state: &ScannerState<'_>) -> bool {
+    match (state.root_fs, metadata.filesystem) {
+        (Some(root_id), Some(this_id)) => root_id != this_id,
+        _ => false,
+    }
 }
 
 fn summarize_non_dir(
     path: &Path,
     kind: FsEntryKind,
-    metadata: &fs::Metadata,
+    metadata: &EntryStat,
     emit_node_update: bool,
-    state: &mut ScannerState<'_>,
+    state: &ScannerState<'_>,
 ) -> NodeSummary {
-    let apparent = metadata.len();
-    let allocated = allocated_size(path, metadata);
+    let naive_apparent = metadata.apparent_bytes;
+    let naive_allocated = metadata.allocated_bytes;
+    // Extra hardlinks to an inode already seen in this scan contribute no unique bytes.
+    let (apparent, allocated) = if state.claim_inode(metadata) {
+        (naive_apparent, naive_allocated)
+    } else {
+        (0, 0)
+    };
     state.bump_entry(apparent, allocated);
 
+    let modified = metadata.modified;
+    let accessed = metadata.accessed;
+    let is_stale = state
+        .stale_cutoff
+        .zip(modified.max(accessed))
+        .is_some_and(|(cutoff, touched)| touched < cutoff);
+
     let summary = NodeSummary {
         path: path.to_path_buf(),
         kind,
         apparent_bytes: apparent,
         allocated_bytes: allocated,
+        naive_apparent_bytes: naive_apparent,
+        naive_allocated_bytes: naive_allocated,
         children_count: 0,
+        excluded_entries: 0,
+        age: AgeSummary {
+            modified,
+            accessed,
+            stale_apparent_bytes: if is_stale { apparent } else { 0 },
+            stale_allocated_bytes: if is_stale { allocated } else { 0 },
+        },
+        owner: metadata.owner,
         is_complete: true,
         last_updated: SystemTime::now(),
     };
+    if kind == FsEntryKind::File {
+        state.offer_largest_file(&summary);
+    }
     if emit_node_update {
         state.send_event(ScanEvent::NodeUpdated(summary.clone()));
     }
@@ -498,10 +959,18 @@ fn summarize_non_dir(
     summary
 }
 
-fn kind_from_non_dir(metadata: &fs::Metadata, is_symlink: bool) -> FsEntryKind {
+/// A directory's own modification time; its access time is skipped since listing it updates it.
+fn dir_age(metadata: &EntryStat) -> AgeSummary {
+    AgeSummary {
+        modified: metadata.modified,
+        ..AgeSummary::default()
+    }
+}
+
+fn kind_from_non_dir(metadata: &EntryStat, is_symlink: bool) -> FsEntryKind {
     if is_symlink {
         FsEntryKind::Symlink
-    } else if metadata.is_file() {
+    } else if metadata.kind == FsEntryKind::File {
         FsEntryKind::File
     } else {
         FsEntryKind::Other
@@ -524,7 +993,7 @@ mod tests {
     use tempfile::TempDir;
 
     use super::*;
-    use crate::model::{ScanEvent, ScanOptions};
+    use crate::model::{DEFAULT_STALE_AFTER, ScanEvent, ScanOptions};
 
     #[test]
     fn scans_and_reports_nodes() {
@@ -541,6 +1010,11 @@ mod tests {
             show_hidden: true,
             show_files: true,
             max_depth: None,
+            threads: 1,
+            exclude_patterns: Vec::new(),
+            include_patterns: Vec::new(),
+            report_excluded: true,
+            stale_after: DEFAULT_STALE_AFTER,
         });
 
         assert!(events.iter().any(|event| {
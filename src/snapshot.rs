@@ -0,0 +1,539 @@
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{self, BufReader, BufWriter, Read, Write};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use crate::errors::AppError;
+use crate::model::{AgeSummary, DEFAULT_STALE_AFTER, FsEntryKind, NodeSummary, ScanOptions};
+use crate::owners::Owner;
+use crate::store::NodeStore;
+
+const MAGIC: &[u8; 8] = b"STSCSNAP";
+pub const SNAPSHOT_VERSION: u16 = 3;
+/// Version 1 predates per-node ages and version 2 per-node owners; such
+/// snapshots still load with those fields unknown.
+const OLDEST_READABLE_VERSION: u16 = 1;
+/// Upper bound for any length prefix; protects against allocating garbage on corrupt input.
+const MAX_FIELD_LEN: u64 = 1 << 20;
+
+/// A completed scan persisted to disk so it can be browsed or diffed elsewhere.
+#[derive(Debug, Clone)]
+pub struct Snapshot {
+    pub created_at: SystemTime,
+    pub host: String,
+    pub scan_options: ScanOptions,
+    /// Nodes ordered so every parent precedes its children; the first node is the root.
+    pub nodes: Vec<NodeSummary>,
+}
+
+impl Snapshot {
+    /// Collects the subtree below `root` in parent-first order.
+    pub fn from_tree(scan_options: ScanOptions, root: &Path, nodes: &NodeStore) -> Self {
+        let ordered = nodes
+            .subtree(root)
+            .map(|(path, stats)| NodeSummary::from_parts(path, *stats))
+            .collect();
+
+        Self {
+            created_at: SystemTime::now(),
+            host: crate::platform::hostname().unwrap_or_else(|| "unknown".to_string()),
+            scan_options,
+            nodes: ordered,
+        }
+    }
+
+    pub fn root(&self) -> &Path {
+        &self.scan_options.root
+    }
+}
+
+pub fn save_snapshot(path: &Path, snapshot: &Snapshot) -> Result<(), AppError> {
+    let to_error = |error: io::Error| AppError::Snapshot {
+        path: path.to_path_buf(),
+        reason: error.to_string(),
+    };
+
+    let file = File::create(path).map_err(to_error)?;
+    let mut out = BufWriter::new(file);
+    write_snapshot(&mut out, snapshot).map_err(to_error)?;
+    out.flush().map_err(to_error)
+}
+
+pub fn load_snapshot(path: &Path) -> Result<Snapshot, AppError> {
+    let to_error = |error: io::Error| AppError::Snapshot {
+        path: path.to_path_buf(),
+        reason: error.to_string(),
+    };
+
+    let file = File::open(path).map_err(to_error)?;
+    read_snapshot(&mut BufReader::new(file)).map_err(to_error)
+}
+
+pub fn write_snapshot(out: &mut impl Write, snapshot: &Snapshot) -> io::Result<()> {
+    out.write_all(MAGIC)?;
+    out.write_all(&SNAPSHOT_VERSION.to_le_bytes())?;
+
+    let since_epoch = snapshot
+        .created_at
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default();
+    write_varint(out, since_epoch.as_secs())?;
+    write_varint(out, u64::from(since_epoch.subsec_nanos()))?;
+    write_bytes(out, snapshot.host.as_bytes())?;
+    write_scan_options(out, &snapshot.scan_options)?;
+
+    write_varint(out, snapshot.nodes.len() as u64)?;
+    // Records reference their parent by index so each path segment is stored once.
+    let mut index_by_path: HashMap<&Path, u64> = HashMap::with_capacity(snapshot.nodes.len());
+    for (idx, node) in snapshot.nodes.iter().enumerate() {
+        let parent_index = node
+            .path
+            .parent()
+            .and_then(|parent| index_by_path.get(parent).copied());
+        match parent_index {
+            Some(parent_idx) => {
+                write_varint(out, parent_idx + 1)?;
+                let name = node.path.file_name().unwrap_or(node.path.as_os_str());
+                write_bytes(out, &os_bytes(name))?;
+            }
+            None => {
+                write_varint(out, 0)?;
+                write_bytes(out, &os_bytes(node.path.as_os_str()))?;
+            }
+        }
+
+        out.write_all(&[kind_tag(node.kind), u8::from(node.is_complete)])?;
+        write_varint(out, node.apparent_bytes)?;
+        write_varint(out, node.allocated_bytes)?;
+        write_varint(out, node.naive_apparent_bytes)?;
+        write_varint(out, node.naive_allocated_bytes)?;
+        write_varint(out, node.children_count)?;
+        write_varint(out, node.excluded_entries)?;
+        write_age(out, &node.age)?;
+        write_owner(out, node.owner)?;
+        index_by_path.insert(node.path.as_path(), idx as u64);
+    }
+
+    Ok(())
+}
+
+pub fn read_snapshot(input: &mut impl Read) -> io::Result<Snapshot> {
+    let mut magic = [0_u8; 8];
+    input.read_exact(&mut magic)?;
+    if &magic != MAGIC {
+        return Err(invalid("not a StorageScope snapshot"));
+    }
+
+    let mut version = [0_u8; 2];
+    input.read_exact(&mut version)?;
+    let version = u16::from_le_bytes(version);
+    if !(OLDEST_READABLE_VERSION..=SNAPSHOT_VERSION).contains(&version) {
+        return Err(invalid(format!(
+            "unsupported snapshot version {version} (expected {SNAPSHOT_VERSION})"
+        )));
+    }
+
+    let secs = read_varint(input)?;
+    let nanos = read_varint(input)?;
+    let created_at = epoch_plus(Duration::new(secs, nanos.min(999_999_999) as u32))?;
+    let host = String::from_utf8_lossy(&read_bytes(input)?).into_owned();
+    let scan_options = read_scan_options(input, version)?;
+
+    let count = read_varint(input)?;
+    let mut nodes: Vec<NodeSummary> = Vec::with_capacity(count.min(MAX_FIELD_LEN) as usize);
+    for _ in 0..count {
+        let parent_ref = read_varint(input)?;
+        let segment = path_from_bytes(read_bytes(input)?);
+        let path = if parent_ref == 0 {
+            segment
+        } else {
+            let parent = nodes
+                .get((parent_ref - 1) as usize)
+                .ok_or_else(|| invalid("node references an unknown parent"))?;
+            parent.path.join(segment)
+        };
+
+        let mut flags = [0_u8; 2];
+        input.read_exact(&mut flags)?;
+        nodes.push(NodeSummary {
+            path,
+            kind: kind_from_tag(flags[0])?,
+            is_complete: flags[1] != 0,
+            apparent_bytes: read_varint(input)?,
+            allocated_bytes: read_varint(input)?,
+            naive_apparent_bytes: read_varint(input)?,
+            naive_allocated_bytes: read_varint(input)?,
+            children_count: read_varint(input)?,
+            excluded_entries: read_varint(input)?,
+            age: if version >= 2 {
+                read_age(input)?
+            } else {
+                AgeSummary::default()
+            },
+            owner: if version >= 3 {
+                read_owner(input)?
+            } else {
+                None
+            },
+            last_updated: created_at,
+        });
+    }
+
+    Ok(Snapshot {
+        created_at,
+        host,
+        scan_options,
+        nodes,
+    })
+}
+
+fn write_scan_options(out: &mut impl Write, options: &ScanOptions) -> io::Result<()> {
+    write_bytes(out, &os_bytes(options.root.as_os_str()))?;
+    out.write_all(&[
+        u8::from(options.one_file_system),
+        u8::from(options.follow_symlinks),
+        u8::from(options.show_hidden),
+        u8::from(options.show_files),
+        u8::from(options.report_excluded),
+    ])?;
+    // 0 encodes "no limit"; depths are shifted by one.
+    write_varint(out, options.max_depth.map_or(0, |depth| depth as u64 + 1))?;
+    write_varint(out, options.threads as u64)?;
+    write_strings(out, &options.exclude_patterns)?;
+    write_strings(out, &options.include_patterns)?;
+    write_varint(out, options.stale_after.as_secs())
+}
+
+fn read_scan_options(input: &mut impl Read, version: u16) -> io::Result<ScanOptions> {
+    let root = path_from_bytes(read_bytes(input)?);
+    let mut flags = [0_u8; 5];
+    input.read_exact(&mut flags)?;
+    let max_depth = match read_varint(input)? {
+        0 => None,
+        depth => Some((depth - 1) as usize),
+    };
+
+    Ok(ScanOptions {
+        root,
+        one_file_system: flags[0] != 0,
+        follow_symlinks: flags[1] != 0,
+        show_hidden: flags[2] != 0,
+        show_files: flags[3] != 0,
+        report_excluded: flags[4] != 0,
+        max_depth,
+        threads: read_varint(input)? as usize,
+        exclude_patterns: read_strings(input)?,
+        include_patterns: read_strings(input)?,
+        stale_after: if version >= 2 {
+            Duration::from_secs(read_varint(input)?)
+        } else {
+            DEFAULT_STALE_AFTER
+        },
+    })
+}
+
+fn write_age(out: &mut impl Write, age: &AgeSummary) -> io::Result<()> {
+    write_timestamp(out, age.modified)?;
+    write_timestamp(out, age.accessed)?;
+    write_varint(out, age.stale_apparent_bytes)?;
+    write_varint(out, age.stale_allocated_bytes)
+}
+
+fn read_age(input: &mut impl Read) -> io::Result<AgeSummary> {
+    Ok(AgeSummary {
+        modified: read_timestamp(input)?,
+        accessed: read_timestamp(input)?,
+        stale_apparent_bytes: read_varint(input)?,
+        stale_allocated_bytes: read_varint(input)?,
+    })
+}
+
+/// Ids shifted by one so 0 encodes "unknown".
+fn write_owner(out: &mut impl Write, owner: Option<Owner>) -> io::Result<()> {
+    match owner {
+        Some(owner) => {
+            write_varint(out, u64::from(owner.uid) + 1)?;
+            write_varint(out, u64::from(owner.gid))
+        }
+        None => write_varint(out, 0),
+    }
+}
+
+fn read_owner(input: &mut impl Read) -> io::Result<Option<Owner>> {
+    let uid = match read_varint(input)? {
+        0 => return Ok(None),
+        uid => u32::try_from(uid - 1).map_err(|_| invalid("owner uid out of range"))?,
+    };
+    let gid = u32::try_from(read_varint(input)?).map_err(|_| invalid("owner gid out of range"))?;
+    Ok(Some(Owner { uid, gid }))
+}
+
+/// Whole seconds since the epoch, shifted by one so 0 encodes "unknown".
+fn write_timestamp(out: &mut impl Write, time: Option<SystemTime>) -> io::Result<()> {
+    let secs = time
+        .and_then(|time| time.duration_since(UNIX_EPOCH).ok())
+        .map_or(0, |since_epoch| since_epoch.as_secs().saturating_add(1));
+    write_varint(out, secs)
+}
+
+fn read_timestamp(input: &mut impl Read) -> io::Result<Option<SystemTime>> {
+    Ok(match read_varint(input)? {
+        0 => None,
+        secs => Some(epoch_plus(Duration::from_secs(secs - 1))?),
+    })
+}
+
+/// Corrupt files may hold times the platform cannot represent.
+fn epoch_plus(since_epoch: Duration) -> io::Result<SystemTime> {
+    UNIX_EPOCH
+        .checked_add(since_epoch)
+        .ok_or_else(|| invalid("timestamp out of range"))
+}
+
+fn kind_tag(kind: FsEntryKind) -> u8 {
+    match kind {
+        FsEntryKind::File => 0,
+        FsEntryKind::Dir => 1,
+        FsEntryKind::Symlink => 2,
+        FsEntryKind::Other => 3,
+    }
+}
+
+fn kind_from_tag(tag: u8) -> io::Result<FsEntryKind> {
+    match tag {
+        0 => Ok(FsEntryKind::File),
+        1 => Ok(FsEntryKind::Dir),
+        2 => Ok(FsEntryKind::Symlink),
+        3 => Ok(FsEntryKind::Other),
+        other => Err(invalid(format!("unknown entry kind tag {other}"))),
+    }
+}
+
+fn write_varint(out: &mut impl Write, mut value: u64) -> io::Result<()> {
+    let mut buf = [0_u8; 10];
+    let mut len = 0;
+    loop {
+        let byte = (value & 0x7f) as u8;
+        value >>= 7;
+        if value == 0 {
+            buf[len] = byte;
+            len += 1;
+            break;
+        }
+        buf[len] = byte | 0x80;
+        len += 1;
+    }
+    out.write_all(&buf[..len])
+}
+
+fn read_varint(input: &mut impl Read) -> io::Result<u64> {
+    let mut value = 0_u64;
+    for shift in (0..64).step_by(7) {
+        let mut byte = [0_u8; 1];
+        input.read_exact(&mut byte)?;
+        value |= u64::from(byte[0] & 0x7f) << shift;
+        if byte[0] & 0x80 == 0 {
+            return Ok(value);
+        }
+    }
+    Err(invalid("varint is too long"))
+}
+
+fn write_bytes(out: &mut impl Write, bytes: &[u8]) -> io::Result<()> {
+    write_varint(out, bytes.len() as u64)?;
+    out.write_all(bytes)
+}
+
+fn read_bytes(input: &mut impl Read) -> io::Result<Vec<u8>> {
+    let len = read_varint(input)?;
+    if len > MAX_FIELD_LEN {
+        return Err(invalid("field length exceeds limit"));
+    }
+    let mut bytes = vec![0_u8; len as usize];
+    input.read_exact(&mut bytes)?;
+    Ok(bytes)
+}
+
+fn write_strings(out: &mut impl Write, values: &[String]) -> io::Result<()> {
+    write_varint(out, values.len() as u64)?;
+    for value in values {
+        write_bytes(out, value.as_bytes())?;
+    }
+    Ok(())
+}
+
+fn read_strings(input: &mut impl Read) -> io::Result<Vec<String>> {
+    let count = read_varint(input)?;
+    if count > MAX_FIELD_LEN {
+        return Err(invalid("pattern count exceeds limit"));
+    }
+    (0..count)
+        .map(|_| read_bytes(input).map(|bytes| String::from_utf8_lossy(&bytes).into_owned()))
+        .collect()
+}
+
+#[cfg(unix)]
+fn os_bytes(value: &std::ffi::OsStr) -> Vec<u8> {
+    use std::os::unix::ffi::OsStrExt;
+    value.as_bytes().to_vec()
+}
+
+#[cfg(not(unix))]
+fn os_bytes(value: &std::ffi::OsStr) -> Vec<u8> {
+    value.to_string_lossy().into_owned().into_bytes()
+}
+
+#[cfg(unix)]
+fn path_from_bytes(bytes: Vec<u8>) -> PathBuf {
+    use std::os::unix::ffi::OsStringExt;
+    PathBuf::from(std::ffi::OsString::from_vec(bytes))
+}
+
+#[cfg(not(unix))]
+fn path_from_bytes(bytes: Vec<u8>) -> PathBuf {
+    PathBuf::from(String::from_utf8_lossy(&bytes).into_owned())
+}
+
+fn invalid(message: impl Into<String>) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message.into())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use super::*;
+
+    fn node(path: &str, kind: FsEntryKind, bytes: u64, children: u64) -> NodeSummary {
+        NodeSummary {
+            path: PathBuf::from(path),
+            kind,
+            apparent_bytes: bytes,
+            allocated_bytes: bytes + 1,
+            naive_apparent_bytes: bytes + 2,
+            naive_allocated_bytes: bytes + 3,
+            children_count: children,
+            excluded_entries: 1,
+            age: AgeSummary {
+                modified: Some(UNIX_EPOCH + Duration::from_secs(1_600_000_000 + bytes)),
+                accessed: None,
+                stale_apparent_bytes: bytes / 2,
+                stale_allocated_bytes: bytes / 2 + 1,
+            },
+            owner: (kind == FsEntryKind::File).then_some(Owner {
+                uid: 1000,
+                gid: bytes as u32,
+            }),
+            is_complete: true,
+            last_updated: SystemTime::now(),
+        }
+    }
+
+    fn sample_snapshot() -> Snapshot {
+        Snapshot {
+            created_at: UNIX_EPOCH + Duration::new(1_700_000_000, 42),
+            host: "build-01".to_string(),
+            scan_options: ScanOptions {
+                root: PathBuf::from("/srv/data"),
+                one_file_system: true,
+                follow_symlinks: false,
+                show_hidden: true,
+                show_files: false,
+                max_depth: Some(3),
+                threads: 8,
+                exclude_patterns: vec!["node_modules".to_string()],
+                include_patterns: Vec::new(),
+                report_excluded: true,
+                stale_after: DEFAULT_STALE_AFTER,
+            },
+            nodes: vec![
+                node("/srv/data", FsEntryKind::Dir, 5_000_000_000, 2),
+                node("/srv/data/logs", FsEntryKind::Dir, 300, 1),
+                node("/srv/data/link", FsEntryKind::Symlink, 7, 0),
+                node("/srv/data/logs/app.log", FsEntryKind::File, 290, 0),
+            ],
+        }
+    }
+
+    #[test]
+    fn round_trips_snapshot() {
+        let snapshot = sample_snapshot();
+        let mut buf = Vec::new();
+        write_snapshot(&mut buf, &snapshot).expect("write");
+        let loaded = read_snapshot(&mut Cursor::new(buf)).expect("read");
+
+        assert_eq!(loaded.created_at, snapshot.created_at);
+        assert_eq!(loaded.host, "build-01");
+        assert_eq!(loaded.root(), Path::new("/srv/data"));
+        assert_eq!(loaded.scan_options.max_depth, Some(3));
+        assert_eq!(loaded.scan_options.exclude_patterns, vec!["node_modules"]);
+        assert_eq!(loaded.nodes.len(), 4);
+        for (original, restored) in snapshot.nodes.iter().zip(&loaded.nodes) {
+            assert_eq!(original.path, restored.path);
+            assert_eq!(original.kind, restored.kind);
+            assert_eq!(original.apparent_bytes, restored.apparent_bytes);
+            assert_eq!(
+                original.naive_allocated_bytes,
+                restored.naive_allocated_bytes
+            );
+            assert_eq!(original.children_count, restored.children_count);
+            assert_eq!(original.excluded_entries, restored.excluded_entries);
+            assert_eq!(original.age, restored.age);
+            assert_eq!(original.owner, restored.owner);
+        }
+    }
+
+    #[test]
+    fn rejects_foreign_files_and_versions() {
+        let error = read_snapshot(&mut Cursor::new(b"not a snapshot".to_vec())).expect_err("magic");
+        assert!(error.to_string().contains("not a StorageScope snapshot"));
+
+        let mut buf = Vec::new();
+        write_snapshot(&mut buf, &sample_snapshot()).expect("write");
+        buf[8] = 99;
+        let error = read_snapshot(&mut Cursor::new(buf)).expect_err("version");
+        assert!(
+            error
+                .to_string()
+                .contains("unsupported snapshot version 99")
+        );
+    }
+
+    #[test]
+    fn rejects_timestamps_out_of_range() {
+        let mut buf = Vec::new();
+        write_snapshot(&mut buf, &sample_snapshot()).expect("write");
+        let mut created_at = Vec::new();
+        write_varint(&mut created_at, 1_700_000_000).expect("write");
+        write_varint(&mut created_at, 42).expect("write");
+        let mut huge = Vec::new();
+        write_varint(&mut huge, u64::MAX).expect("write");
+        write_varint(&mut huge, 999_999_999).expect("write");
+        buf.splice(10..10 + created_at.len(), huge);
+        let error = read_snapshot(&mut Cursor::new(buf)).expect_err("created_at");
+        assert!(error.to_string().contains("timestamp out of range"));
+
+        let mut buf = Vec::new();
+        write_varint(&mut buf, u64::MAX).expect("write");
+        let error = read_timestamp(&mut Cursor::new(buf)).expect_err("modified");
+        assert!(error.to_string().contains("timestamp out of range"));
+    }
+
+    #[test]
+    fn from_tree_orders_parents_first() {
+        let mut nodes = NodeStore::new();
+        for node in sample_snapshot().nodes.into_iter().rev() {
+            nodes.upsert(node);
+        }
+
+        let snapshot = Snapshot::from_tree(
+            sample_snapshot().scan_options,
+            Path::new("/srv/data"),
+            &nodes,
+        );
+        let paths: Vec<&Path> = snapshot.nodes.iter().map(|n| n.path.as_path()).collect();
+        assert_eq!(paths[0], Path::new("/srv/data"));
+        assert_eq!(paths[3], Path::new("/srv/data/logs/app.log"));
+    }
+}
@@ -0,0 +1,416 @@
+//! Arena-backed tree of node summaries, shared by the TUI, reports, baselines
+//! and the watcher.
+//!
+//! Entries live in one vector and refer to their parent and children by
+//! `NodeId`. Each keeps only its own name, interned once per distinct name, so
+//! a path costs a few ids instead of a `PathBuf` in every map that mentions it;
+//! full paths are rebuilt on demand.
+//!
+//! The store is the app's, not the scanner's: scan and watch events still
+//! name entries by full path, and sending a parent id plus name segment
+//! instead is a separate change. The app interns the paths as they arrive and
+//! the scan's event queue is bounded, so they do not accumulate. Data kept per entry outside the store is keyed by
+//! `NodeId` and must be dropped before `remove_subtree` frees the ids.
+
+use std::collections::{HashMap, VecDeque};
+use std::ffi::OsStr;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use crate::model::{NodeStats, NodeSummary};
+
+/// Index of an entry in a `NodeStore`; ids of removed entries are reused.
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash, Ord, PartialOrd)]
+pub struct NodeId(u32);
+
+impl NodeId {
+    fn index(self) -> usize {
+        self.0 as usize
+    }
+}
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash)]
+struct NameId(u32);
+
+/// Parent of top-level entries: `/`, a drive prefix or the first component of
+/// a relative path.
+const TOP: NodeId = NodeId(u32::MAX);
+
+#[derive(Debug, Clone)]
+struct Slot {
+    name: NameId,
+    parent: NodeId,
+    children: Vec<NodeId>,
+    /// `None` for ancestors that only exist to hold summarized descendants,
+    /// and for freed slots.
+    stats: Option<NodeStats>,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct NodeStore {
+    slots: Vec<Slot>,
+    free: Vec<NodeId>,
+    names: Vec<Arc<OsStr>>,
+    name_ids: HashMap<Arc<OsStr>, NameId>,
+    /// Entry by parent and name, for path lookups.
+    index: HashMap<(NodeId, NameId), NodeId>,
+    len: usize,
+}
+
+impl NodeStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Number of summarized entries.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Stores `node`, replacing any summary already kept for its path.
+    pub fn upsert(&mut self, node: NodeSummary) -> NodeId {
+        let id = self.intern(&node.path);
+        if self.slots[id.index()].stats.replace(node.stats()).is_none() {
+            self.len += 1;
+        }
+        id
+    }
+
+    /// Entry at `path`, including ancestors that have no summary of their own.
+    pub fn id(&self, path: &Path) -> Option<NodeId> {
+        let mut id = None;
+        let mut parent = TOP;
+        for component in path.components() {
+            let name = *self.name_ids.get(component.as_os_str())?;
+            parent = *self.index.get(&(parent, name))?;
+            id = Some(parent);
+        }
+        id
+    }
+
+    pub fn path(&self, id: NodeId) -> PathBuf {
+        let mut names = Vec::new();
+        let mut current = id;
+        while current != TOP {
+            let slot = &self.slots[current.index()];
+            names.push(self.name(current));
+            current = slot.parent;
+        }
+        names.iter().rev().collect()
+    }
+
+    pub fn name(&self, id: NodeId) -> &OsStr {
+        &self.names[self.slots[id.index()].name.0 as usize]
+    }
+
+    pub fn parent(&self, id: NodeId) -> Option<NodeId> {
+        Some(self.slots[id.index()].parent).filter(|parent| *parent != TOP)
+    }
+
+    /// `id` followed by its ancestors, nearest first.
+    pub fn ancestors(&self, id: NodeId) -> impl Iterator<Item = NodeId> + '_ {
+        std::iter::successors(Some(id), |id| self.parent(*id))
+    }
+
+    /// `id` and the ancestors between it and `root`, nearest first; `None`
+    /// when `id` is not at or below `root`.
+    pub fn branch(&self, root: NodeId, id: NodeId) -> Option<Vec<NodeId>> {
+        let mut branch = Vec::new();
+        for ancestor in self.ancestors(id) {
+            if ancestor == root {
+                return Some(branch);
+            }
+            branch.push(ancestor);
+        }
+        None
+    }
+
+    /// `id` and every entry below it, including entries without a summary.
+    pub fn subtree_ids(&self, id: NodeId) -> Vec<NodeId> {
+        let mut ids = vec![id];
+        let mut next = 0;
+        while let Some(&id) = ids.get(next) {
+            ids.extend_from_slice(self.children(id));
+            next += 1;
+        }
+        ids
+    }
+
+    /// Direct children in insertion order, including entries without a summary.
+    pub fn children(&self, id: NodeId) -> &[NodeId] {
+        &self.slots[id.index()].children
+    }
+
+    pub fn stats(&self, id: NodeId) -> Option<&NodeStats> {
+        self.slots[id.index()].stats.as_ref()
+    }
+
+    pub fn stats_mut(&mut self, id: NodeId) -> Option<&mut NodeStats> {
+        self.slots[id.index()].stats.as_mut()
+    }
+
+    pub fn get(&self, path: &Path) -> Option<&NodeStats> {
+        self.stats(self.id(path)?)
+    }
+
+    pub fn get_mut(&mut self, path: &Path) -> Option<&mut NodeStats> {
+        let id = self.id(path)?;
+        self.stats_mut(id)
+    }
+
+    pub fn contains(&self, path: &Path) -> bool {
+        self.get(path).is_some()
+    }
+
+    /// The summary at `path` with its path attached.
+    pub fn summary(&self, path: &Path) -> Option<NodeSummary> {
+        self.get(path)
+            .map(|stats| NodeSummary::from_parts(path.to_path_buf(), *stats))
+    }
+
+    /// Paths of the summarized children of `path`, in insertion order.
+    pub fn child_paths(&self, path: &Path) -> Vec<PathBuf> {
+        let Some(id) = self.id(path) else {
+            return Vec::new();
+        };
+        self.children(id)
+            .iter()
+            .filter(|child| self.stats(**child).is_some())
+            .map(|child| path.join(self.name(*child)))
+            .collect()
+    }
+
+    pub fn has_children(&self, path: &Path) -> bool {
+        self.id(path)
+            .is_some_and(|id| !self.children(id).is_empty())
+    }
+
+    /// Every summarized entry, in no particular order.
+    pub fn iter(&self) -> impl Iterator<Item = (NodeId, &NodeStats)> {
+        self.slots
+            .iter()
+            .enumerate()
+            .filter_map(|(index, slot)| Some((NodeId(index as u32), slot.stats.as_ref()?)))
+    }
+
+    /// Summarized entries at and below `root` with their paths, breadth first
+    /// so parents come before their children.
+    pub fn subtree(&self, root: &Path) -> Subtree<'_> {
+        Subtree {
+            store: self,
+            queue: self
+                .id(root)
+                .map(|id| (id, root.to_path_buf()))
+                .into_iter()
+                .collect(),
+        }
+    }
+
+    /// Drops the entry at `path` and everything below it; returns its summary.
+    pub fn remove_subtree(&mut self, path: &Path) -> Option<NodeStats> {
+        let id = self.id(path)?;
+        let removed = self.slots[id.index()].stats;
+
+        let parent = self.slots[id.index()].parent;
+        if parent != TOP {
+            self.slots[parent.index()]
+                .children
+                .retain(|child| *child != id);
+        }
+        let mut pending = vec![id];
+        while let Some(id) = pending.pop() {
+            let slot = &mut self.slots[id.index()];
+            pending.append(&mut slot.children);
+            if slot.stats.take().is_some() {
+                self.len -= 1;
+            }
+            self.index.remove(&(slot.parent, slot.name));
+            slot.parent = TOP;
+            self.free.push(id);
+        }
+        removed
+    }
+
+    /// Looks up `path`, adding the missing entries along it without a summary,
+    /// so data about an entry can be keyed by its id before its summary arrives.
+    pub fn intern(&mut self, path: &Path) -> NodeId {
+        let mut parent = TOP;
+        for component in path.components() {
+            let name = self.intern_name(component.as_os_str());
+            parent = match self.index.get(&(parent, name)) {
+                Some(id) => *id,
+                None => self.push_slot(parent, name),
+            };
+        }
+        assert_ne!(parent, TOP, "node paths are never empty");
+        parent
+    }
+
+    fn intern_name(&mut self, name: &OsStr) -> NameId {
+        if let Some(id) = self.name_ids.get(name) {
+            return *id;
+        }
+        let id = NameId(u32::try_from(self.names.len()).expect("fewer than 2^32 names"));
+        let name: Arc<OsStr> = Arc::from(name);
+        self.names.push(Arc::clone(&name));
+        self.name_ids.insert(name, id);
+        id
+    }
+
+    fn push_slot(&mut self, parent: NodeId, name: NameId) -> NodeId {
+        let slot = Slot {
+            name,
+            parent,
+            children: Vec::new(),
+            stats: None,
+        };
+        let id = match self.free.pop() {
+            Some(id) => {
+                self.slots[id.index()] = slot;
+                id
+            }
+            None => {
+                let id = NodeId(u32::try_from(self.slots.len()).expect("fewer than 2^32 nodes"));
+                self.slots.push(slot);
+                id
+            }
+        };
+        if parent != TOP {
+            self.slots[parent.index()].children.push(id);
+        }
+        self.index.insert((parent, name), id);
+        id
+    }
+}
+
+/// Iterator returned by `NodeStore::subtree`.
+pub struct Subtree<'a> {
+    store: &'a NodeStore,
+    queue: VecDeque<(NodeId, PathBuf)>,
+}
+
+impl<'a> Iterator for Subtree<'a> {
+    type Item = (PathBuf, &'a NodeStats);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while let Some((id, path)) = self.queue.pop_front() {
+            self.queue.extend(
+                self.store
+                    .children(id)
+                    .iter()
+                    .map(|child| (*child, path.join(self.store.name(*child)))),
+            );
+            if let Some(stats) = self.store.stats(id) {
+                return Some((path, stats));
+            }
+        }
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::SystemTime;
+
+    use super::*;
+    use crate::model::{AgeSummary, FsEntryKind};
+
+    fn node(path: &str, bytes: u64) -> NodeSummary {
+        NodeSummary {
+            path: PathBuf::from(path),
+            kind: FsEntryKind::Dir,
+            apparent_bytes: bytes,
+            allocated_bytes: bytes,
+            naive_apparent_bytes: bytes,
+            naive_allocated_bytes: bytes,
+            children_count: 0,
+            excluded_entries: 0,
+            age: AgeSummary::default(),
+            owner: None,
+            is_complete: true,
+            last_updated: SystemTime::now(),
+        }
+    }
+
+    #[test]
+    fn rebuilds_paths_and_lists_children() {
+        let mut store = NodeStore::new();
+        store.upsert(node("/srv/data", 30));
+        let logs = store.upsert(node("/srv/data/logs", 20));
+        store.upsert(node("/srv/data/logs/app.log", 20));
+        store.upsert(node("/srv/data/cache", 10));
+        assert_eq!(store.upsert(node("/srv/data/logs", 25)), logs);
+
+        assert_eq!(store.len(), 4);
+        assert_eq!(store.path(logs), PathBuf::from("/srv/data/logs"));
+        assert_eq!(
+            store
+                .get(Path::new("/srv/data/logs/"))
+                .unwrap()
+                .apparent_bytes,
+            25
+        );
+        assert!(!store.contains(Path::new("/srv")));
+        assert!(store.id(Path::new("/srv")).is_some());
+        assert_eq!(
+            store.child_paths(Path::new("/srv/data")),
+            [
+                PathBuf::from("/srv/data/logs"),
+                PathBuf::from("/srv/data/cache")
+            ]
+        );
+        assert!(!store.has_children(Path::new("/srv/data/cache")));
+
+        let srv = store.id(Path::new("/srv")).unwrap();
+        let app_log = store.id(Path::new("/srv/data/logs/app.log")).unwrap();
+        assert_eq!(store.ancestors(app_log).count(), 5);
+        assert_eq!(
+            store.branch(srv, app_log).map(|branch| branch.len()),
+            Some(3)
+        );
+        assert_eq!(store.branch(logs, srv), None);
+        assert_eq!(store.subtree_ids(logs), [logs, app_log]);
+
+        let paths: Vec<PathBuf> = store
+            .subtree(Path::new("/srv/data"))
+            .map(|(path, _)| path)
+            .collect();
+        assert_eq!(paths[0], PathBuf::from("/srv/data"));
+        assert_eq!(paths[3], PathBuf::from("/srv/data/logs/app.log"));
+    }
+
+    #[test]
+    fn removes_subtrees_and_reuses_their_slots() {
+        let mut store = NodeStore::new();
+        store.upsert(node("/srv/data", 30));
+        store.upsert(node("/srv/data/logs", 20));
+        store.upsert(node("/srv/data/logs/app.log", 20));
+        store.upsert(node("/srv/data/cache", 10));
+
+        let removed = store.remove_subtree(Path::new("/srv/data/logs")).unwrap();
+        assert_eq!(removed.apparent_bytes, 20);
+        assert_eq!(store.len(), 2);
+        assert!(!store.contains(Path::new("/srv/data/logs/app.log")));
+        assert_eq!(
+            store.child_paths(Path::new("/srv/data")),
+            [PathBuf::from("/srv/data/cache")]
+        );
+        assert_eq!(store.remove_subtree(Path::new("/srv/data/logs")), None);
+
+        let slots = store.slots.len();
+        store.upsert(node("/srv/data/tmp", 5));
+        assert_eq!(store.slots.len(), slots);
+        assert_eq!(
+            store
+                .get(Path::new("/srv/data/tmp"))
+                .unwrap()
+                .apparent_bytes,
+            5
+        );
+        assert_eq!(store.iter().count(), 3);
+    }
+}
@@ -7,6 +7,8 @@ use std::time::{Duration, Instant, SystemTime};
 
 use ratatui::style::{Color, Modifier, Style};
 
+use crate::filetypes::FileCategory;
+
 #[derive(Debug, Clone)]
 pub struct ThemePalette {
     source: String,
@@ -59,7 +61,7 @@ impl ThemePalette {
     }
 
     fn from_omarchy_path(path: &Path) -> Option<Self> {
-        let content = fs::read_to_string(&path).ok()?;
+        let content = fs::read_to_string(path).ok()?;
         let map = parse_color_assignments(&content);
 
         let foreground = map.get("foreground").copied().unwrap_or(Color::Reset);
@@ -209,6 +211,45 @@ impl ThemePalette {
         blend_color_stops(&stops, t)
     }
 
+    /// Treemap tile color for entries `depth` levels below the current path.
+    pub fn treemap_depth_color(&self, depth: usize) -> Color {
+        let colors = [self.accent, self.success, self.warning, self.danger];
+        colors[depth % colors.len()]
+    }
+
+    /// Treemap tile color for a file category; unknown content uses the muted color.
+    pub fn treemap_category_color(&self, category: Option<FileCategory>) -> Color {
+        match category {
+            Some(FileCategory::Video) => self.danger,
+            Some(FileCategory::Images) => self.warning,
+            Some(FileCategory::Archives) => blend_two_colors(self.accent, self.success, 0.5),
+            Some(FileCategory::Source) => self.success,
+            Some(FileCategory::Logs) => blend_two_colors(self.warning, self.danger, 0.5),
+            Some(FileCategory::VmImages) => self.accent,
+            Some(FileCategory::Other) | None => self.muted,
+        }
+    }
+
+    /// Darkens every other tile so neighbours of the same color stay apart.
+    pub fn treemap_shade(&self, color: Color, index: usize) -> Color {
+        if index.is_multiple_of(2) {
+            color
+        } else {
+            blend_two_colors(color, Color::Black, 0.3)
+        }
+    }
+
+    /// Readable label style on a filled treemap tile.
+    pub fn treemap_tile_style(&self, fill: Color) -> Style {
+        if self.use_color {
+            Style::default()
+                .bg(fill)
+                .fg(adaptive_selected_foreground(self.foreground, fill))
+        } else {
+            Style::default()
+        }
+    }
+
     pub fn warning_style(&self) -> Style {
         if self.use_color {
             Style::default().fg(self.warning)
@@ -577,7 +618,7 @@ mod tests {
         );
         assert_eq!(parsed.get("accent"), Some(&Color::Rgb(0x7a, 0xa2, 0xf7)));
         assert_eq!(parsed.get("color1"), Some(&Color::Rgb(0xf7, 0x76, 0x8e)));
-        assert!(parsed.get("ignored").is_none());
+        assert!(!parsed.contains_key("ignored"));
     }
 
     #[test]
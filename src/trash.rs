@@ -0,0 +1,339 @@
+//! Move-to-trash backend following the freedesktop.org Trash specification 1.0.
+
+use std::fs::{self, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+
+/// Upper bound on `name.N` candidates tried before giving up on a unique trash name.
+const MAX_NAME_ATTEMPTS: u32 = 10_000;
+
+/// A trash directory containing `files/` and `info/`.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct TrashDir {
+    pub root: PathBuf,
+    /// Mount top directory for per-mount trashes; `Path=` is written relative to it.
+    pub topdir: Option<PathBuf>,
+}
+
+/// Moves `target` into the home trash when it lives on the same filesystem,
+/// otherwise into the trash of the mount it lives on. Returns the trashed path.
+#[cfg(unix)]
+pub fn move_to_trash(target: &Path) -> io::Result<PathBuf> {
+    let target = std::path::absolute(target)?;
+    let trash = select_trash(&target)?;
+    trash_into(&target, &trash, &local_timestamp())
+}
+
+#[cfg(not(unix))]
+pub fn move_to_trash(_target: &Path) -> io::Result<PathBuf> {
+    Err(io::Error::new(
+        io::ErrorKind::Unsupported,
+        "trash is only supported on freedesktop systems",
+    ))
+}
+
+/// `$XDG_DATA_HOME/Trash`, falling back to `~/.local/share/Trash`.
+pub fn home_trash_dir() -> Option<PathBuf> {
+    let data_home = std::env::var_os("XDG_DATA_HOME")
+        .map(PathBuf::from)
+        .filter(|path| path.is_absolute())
+        .or_else(|| {
+            std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".local").join("share"))
+        })?;
+    Some(data_home.join("Trash"))
+}
+
+#[cfg(unix)]
+fn select_trash(target: &Path) -> io::Result<TrashDir> {
+    use std::os::unix::fs::MetadataExt;
+
+    let parent = target
+        .parent()
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "cannot trash a root path"))?;
+    let device = fs::metadata(parent)?.dev();
+
+    if let Some(home_trash) = home_trash_dir()
+        && fs::create_dir_all(home_trash.join("files")).is_ok()
+        && fs::create_dir_all(home_trash.join("info")).is_ok()
+        && fs::metadata(&home_trash)?.dev() == device
+    {
+        return Ok(TrashDir {
+            root: home_trash,
+            topdir: None,
+        });
+    }
+
+    let topdir = mount_topdir(parent, device);
+    mount_trash(&topdir).map(|root| TrashDir {
+        root,
+        topdir: Some(topdir),
+    })
+}
+
+/// Walks up from `start` to the highest ancestor that is still on `device`.
+#[cfg(unix)]
+fn mount_topdir(start: &Path, device: u64) -> PathBuf {
+    use std::os::unix::fs::MetadataExt;
+
+    let mut topdir = start.to_path_buf();
+    while let Some(parent) = topdir.parent() {
+        match fs::metadata(parent) {
+            Ok(metadata) if metadata.dev() == device => topdir = parent.to_path_buf(),
+            _ => break,
+        }
+    }
+    topdir
+}
+
+/// Prefers an admin-provided sticky `$topdir/.Trash/$uid`, then `$topdir/.Trash-$uid`.
+#[cfg(unix)]
+fn mount_trash(topdir: &Path) -> io::Result<PathBuf> {
+    use std::os::unix::fs::{DirBuilderExt, MetadataExt, PermissionsExt};
+
+    // SAFETY: getuid has no preconditions and cannot fail.
+    let uid = unsafe { libc::getuid() };
+
+    let shared = topdir.join(".Trash");
+    // 0o1000 is the sticky bit the spec requires on a shared `.Trash`.
+    if let Ok(metadata) = fs::symlink_metadata(&shared)
+        && metadata.is_dir()
+        && metadata.permissions().mode() & 0o1000 != 0
+    {
+        let candidate = shared.join(uid.to_string());
+        if ensure_private_trash(&candidate).is_ok() {
+            return Ok(candidate);
+        }
+    }
+
+    let candidate = topdir.join(format!(".Trash-{uid}"));
+    match fs::DirBuilder::new().mode(0o700).create(&candidate) {
+        Ok(()) => {}
+        Err(error) if error.kind() == io::ErrorKind::AlreadyExists => {
+            let metadata = fs::symlink_metadata(&candidate)?;
+            if !metadata.is_dir() || metadata.uid() != uid {
+                return Err(io::Error::new(
+                    io::ErrorKind::PermissionDenied,
+                    format!(
+                        "{} is not a trash directory owned by you",
+                        candidate.display()
+                    ),
+                ));
+            }
+        }
+        Err(error) => return Err(error),
+    }
+    ensure_private_trash(&candidate)?;
+    Ok(candidate)
+}
+
+#[cfg(unix)]
+fn ensure_private_trash(root: &Path) -> io::Result<()> {
+    use std::os::unix::fs::DirBuilderExt;
+
+    let mut builder = fs::DirBuilder::new();
+    builder.recursive(true).mode(0o700);
+    builder.create(root.join("files"))?;
+    builder.create(root.join("info"))
+}
+
+/// Writes the `.trashinfo` record first (claiming a unique name) and then renames
+/// the target into `files/`; the record is rolled back if the rename fails.
+pub fn trash_into(target: &Path, trash: &TrashDir, deleted_at: &str) -> io::Result<PathBuf> {
+    let name = target
+        .file_name()
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "target has no file name"))?;
+    let original = match &trash.topdir {
+        Some(topdir) => target.strip_prefix(topdir).unwrap_or(target),
+        None => target,
+    };
+    let contents = trashinfo_contents(original, deleted_at);
+
+    for attempt in 1..=MAX_NAME_ATTEMPTS {
+        let mut candidate = name.to_os_string();
+        if attempt > 1 {
+            candidate.push(format!(".{attempt}"));
+        }
+        let mut info_name = candidate.clone();
+        info_name.push(".trashinfo");
+
+        let info_path = trash.root.join("info").join(&info_name);
+        let files_path = trash.root.join("files").join(&candidate);
+        if fs::symlink_metadata(&files_path).is_ok() {
+            continue;
+        }
+
+        let mut info = match OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .open(&info_path)
+        {
+            Ok(file) => file,
+            Err(error) if error.kind() == io::ErrorKind::AlreadyExists => continue,
+            Err(error) => return Err(error),
+        };
+        if let Err(error) = info.write_all(contents.as_bytes()) {
+            let _ = fs::remove_file(&info_path);
+            return Err(error);
+        }
+
+        return match fs::rename(target, &files_path) {
+            Ok(()) => Ok(files_path),
+            Err(error) => {
+                let _ = fs::remove_file(&info_path);
+                Err(error)
+            }
+        };
+    }
+
+    Err(io::Error::new(
+        io::ErrorKind::AlreadyExists,
+        "no free name left in trash",
+    ))
+}
+
+pub fn trashinfo_contents(original: &Path, deleted_at: &str) -> String {
+    format!(
+        "[Trash Info]\nPath={}\nDeletionDate={deleted_at}\n",
+        percent_encode_path(original)
+    )
+}
+
+/// Percent-encodes everything except RFC 2396 unreserved characters and `/`.
+fn percent_encode_path(path: &Path) -> String {
+    let bytes = path_bytes(path);
+    let mut encoded = String::with_capacity(bytes.len());
+    for byte in bytes {
+        match byte {
+            b'A'..=b'Z'
+            | b'a'..=b'z'
+            | b'0'..=b'9'
+            | b'/'
+            | b'-'
+            | b'_'
+            | b'.'
+            | b'!'
+            | b'~'
+            | b'*'
+            | b'\''
+            | b'('
+            | b')' => encoded.push(char::from(byte)),
+            other => encoded.push_str(&format!("%{other:02X}")),
+        }
+    }
+    encoded
+}
+
+#[cfg(unix)]
+fn path_bytes(path: &Path) -> Vec<u8> {
+    use std::os::unix::ffi::OsStrExt;
+    path.as_os_str().as_bytes().to_vec()
+}
+
+#[cfg(not(unix))]
+fn path_bytes(path: &Path) -> Vec<u8> {
+    path.to_string_lossy().into_owned().into_bytes()
+}
+
+/// Local time as `YYYY-MM-DDThh:mm:ss`, the format required for `DeletionDate`.
+#[cfg(unix)]
+fn local_timestamp() -> String {
+    // SAFETY: `time` accepts a null pointer; `tm` is valid writable memory for localtime_r.
+    let now = unsafe { libc::time(std::ptr::null_mut()) };
+    let mut tm: libc::tm = unsafe { std::mem::zeroed() };
+    // SAFETY: both pointers are valid for the duration of the call.
+    if unsafe { libc::localtime_r(&now, &mut tm) }.is_null() {
+        return "1970-01-01T00:00:00".to_string();
+    }
+    format!(
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}",
+        tm.tm_year + 1900,
+        tm.tm_mon + 1,
+        tm.tm_mday,
+        tm.tm_hour,
+        tm.tm_min,
+        tm.tm_sec
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use tempfile::TempDir;
+
+    use super::*;
+
+    fn temp_trash(temp: &TempDir, topdir: Option<PathBuf>) -> TrashDir {
+        let root = temp.path().join("Trash");
+        fs::create_dir_all(root.join("files")).expect("files dir");
+        fs::create_dir_all(root.join("info")).expect("info dir");
+        TrashDir { root, topdir }
+    }
+
+    #[test]
+    fn moves_target_and_writes_trashinfo() {
+        let temp = TempDir::new().expect("temp dir");
+        let trash = temp_trash(&temp, None);
+        let target = temp.path().join("old report.txt");
+        fs::write(&target, "x").expect("write target");
+
+        let trashed = trash_into(&target, &trash, "2024-03-01T10:20:30").expect("trash");
+
+        assert!(!target.exists());
+        assert_eq!(trashed, trash.root.join("files").join("old report.txt"));
+        let info = fs::read_to_string(trash.root.join("info").join("old report.txt.trashinfo"))
+            .expect("read trashinfo");
+        assert_eq!(
+            info,
+            format!(
+                "[Trash Info]\nPath={}\nDeletionDate=2024-03-01T10:20:30\n",
+                percent_encode_path(&target)
+            )
+        );
+        assert!(info.contains("old%20report.txt"));
+    }
+
+    #[test]
+    fn picks_a_free_name_on_collision() {
+        let temp = TempDir::new().expect("temp dir");
+        let trash = temp_trash(&temp, None);
+        for _ in 0..2 {
+            fs::create_dir_all(temp.path().join("cache").join("nested")).expect("create");
+            trash_into(&temp.path().join("cache"), &trash, "2024-03-01T10:20:30").expect("trash");
+        }
+
+        assert!(trash.root.join("files").join("cache").is_dir());
+        assert!(
+            trash
+                .root
+                .join("files")
+                .join("cache.2")
+                .join("nested")
+                .is_dir()
+        );
+        assert!(trash.root.join("info").join("cache.2.trashinfo").exists());
+    }
+
+    #[test]
+    fn mount_trash_records_paths_relative_to_topdir() {
+        let temp = TempDir::new().expect("temp dir");
+        let trash = temp_trash(&temp, Some(temp.path().to_path_buf()));
+        fs::create_dir(temp.path().join("media")).expect("create media");
+        let target = temp.path().join("media").join("clip.mp4");
+        fs::write(&target, "x").expect("write target");
+
+        trash_into(&target, &trash, "2024-03-01T10:20:30").expect("trash");
+        let info = fs::read_to_string(trash.root.join("info").join("clip.mp4.trashinfo"))
+            .expect("read trashinfo");
+        assert!(info.contains("\nPath=media/clip.mp4\n"));
+    }
+
+    #[test]
+    fn failed_rename_removes_trashinfo() {
+        let temp = TempDir::new().expect("temp dir");
+        let trash = temp_trash(&temp, None);
+
+        let error = trash_into(&temp.path().join("missing"), &trash, "2024-03-01T10:20:30")
+            .expect_err("missing target");
+        assert_eq!(error.kind(), io::ErrorKind::NotFound);
+        assert!(!trash.root.join("info").join("missing.trashinfo").exists());
+    }
+}
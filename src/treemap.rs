@@ -0,0 +1,168 @@
+//! Squarified treemap layout (Bruls, Huizing, van Wijk) over terminal cells.
+
+use ratatui::layout::Rect;
+
+/// Floating-point rectangle used while laying out, rounded to cells at the end.
+#[derive(Debug, Clone, Copy)]
+struct Area {
+    x: f64,
+    y: f64,
+    w: f64,
+    h: f64,
+}
+
+/// Splits `area` into one rectangle per weight, proportional to the weights and
+/// as close to square as the greedy row packing allows.
+///
+/// The result is aligned with `weights`; zero weights, and weights too small to
+/// cover a whole cell, get an empty rectangle. Larger weights are placed first,
+/// so the input order does not matter.
+pub fn squarify(weights: &[u64], area: Rect) -> Vec<Rect> {
+    let mut rects = vec![Rect::default(); weights.len()];
+    let total: u64 = weights.iter().sum();
+    if total == 0 || area.is_empty() {
+        return rects;
+    }
+
+    let mut order: Vec<usize> = (0..weights.len()).filter(|i| weights[*i] > 0).collect();
+    order.sort_by(|a, b| weights[*b].cmp(&weights[*a]).then(a.cmp(b)));
+
+    let scale = f64::from(area.width) * f64::from(area.height) / total as f64;
+    let scaled: Vec<f64> = weights.iter().map(|w| *w as f64 * scale).collect();
+    let mut free = Area {
+        x: f64::from(area.x),
+        y: f64::from(area.y),
+        w: f64::from(area.width),
+        h: f64::from(area.height),
+    };
+
+    let mut row: Vec<usize> = Vec::new();
+    let mut next = 0;
+    while next < order.len() {
+        let side = free.w.min(free.h);
+        let candidate = order[next];
+        let keeps_improving = row.is_empty() || {
+            let current = worst_ratio(row.iter().map(|i| scaled[*i]), side);
+            let extended = worst_ratio(row.iter().chain([&candidate]).map(|i| scaled[*i]), side);
+            extended <= current
+        };
+        if keeps_improving {
+            row.push(candidate);
+            next += 1;
+        } else {
+            place_row(&row, &scaled, &mut free, &mut rects);
+            row.clear();
+        }
+    }
+    place_row(&row, &scaled, &mut free, &mut rects);
+
+    rects
+}
+
+/// Worst aspect ratio of a row of `areas` laid along a side of length `side`.
+fn worst_ratio(areas: impl Iterator<Item = f64> + Clone, side: f64) -> f64 {
+    let sum: f64 = areas.clone().sum();
+    if sum <= 0.0 || side <= 0.0 {
+        return f64::INFINITY;
+    }
+    let (side_sq, sum_sq) = (side * side, sum * sum);
+    areas
+        .map(|area| (side_sq * area / sum_sq).max(sum_sq / (side_sq * area)))
+        .fold(0.0, f64::max)
+}
+
+/// Lays `row` out along the shorter side of `free` and shrinks `free` past it.
+fn place_row(row: &[usize], scaled: &[f64], free: &mut Area, rects: &mut [Rect]) {
+    let sum: f64 = row.iter().map(|i| scaled[*i]).sum();
+    if row.is_empty() || sum <= 0.0 {
+        return;
+    }
+
+    if free.w >= free.h {
+        // Column on the left edge, items stacked top to bottom.
+        let width = (sum / free.h).min(free.w);
+        let mut y = free.y;
+        for index in row {
+            let height = scaled[*index] / width;
+            rects[*index] = to_cells(free.x, y, width, height);
+            y += height;
+        }
+        free.x += width;
+        free.w -= width;
+    } else {
+        // Row along the top edge, items left to right.
+        let height = (sum / free.w).min(free.h);
+        let mut x = free.x;
+        for index in row {
+            let width = scaled[*index] / height;
+            rects[*index] = to_cells(x, free.y, width, height);
+            x += width;
+        }
+        free.y += height;
+        free.h -= height;
+    }
+}
+
+/// Rounds both edges independently so neighbouring rectangles share borders
+/// without gaps or overlap.
+fn to_cells(x: f64, y: f64, w: f64, h: f64) -> Rect {
+    let (left, top) = (x.round(), y.round());
+    let (right, bottom) = ((x + w).round(), (y + h).round());
+    Rect {
+        x: left as u16,
+        y: top as u16,
+        width: (right - left).max(0.0) as u16,
+        height: (bottom - top).max(0.0) as u16,
+    }
+}
+
+/// Index of the non-empty rectangle containing the cell at (`column`, `row`).
+pub fn hit_test(rects: &[Rect], column: u16, row: u16) -> Option<usize> {
+    rects.iter().position(|rect| {
+        !rect.is_empty()
+            && column >= rect.x
+            && column < rect.x.saturating_add(rect.width)
+            && row >= rect.y
+            && row < rect.y.saturating_add(rect.height)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cells(rect: &Rect) -> u32 {
+        u32::from(rect.width) * u32::from(rect.height)
+    }
+
+    #[test]
+    fn tiles_the_whole_area_proportionally() {
+        let area = Rect::new(2, 3, 60, 20);
+        let weights = [600, 300, 200, 100, 0];
+        let rects = squarify(&weights, area);
+
+        assert!(rects[4].is_empty());
+        assert_eq!(rects.iter().map(cells).sum::<u32>(), 60 * 20);
+        for (weight, rect) in weights.iter().zip(&rects).take(4) {
+            let expected = f64::from(60 * 20) * *weight as f64 / 1200.0;
+            assert!((f64::from(cells(rect)) - expected).abs() <= expected * 0.1);
+            assert!(rect.x >= area.x && rect.right() <= area.right());
+            assert!(rect.y >= area.y && rect.bottom() <= area.bottom());
+        }
+        for (i, a) in rects.iter().enumerate() {
+            for b in rects.iter().skip(i + 1) {
+                assert!(a.intersection(*b).is_empty());
+            }
+        }
+    }
+
+    #[test]
+    fn places_largest_first_and_hit_tests_cells() {
+        let rects = squarify(&[1, 3], Rect::new(0, 0, 40, 10));
+        assert_eq!(rects[1], Rect::new(0, 0, 30, 10));
+        assert_eq!(rects[0], Rect::new(30, 0, 10, 10));
+        assert_eq!(hit_test(&rects, 35, 9), Some(0));
+        assert_eq!(hit_test(&rects, 0, 0), Some(1));
+        assert_eq!(hit_test(&rects, 40, 0), None);
+    }
+}
@@ -1,28 +1,102 @@
+use std::time::Duration;
+
 use ratatui::layout::{Alignment, Constraint, Layout, Rect};
-use ratatui::style::{Modifier, Style};
+use ratatui::style::{Color, Modifier, Style};
 use ratatui::text::{Line, Span};
-use ratatui::widgets::{Block, Borders, Cell, Clear, Paragraph, Row, Table, Wrap};
+use ratatui::widgets::{Block, BorderType, Borders, Cell, Clear, Paragraph, Row, Table, Wrap};
 
-use crate::model::FsEntryKind;
+use crate::delete::DeleteMode;
+use crate::diff::DiffStatus;
+use crate::filetypes::FileCategory;
+use crate::keymap::{Action, KeyBinding, Keymap};
+use crate::model::{ColumnVisibility, FsEntryKind, TableColumn};
 use crate::theme::{ThemePalette, current_theme};
+use crate::treemap::squarify;
 
 pub const HEADER_HEIGHT: u16 = 5;
 pub const FOOTER_HEIGHT: u16 = 5;
+/// Paths listed in a batch delete dialog before the rest is collapsed.
+const MAX_DIALOG_TARGETS: usize = 12;
 
 #[derive(Debug, Clone)]
 pub struct RowModel {
     pub name: String,
     pub kind: FsEntryKind,
     pub size_bytes: u64,
+    /// Time since the newest modification at or below the entry.
+    pub modified_age: Option<Duration>,
     pub path_display: String,
     pub is_loading: bool,
+    pub excluded_entries: u64,
+    pub marked: bool,
+    pub diff: Option<DiffStatus>,
+    pub delta_bytes: i64,
+    /// Indentation guides and expand marker drawn before the name in tree mode.
+    pub tree_prefix: String,
+    /// Size of the largest sibling in tree mode, so bars compare one level at a time.
+    pub sibling_max_bytes: Option<u64>,
 }
 
 #[derive(Debug, Clone)]
 pub enum DialogStateView {
     None,
-    Confirm { target: String },
-    TypePhrase { target: String, typed: String },
+    Confirm {
+        targets: Vec<String>,
+        total_size: String,
+    },
+    TypePhrase {
+        targets: Vec<String>,
+        total_size: String,
+        typed: String,
+    },
+    Summary {
+        lines: Vec<String>,
+    },
+}
+
+/// One aggregated row of a breakdown panel; `bar_bytes` drives the bar column.
+#[derive(Debug, Clone)]
+pub struct PanelRow {
+    pub cells: Vec<String>,
+    pub bar_bytes: u64,
+}
+
+/// A sortable aggregate table shown in place of the children table.
+#[derive(Debug, Clone)]
+pub struct PanelView {
+    pub title: String,
+    pub columns: Vec<(String, Constraint)>,
+    pub rows: Vec<PanelRow>,
+    pub selected_index: usize,
+    pub scroll_offset: usize,
+    pub empty_hint: String,
+}
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum TreemapColoring {
+    Depth,
+    Category,
+}
+
+/// One treemap rectangle; directories carry their children for a nested level.
+#[derive(Debug, Clone)]
+pub struct TreemapTile {
+    pub name: String,
+    pub size_bytes: u64,
+    /// Category of a file, or the dominant category below a directory.
+    pub category: Option<FileCategory>,
+    pub is_loading: bool,
+    pub marked: bool,
+    pub children: Vec<TreemapTile>,
+}
+
+/// The children of the current path drawn as a treemap instead of the table;
+/// tiles are in table order so `selected_index` is shared with it.
+#[derive(Debug, Clone)]
+pub struct TreemapView {
+    pub tiles: Vec<TreemapTile>,
+    pub selected_index: usize,
+    pub coloring: TreemapColoring,
 }
 
 #[derive(Debug, Clone)]
@@ -32,19 +106,39 @@ pub struct ViewModel {
     pub metric: String,
     pub sort_mode: String,
     pub scan_status: String,
+    pub shared_bytes_line: Option<String>,
+    pub diff_line: Option<String>,
+    pub marked_line: Option<String>,
+    pub age_line: Option<String>,
+    pub owner_line: Option<String>,
     pub filter: String,
     pub filter_mode: bool,
+    /// Rows of the children table from `table_scroll_offset` on, as many as
+    /// fit on screen once the table height is known.
     pub rows: Vec<RowModel>,
+    pub panel: Option<PanelView>,
+    pub treemap: Option<TreemapView>,
+    pub tree_mode: bool,
     pub selected_index: usize,
     pub table_scroll_offset: usize,
+    /// Largest size among all rows, not only the built ones, for bars of rows
+    /// without a `sibling_max_bytes`.
+    pub max_size_bytes: u64,
     pub show_name_column: bool,
     pub show_kind_column: bool,
     pub show_size_column: bool,
+    pub show_modified_column: bool,
     pub show_relative_column: bool,
     pub show_path_column: bool,
+    pub show_delta_column: bool,
     pub warning_line: Option<String>,
     pub message_line: Option<String>,
     pub delete_enabled: bool,
+    pub delete_mode: DeleteMode,
+    /// Active bindings, for the help dialog, footer legend and column hotkeys.
+    pub keymap: Keymap,
+    /// `(keys, template)` for each user command from the config file.
+    pub commands: Vec<(String, String)>,
     pub dialog: DialogStateView,
     pub loading_hint: Option<String>,
     pub live_loading_line: Option<String>,
@@ -62,16 +156,28 @@ pub fn render(frame: &mut ratatui::Frame<'_>, model: &ViewModel) {
     .split(frame.area());
     let header_subtle_style = theme.text_style().add_modifier(Modifier::DIM);
 
+    let mut status_line = format!(
+        "Metric: {} | Sort: {} | Status: {}",
+        model.metric, model.sort_mode, model.scan_status
+    );
+    for note in [
+        &model.marked_line,
+        &model.diff_line,
+        &model.age_line,
+        &model.owner_line,
+        &model.shared_bytes_line,
+    ]
+    .into_iter()
+    .flatten()
+    {
+        status_line.push_str(" | ");
+        status_line.push_str(note);
+    }
+
     let header = Paragraph::new(vec![
         Line::styled(format!("Path: {}", model.current_root), theme.text_style()),
         Line::styled(model.disk_line.clone(), header_subtle_style),
-        Line::styled(
-            format!(
-                "Metric: {} | Sort: {} | Status: {}",
-                model.metric, model.sort_mode, model.scan_status
-            ),
-            header_subtle_style,
-        ),
+        Line::styled(status_line, header_subtle_style),
     ])
     .block(
         Block::default()
@@ -82,7 +188,13 @@ pub fn render(frame: &mut ratatui::Frame<'_>, model: &ViewModel) {
     );
     frame.render_widget(header, chunks[0]);
 
-    render_table(frame, chunks[1], model, &theme);
+    if let Some(panel) = &model.panel {
+        render_panel(frame, chunks[1], panel, &theme);
+    } else if let Some(treemap) = &model.treemap {
+        render_treemap(frame, chunks[1], treemap, &model.keymap, &theme);
+    } else {
+        render_table(frame, chunks[1], model, &theme);
+    }
     render_footer(frame, chunks[2], model, &theme);
 
     if !matches!(model.dialog, DialogStateView::None) {
@@ -104,8 +216,10 @@ fn render_table(
         model.show_name_column,
         model.show_kind_column,
         model.show_size_column,
+        model.show_modified_column,
         model.show_relative_column,
         model.show_path_column,
+        model.show_delta_column,
     ]
     .into_iter()
     .filter(|enabled| *enabled)
@@ -113,7 +227,7 @@ fn render_table(
 
     if visible_column_count == 0 {
         let empty = Paragraph::new(Line::styled(
-            "All columns are hidden. Use Shift+N/K/S/R/P to show columns.",
+            "All columns are hidden. Use Shift+N/K/S/M/R/P to show columns.",
             theme.warning_style(),
         ))
         .alignment(Alignment::Center)
@@ -121,7 +235,7 @@ fn render_table(
             Block::default()
                 .borders(Borders::ALL)
                 .border_style(theme.border_style())
-                .title("Children (drill down with Enter)")
+                .title(table_title(model))
                 .title_style(theme.panel_title_style()),
         )
         .wrap(Wrap { trim: true });
@@ -146,7 +260,7 @@ fn render_table(
                 Block::default()
                     .borders(Borders::ALL)
                     .border_style(theme.border_style())
-                    .title("Children (drill down with Enter)")
+                    .title(table_title(model))
                     .title_style(theme.panel_title_style()),
             )
             .wrap(Wrap { trim: true });
@@ -155,25 +269,15 @@ fn render_table(
         return;
     }
 
-    let max_size = model
-        .rows
-        .iter()
-        .map(|row| row.size_bytes)
-        .max()
-        .unwrap_or(1);
-
+    let max_size = model.max_size_bytes;
     let visible_rows = area.height.saturating_sub(3) as usize;
-    let start = model
-        .table_scroll_offset
-        .min(model.rows.len().saturating_sub(1));
     let rows = model
         .rows
         .iter()
         .enumerate()
-        .skip(start)
         .take(visible_rows)
         .map(|(idx, row)| {
-            let selected = idx == model.selected_index;
+            let selected = model.table_scroll_offset + idx == model.selected_index;
             let style = if selected {
                 theme.selected_style()
             } else if row.is_loading {
@@ -182,11 +286,24 @@ fn render_table(
                 theme.text_style()
             };
 
-            let name = if row.is_loading {
-                format!("{} [loading]", row.name)
+            let label = if row.marked {
+                format!("{}[x] {}", row.tree_prefix, row.name)
+            } else {
+                format!("{}{}", row.tree_prefix, row.name)
+            };
+            let mut name = if row.is_loading {
+                format!("{label} [loading]")
             } else {
-                row.name.clone()
+                label
             };
+            if row.excluded_entries > 0 {
+                name.push_str(&format!(" [{} excluded]", row.excluded_entries));
+            }
+            match row.diff {
+                Some(DiffStatus::Added) => name.push_str(" [new]"),
+                Some(DiffStatus::Removed) => name.push_str(" [removed]"),
+                Some(DiffStatus::Present) | None => {}
+            }
             let mut row_cells = Vec::with_capacity(visible_column_count);
             if model.show_name_column {
                 row_cells.push(Cell::from(name));
@@ -197,8 +314,24 @@ fn render_table(
             if model.show_size_column {
                 row_cells.push(Cell::from(format_bytes(row.size_bytes)));
             }
+            if model.show_delta_column {
+                row_cells.push(Cell::from(format_delta(row.delta_bytes)));
+            }
+            if model.show_modified_column {
+                row_cells.push(Cell::from(
+                    row.modified_age
+                        .map(|age| format!("{} ago", format_age(age)))
+                        .unwrap_or_else(|| "-".to_string()),
+                ));
+            }
             if model.show_relative_column {
-                let bar = make_bar_line(row.size_bytes, max_size, 18, theme, selected);
+                let bar = make_bar_line(
+                    row.size_bytes,
+                    row.sibling_max_bytes.unwrap_or(max_size),
+                    18,
+                    theme,
+                    selected,
+                );
                 row_cells.push(Cell::from(bar));
             }
             if model.show_path_column {
@@ -208,27 +341,42 @@ fn render_table(
             Row::new(row_cells).style(style)
         });
 
+    let column_header = |column: TableColumn| {
+        let hotkey = model
+            .keymap
+            .keys(Action::ToggleColumn(column))
+            .find_map(KeyBinding::as_char);
+        hotkey_label_line(ColumnVisibility::label(column), hotkey, theme)
+    };
     let mut widths = Vec::with_capacity(visible_column_count);
     let mut header_cells = Vec::with_capacity(visible_column_count);
     if model.show_name_column {
-        widths.push(Constraint::Length(28));
-        header_cells.push(Cell::from(hotkey_label_line("Name", "N", theme)));
+        widths.push(Constraint::Length(if model.tree_mode { 44 } else { 28 }));
+        header_cells.push(Cell::from(column_header(TableColumn::Name)));
     }
     if model.show_kind_column {
         widths.push(Constraint::Length(8));
-        header_cells.push(Cell::from(hotkey_label_line("Kind", "K", theme)));
+        header_cells.push(Cell::from(column_header(TableColumn::Kind)));
     }
     if model.show_size_column {
         widths.push(Constraint::Length(12));
-        header_cells.push(Cell::from(hotkey_label_line("Size", "S", theme)));
+        header_cells.push(Cell::from(column_header(TableColumn::Size)));
+    }
+    if model.show_delta_column {
+        widths.push(Constraint::Length(12));
+        header_cells.push(Cell::from(Line::styled("Change", theme.header_style())));
+    }
+    if model.show_modified_column {
+        widths.push(Constraint::Length(10));
+        header_cells.push(Cell::from(column_header(TableColumn::Modified)));
     }
     if model.show_relative_column {
         widths.push(Constraint::Length(20));
-        header_cells.push(Cell::from(hotkey_label_line("Relative", "R", theme)));
+        header_cells.push(Cell::from(column_header(TableColumn::Relative)));
     }
     if model.show_path_column {
         widths.push(Constraint::Min(10));
-        header_cells.push(Cell::from(hotkey_label_line("Path", "P", theme)));
+        header_cells.push(Cell::from(column_header(TableColumn::Path)));
     }
 
     let table = Table::new(rows, widths)
@@ -237,13 +385,218 @@ fn render_table(
             Block::default()
                 .borders(Borders::ALL)
                 .border_style(theme.border_style())
-                .title("Children (drill down with Enter)")
+                .title(table_title(model))
                 .title_style(theme.panel_title_style()),
         );
 
     frame.render_widget(table, area);
 }
 
+fn table_title(model: &ViewModel) -> &'static str {
+    if model.tree_mode {
+        "Tree (Right expands, Left collapses, Enter drills down)"
+    } else {
+        "Children (drill down with Enter)"
+    }
+}
+
+fn render_panel(
+    frame: &mut ratatui::Frame<'_>,
+    area: Rect,
+    panel: &PanelView,
+    theme: &ThemePalette,
+) {
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .border_style(theme.border_style())
+        .title(panel.title.clone())
+        .title_style(theme.panel_title_style());
+
+    if panel.rows.is_empty() {
+        let empty = Paragraph::new(Line::styled(panel.empty_hint.clone(), theme.muted_style()))
+            .alignment(Alignment::Center)
+            .block(block)
+            .wrap(Wrap { trim: true });
+        frame.render_widget(empty, area);
+        return;
+    }
+
+    let max_size = panel
+        .rows
+        .iter()
+        .map(|row| row.bar_bytes)
+        .max()
+        .unwrap_or(1);
+
+    let visible_rows = area.height.saturating_sub(3) as usize;
+    let start = panel.scroll_offset.min(panel.rows.len().saturating_sub(1));
+    let rows = panel
+        .rows
+        .iter()
+        .enumerate()
+        .skip(start)
+        .take(visible_rows)
+        .map(|(idx, row)| {
+            let selected = idx == panel.selected_index;
+            let style = if selected {
+                theme.selected_style()
+            } else {
+                theme.text_style()
+            };
+
+            let mut row_cells: Vec<Cell> = row.cells.iter().cloned().map(Cell::from).collect();
+            row_cells.push(Cell::from(make_bar_line(
+                row.bar_bytes,
+                max_size,
+                18,
+                theme,
+                selected,
+            )));
+            Row::new(row_cells).style(style)
+        });
+
+    let mut widths: Vec<Constraint> = panel.columns.iter().map(|(_, width)| *width).collect();
+    widths.push(Constraint::Min(20));
+    let mut header_cells: Vec<Cell> = panel
+        .columns
+        .iter()
+        .map(|(label, _)| Cell::from(Line::styled(label.clone(), theme.header_style())))
+        .collect();
+    header_cells.push(Cell::from(Line::styled("Relative", theme.header_style())));
+
+    let table = Table::new(rows, widths)
+        .header(Row::new(header_cells).style(theme.header_style()))
+        .block(block);
+
+    frame.render_widget(table, area);
+}
+
+/// Tile rectangles for the treemap drawn in `area`, aligned with `weights`;
+/// shared with mouse hit-testing so clicks land on what was drawn.
+pub fn treemap_layout(area: Rect, weights: &[u64]) -> Vec<Rect> {
+    squarify(weights, Block::default().borders(Borders::ALL).inner(area))
+}
+
+fn render_treemap(
+    frame: &mut ratatui::Frame<'_>,
+    area: Rect,
+    treemap: &TreemapView,
+    keymap: &Keymap,
+    theme: &ThemePalette,
+) {
+    let (coloring, next) = match treemap.coloring {
+        TreemapColoring::Depth => ("depth", "by category"),
+        TreemapColoring::Category => ("file category", "table"),
+    };
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .border_style(theme.border_style())
+        .title(format!(
+            "Treemap by {coloring} ({}: {next}, {}: open)",
+            keymap.primary(Action::Treemap),
+            keymap.primary(Action::Open),
+        ))
+        .title_style(theme.panel_title_style());
+
+    let weights: Vec<u64> = treemap.tiles.iter().map(|tile| tile.size_bytes).collect();
+    if weights.iter().all(|weight| *weight == 0) {
+        let empty = Paragraph::new(Line::styled(
+            "Nothing to draw yet: no entry below this path has a size.",
+            theme.muted_style(),
+        ))
+        .alignment(Alignment::Center)
+        .block(block);
+        frame.render_widget(empty, area);
+        return;
+    }
+    frame.render_widget(block, area);
+
+    let rects = treemap_layout(area, &weights);
+    for (index, (tile, rect)) in treemap.tiles.iter().zip(rects).enumerate() {
+        if rect.is_empty() {
+            continue;
+        }
+        let selected = index == treemap.selected_index;
+        let fill = treemap_color(treemap.coloring, tile, 0, index, theme);
+
+        if rect.width < 4 || rect.height < 3 {
+            let style = if selected {
+                theme.selected_style()
+            } else {
+                theme.treemap_tile_style(fill)
+            };
+            frame.render_widget(Paragraph::new(tile_label(tile)).style(style), rect);
+            continue;
+        }
+
+        let tile_block = Block::default()
+            .borders(Borders::ALL)
+            .border_type(if selected {
+                BorderType::Thick
+            } else {
+                BorderType::Plain
+            })
+            .border_style(if selected {
+                theme.selected_style()
+            } else {
+                Style::default().fg(fill)
+            })
+            .title(tile_label(tile));
+        let inner = tile_block.inner(rect);
+        frame.render_widget(tile_block, rect);
+
+        if tile.children.is_empty() {
+            let style = theme.treemap_tile_style(fill);
+            let mut lines = vec![Line::from(format_bytes(tile.size_bytes))];
+            if tile.is_loading {
+                lines.push(Line::from("[loading]"));
+            }
+            frame.render_widget(Paragraph::new(lines).style(style), inner);
+            continue;
+        }
+
+        let child_weights: Vec<u64> = tile.children.iter().map(|child| child.size_bytes).collect();
+        for (child_index, (child, child_rect)) in tile
+            .children
+            .iter()
+            .zip(squarify(&child_weights, inner))
+            .enumerate()
+        {
+            if child_rect.is_empty() {
+                continue;
+            }
+            let child_fill = treemap_color(treemap.coloring, child, 1, child_index, theme);
+            let mut lines = vec![Line::from(child.name.clone())];
+            if child_rect.height > 1 {
+                lines.push(Line::from(format_bytes(child.size_bytes)));
+            }
+            frame.render_widget(
+                Paragraph::new(lines).style(theme.treemap_tile_style(child_fill)),
+                child_rect,
+            );
+        }
+    }
+}
+
+fn treemap_color(
+    coloring: TreemapColoring,
+    tile: &TreemapTile,
+    depth: usize,
+    index: usize,
+    theme: &ThemePalette,
+) -> Color {
+    let base = match coloring {
+        TreemapColoring::Depth => theme.treemap_depth_color(depth),
+        TreemapColoring::Category => theme.treemap_category_color(tile.category),
+    };
+    theme.treemap_shade(base, index)
+}
+
+fn tile_label(tile: &TreemapTile) -> String {
+    let mark = if tile.marked { "[x] " } else { "" };
+    format!("{mark}{} {}", tile.name, format_bytes(tile.size_bytes))
+}
+
 fn render_footer(
     frame: &mut ratatui::Frame<'_>,
     area: Rect,
@@ -265,13 +618,40 @@ fn render_footer(
         ));
     }
 
+    let keymap = &model.keymap;
+    let key = |action| keymap.primary(action);
     lines.push(Line::styled(
-        "Legend: ?/F1 help | q quit | j/k move | Enter open | h/back up | / filter | wheel scroll | click select",
+        format!(
+            "Legend: {} help | {} quit | {}/{} move | {} open | {} up | {} filter | wheel scroll | click select",
+            keymap.label(Action::Help, "/"),
+            key(Action::Quit),
+            key(Action::MoveDown),
+            key(Action::MoveUp),
+            key(Action::Open),
+            key(Action::Parent),
+            key(Action::Filter),
+        ),
         theme.accent_style(),
     ));
-    let mut quick_actions = String::from("Actions: s sort | m metric | r rescan");
+    let mut quick_actions = format!(
+        "Actions: {} sort | {} metric | {} tree | {} treemap | {} file types | {} owners | {} largest files | {} duplicates | {} rescan",
+        key(Action::CycleSort),
+        key(Action::ToggleMetric),
+        key(Action::TreeMode),
+        key(Action::Treemap),
+        key(Action::CycleFileTypes),
+        key(Action::CycleOwners),
+        key(Action::LargestFiles),
+        key(Action::Duplicates),
+        key(Action::Rescan),
+    );
     if model.delete_enabled {
-        quick_actions.push_str(" | d delete");
+        quick_actions.push_str(&format!(
+            " | {} mark | {} delete ({})",
+            key(Action::Mark),
+            key(Action::Delete),
+            model.delete_mode.as_str()
+        ));
     }
     lines.push(Line::styled(quick_actions, theme.accent_style()));
 
@@ -307,25 +687,61 @@ fn render_footer(
 }
 
 fn render_delete_dialog(frame: &mut ratatui::Frame<'_>, model: &ViewModel, theme: &ThemePalette) {
-    let area = centered_rect(70, 40, frame.area());
+    let is_batch = match &model.dialog {
+        DialogStateView::Confirm { targets, .. } | DialogStateView::TypePhrase { targets, .. } => {
+            targets.len() > 1
+        }
+        DialogStateView::Summary { .. } => true,
+        DialogStateView::None => false,
+    };
+    let area = centered_rect(70, if is_batch { 70 } else { 40 }, frame.area());
     frame.render_widget(Clear, area);
 
-    let text = match &model.dialog {
-        DialogStateView::None => String::new(),
-        DialogStateView::Confirm { target } => format!(
-            "Delete target?\n\n{}\n\nPress Enter to continue or Esc to cancel.",
-            target
+    let (title, mode_line) = match model.delete_mode {
+        DeleteMode::Trash => (
+            "Delete Confirmation (move to trash)",
+            "Mode: trash - the entry can be restored from your desktop trash.",
+        ),
+        DeleteMode::Permanent => (
+            "Delete Confirmation (PERMANENT)",
+            "Mode: permanent - the entry is removed from disk and cannot be restored.",
         ),
-        DialogStateView::TypePhrase { target, typed } => format!(
-            "Type DELETE to confirm removal:\n\n{}\n\nInput: {}",
-            target, typed
+    };
+    let (title, text) = match &model.dialog {
+        DialogStateView::None => (title, String::new()),
+        DialogStateView::Confirm {
+            targets,
+            total_size,
+        } => (
+            title,
+            format!(
+                "{}\n\n{}\n\nReclaimable: {total_size}\n{mode_line}\n\nPress Enter to continue or Esc to cancel.",
+                delete_heading("Delete", targets),
+                list_targets(targets)
+            ),
+        ),
+        DialogStateView::TypePhrase {
+            targets,
+            total_size,
+            typed,
+        } => (
+            title,
+            format!(
+                "Type DELETE to confirm removal:\n\n{}\n\nReclaimable: {total_size}\n{mode_line}\n\nInput: {}",
+                list_targets(targets),
+                typed
+            ),
+        ),
+        DialogStateView::Summary { lines } => (
+            "Delete Summary",
+            format!("{}\n\nPress any key to close.", lines.join("\n")),
         ),
     };
 
     let dialog = Paragraph::new(text)
         .block(
             Block::default()
-                .title("Delete Confirmation")
+                .title(title)
                 .borders(Borders::ALL)
                 .border_style(theme.danger_style())
                 .title_style(theme.danger_style())
@@ -336,33 +752,95 @@ fn render_delete_dialog(frame: &mut ratatui::Frame<'_>, model: &ViewModel, theme
     frame.render_widget(dialog, area);
 }
 
+fn delete_heading(verb: &str, targets: &[String]) -> String {
+    match targets.len() {
+        1 => format!("{verb} target?"),
+        count => format!("{verb} {count} marked entries?"),
+    }
+}
+
+fn list_targets(targets: &[String]) -> String {
+    let mut listed = targets
+        .iter()
+        .take(MAX_DIALOG_TARGETS)
+        .cloned()
+        .collect::<Vec<_>>()
+        .join("\n");
+    if targets.len() > MAX_DIALOG_TARGETS {
+        listed.push_str(&format!(
+            "\n... and {} more",
+            targets.len() - MAX_DIALOG_TARGETS
+        ));
+    }
+    listed
+}
+
 fn render_help_dialog(frame: &mut ratatui::Frame<'_>, model: &ViewModel, theme: &ThemePalette) {
     let area = centered_rect(80, 80, frame.area());
     frame.render_widget(Clear, area);
 
+    let keys = |action| model.keymap.label(action, " / ");
+    let delete = keys(Action::Delete);
     let delete_line = if model.delete_enabled {
-        "d: delete selected item (requires typing DELETE)"
+        match model.delete_mode {
+            DeleteMode::Trash => {
+                format!("{delete}: move selected/marked items to trash (requires typing DELETE)")
+            }
+            DeleteMode::Permanent => {
+                format!(
+                    "{delete}: permanently delete selected/marked items (requires typing DELETE)"
+                )
+            }
+        }
     } else {
-        "d: delete is disabled in this session (--no-delete)"
+        format!("{delete}: delete is disabled in this session (--no-delete)")
     };
 
+    let command_lines: String = model
+        .commands
+        .iter()
+        .map(|(keys, template)| format!("  {keys}: {template}\n"))
+        .collect();
+
     let text = format!(
         "StorageScope Help\n\n\
 Navigation:\n\
-  j / k or Up / Down: move selection\n\
-  Enter: open selected directory\n\
-  h or Backspace: go to parent directory\n\n\
+  {down}: move selection down\n\
+  {up}: move selection up\n\
+  {open}: open selected directory\n\
+  {parent}: go to parent directory\n\n\
 Scan and View:\n\
-  r: rescan current path\n\
-  s: cycle sort mode\n\
-  Shift+N: toggle Name column\n\
-  Shift+K: toggle Kind column\n\
-  Shift+S: toggle Size column\n\
-  Shift+R: toggle Relative column\n\
-  Shift+P: toggle Path column\n\
-  m: toggle size metric (allocated/apparent)\n\
-  /: filter by name/path\n\
-  Esc: clear filter or close dialog\n\n\
+  {rescan}: rescan current path\n\
+  {sort}: cycle sort mode, including oldest-modified first (growth sort is available with --diff-against)\n\
+  {name_column}: toggle Name column\n\
+  {kind_column}: toggle Kind column\n\
+  {size_column}: toggle Size column\n\
+  {modified_column}: toggle Modified column (age of the newest change below the entry)\n\
+  {relative_column}: toggle Relative column\n\
+  {path_column}: toggle Path column\n\
+  {metric}: toggle size metric (allocated/apparent)\n\
+  {tree}: toggle tree mode; {expand} expands the selected directory in place,\n\
+     {collapse} collapses it or jumps to its parent row\n\
+  {treemap}: cycle table / treemap colored by depth / treemap colored by file category\n\
+     (selection, {open}, marks and delete work on treemap tiles as in the table)\n\
+  {file_types}: cycle children / file types by extension / file types by category\n\
+     ({sort} sorts the breakdown by size, file count or name; {clear} returns)\n\
+  {owners}: cycle children / usage by user / usage by group; {open} on an owner\n\
+     lists only entries holding its bytes ({clear} clears the owner filter)\n\
+  {largest}: largest files anywhere below the current path ({open} opens the folder)\n\
+  {duplicates}: duplicate files below the current path, even with --show-files false;\n\
+     {mark} marks copies, {delete} deletes them but always keeps one copy per group\n\
+  {filter}: filter by name/path\n\
+  {clear}: clear filter, owner filter and marks, or close dialog\n\n\
+Marking:\n\
+  {mark}: mark/unmark selected entry and move down\n\
+  {mark_all}: mark all visible entries (again to unmark them)\n\
+  [x] rows are deleted together with {delete}; the dialog shows their combined size\n\n\
+External:\n\
+  {shell}: open $SHELL in the selected directory (exit the shell to return)\n\
+  {open_external}: open the selected entry with the default application\n\
+  {copy_path}: copy the selected path to the clipboard (OSC 52)\n\
+{commands}\n\
 Mouse:\n\
   Wheel: scroll selection\n\
   Left click: select row\n\
@@ -372,10 +850,44 @@ Safety:\n\
   {delete_line}\n\n\
 Loading Indicators:\n\
   [loading] on a row means directory size is still being calculated\n\
+  [new] / [removed] and the Change column compare against --diff-against\n\
+  [N excluded] means N children were skipped by --exclude/--include (size unknown)\n\
   Footer spinner means scan is still in progress and rows may update\n\n\
 Help:\n\
-  ? or F1: open/close this help\n\
-  q: quit app (or close help when this modal is open)"
+  {help}: open/close this help\n\
+  {quit}: quit app (or close help when this modal is open)\n\
+  Keys can be changed in the [keys] table of the config file",
+        down = keys(Action::MoveDown),
+        up = keys(Action::MoveUp),
+        open = keys(Action::Open),
+        parent = keys(Action::Parent),
+        rescan = keys(Action::Rescan),
+        sort = keys(Action::CycleSort),
+        name_column = keys(Action::ToggleColumn(TableColumn::Name)),
+        kind_column = keys(Action::ToggleColumn(TableColumn::Kind)),
+        size_column = keys(Action::ToggleColumn(TableColumn::Size)),
+        modified_column = keys(Action::ToggleColumn(TableColumn::Modified)),
+        relative_column = keys(Action::ToggleColumn(TableColumn::Relative)),
+        path_column = keys(Action::ToggleColumn(TableColumn::Path)),
+        metric = keys(Action::ToggleMetric),
+        tree = keys(Action::TreeMode),
+        expand = keys(Action::Expand),
+        collapse = keys(Action::Collapse),
+        treemap = keys(Action::Treemap),
+        file_types = keys(Action::CycleFileTypes),
+        clear = keys(Action::Clear),
+        owners = keys(Action::CycleOwners),
+        largest = keys(Action::LargestFiles),
+        duplicates = keys(Action::Duplicates),
+        mark = keys(Action::Mark),
+        filter = keys(Action::Filter),
+        mark_all = keys(Action::MarkAll),
+        help = keys(Action::Help),
+        quit = keys(Action::Quit),
+        shell = keys(Action::Shell),
+        open_external = keys(Action::OpenExternal),
+        copy_path = keys(Action::CopyPath),
+        commands = command_lines,
     );
 
     let dialog = Paragraph::new(text)
@@ -399,7 +911,7 @@ fn hotkey_key_style(theme: &ThemePalette) -> Style {
         .add_modifier(Modifier::BOLD | Modifier::UNDERLINED)
 }
 
-fn hotkey_label_line(label: &str, key: &str, theme: &ThemePalette) -> Line<'static> {
+fn hotkey_label_line(label: &str, key: Option<char>, theme: &ThemePalette) -> Line<'static> {
     let mut spans = Vec::new();
     let mut chars = label.chars();
     let first = chars.next();
@@ -407,12 +919,12 @@ fn hotkey_label_line(label: &str, key: &str, theme: &ThemePalette) -> Line<'stat
     let key_style = hotkey_key_style(theme);
     let label_style = theme.header_style();
 
-    if let Some(first_char) = first {
-        if first_char.eq_ignore_ascii_case(&key.chars().next().unwrap_or(first_char)) {
-            spans.push(Span::styled(first_char.to_string(), key_style));
-            spans.push(Span::styled(rest, label_style));
-            return Line::from(spans);
-        }
+    if let Some(first_char) = first
+        && key.is_some_and(|key| first_char.eq_ignore_ascii_case(&key))
+    {
+        spans.push(Span::styled(first_char.to_string(), key_style));
+        spans.push(Span::styled(rest, label_style));
+        return Line::from(spans);
     }
 
     spans.push(Span::styled(label.to_string(), label_style));
@@ -440,6 +952,29 @@ pub fn format_bytes(bytes: u64) -> String {
     }
 }
 
+/// Compact age such as `42s`, `5m`, `3h` or `180d`.
+pub fn format_age(age: Duration) -> String {
+    let secs = age.as_secs();
+    if secs < 60 {
+        format!("{secs}s")
+    } else if secs < 3600 {
+        format!("{}m", secs / 60)
+    } else if secs < 86_400 {
+        format!("{}h", secs / 3600)
+    } else {
+        format!("{}d", secs / 86_400)
+    }
+}
+
+/// Formats a signed byte difference with an explicit `+` for growth.
+pub fn format_delta(delta: i64) -> String {
+    match delta.cmp(&0) {
+        std::cmp::Ordering::Greater => format!("+{}", format_bytes(delta.unsigned_abs())),
+        std::cmp::Ordering::Less => format!("-{}", format_bytes(delta.unsigned_abs())),
+        std::cmp::Ordering::Equal => format_bytes(0),
+    }
+}
+
 fn make_bar_line(
     value: u64,
     max: u64,
@@ -541,7 +1076,7 @@ fn centered_rect(percent_x: u16, percent_y: u16, area: Rect) -> Rect {
 
 #[cfg(test)]
 mod tests {
-    use super::{format_bytes, make_bar_line};
+    use super::{format_bytes, format_delta, make_bar_line};
     use crate::theme::current_theme;
 
     #[test]
@@ -551,6 +1086,13 @@ mod tests {
         assert_eq!(format_bytes(2048), "2.0 KB");
     }
 
+    #[test]
+    fn formats_signed_deltas() {
+        assert_eq!(format_delta(0), "0 B");
+        assert_eq!(format_delta(2048), "+2.0 KB");
+        assert_eq!(format_delta(-12), "-12 B");
+    }
+
     #[test]
     fn renders_pretty_bar() {
         let theme = current_theme();
@@ -0,0 +1,674 @@
+//! Linux-only live updates: inotify watches on every scanned directory turn changes
+//! into targeted directory refreshes instead of full rescans.
+
+use std::collections::{HashMap, HashSet};
+use std::ffi::{CString, OsStr, OsString};
+use std::io;
+use std::os::unix::ffi::OsStrExt;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+
+use crossbeam_channel::{Receiver, Sender, unbounded};
+
+use crate::model::{FsEntryKind, NodeStats, NodeSummary, ScanEvent, ScanOptions};
+use crate::scanner::refresh_dir;
+use crate::store::NodeStore;
+
+const WATCH_MASK: u32 = libc::IN_CREATE
+    | libc::IN_DELETE
+    | libc::IN_MODIFY
+    | libc::IN_CLOSE_WRITE
+    | libc::IN_ATTRIB
+    | libc::IN_MOVED_FROM
+    | libc::IN_MOVED_TO
+    | libc::IN_DELETE_SELF
+    | libc::IN_ONLYDIR;
+const POLL_INTERVAL_MS: i32 = 100;
+/// Changes are batched until the tree has been quiet this long...
+const QUIET_PERIOD: Duration = Duration::from_millis(250);
+/// ...but never held back longer than this while a build keeps writing.
+const MAX_BATCH_DELAY: Duration = Duration::from_secs(1);
+const READ_BUFFER_LEN: usize = 64 * 1024;
+
+pub struct WatchSession {
+    receiver: Receiver<ScanEvent>,
+    removed: Sender<PathBuf>,
+    stop: Arc<AtomicBool>,
+    join: Option<JoinHandle<()>>,
+}
+
+impl WatchSession {
+    pub fn receiver(&self) -> &Receiver<ScanEvent> {
+        &self.receiver
+    }
+
+    /// Tells the watcher that the app deleted `path`, so it stops watching
+    /// it and refreshes the parent's totals.
+    pub fn removed(&self, path: &Path) {
+        let _ = self.removed.send(path.to_path_buf());
+    }
+
+    pub fn stop(&mut self) {
+        self.stop.store(true, Ordering::Relaxed);
+        if let Some(join) = self.join.take() {
+            let _ = join.join();
+        }
+    }
+}
+
+impl Drop for WatchSession {
+    fn drop(&mut self) {
+        self.stop();
+    }
+}
+
+/// Watches every directory in `nodes` (the completed scan below `options.root`) and
+/// streams `NodeUpdated` / `Relisted` events for whatever changes afterwards.
+pub fn start_watch(options: ScanOptions, nodes: &NodeStore) -> io::Result<WatchSession> {
+    let inotify = Inotify::new()?;
+    let (tx, rx) = unbounded();
+    let (removed_tx, removed_rx) = unbounded();
+    let stop = Arc::new(AtomicBool::new(false));
+    let stop_for_thread = Arc::clone(&stop);
+
+    // Watches are registered before returning so no change after this call is missed.
+    let mut watcher = TreeWatcher::new(options, nodes, inotify, tx, removed_rx);
+    let join = thread::spawn(move || watcher.run(&stop_for_thread));
+
+    Ok(WatchSession {
+        receiver: rx,
+        removed: removed_tx,
+        stop,
+        join: Some(join),
+    })
+}
+
+struct Inotify {
+    fd: libc::c_int,
+}
+
+impl Inotify {
+    fn new() -> io::Result<Self> {
+        // SAFETY: inotify_init1 takes only flags and returns a new descriptor or -1.
+        let fd = unsafe { libc::inotify_init1(libc::IN_NONBLOCK | libc::IN_CLOEXEC) };
+        if fd < 0 {
+            return Err(io::Error::last_os_error());
+        }
+        Ok(Self { fd })
+    }
+
+    fn add_watch(&self, path: &Path) -> io::Result<i32> {
+        let c_path = CString::new(path.as_os_str().as_bytes())
+            .map_err(|error| io::Error::new(io::ErrorKind::InvalidInput, error))?;
+        // SAFETY: `fd` is an open inotify descriptor and `c_path` is nul-terminated.
+        let wd = unsafe { libc::inotify_add_watch(self.fd, c_path.as_ptr(), WATCH_MASK) };
+        if wd < 0 {
+            return Err(io::Error::last_os_error());
+        }
+        Ok(wd)
+    }
+
+    fn remove_watch(&self, wd: i32) {
+        // SAFETY: removing an unknown watch descriptor only returns EINVAL.
+        unsafe {
+            libc::inotify_rm_watch(self.fd, wd);
+        }
+    }
+
+    /// Waits up to `timeout_ms` and returns the events read.
+    fn read_events(&self, timeout_ms: i32, buf: &mut [u8]) -> io::Result<Vec<WatchEvent>> {
+        let mut poll_fd = libc::pollfd {
+            fd: self.fd,
+            events: libc::POLLIN,
+            revents: 0,
+        };
+        // SAFETY: `poll_fd` is a valid pollfd for the duration of the call.
+        let ready = unsafe { libc::poll(&mut poll_fd, 1, timeout_ms) };
+        if ready <= 0 {
+            return Ok(Vec::new());
+        }
+
+        // SAFETY: `buf` is valid writable memory of the length passed to read.
+        let len = unsafe { libc::read(self.fd, buf.as_mut_ptr().cast(), buf.len()) };
+        if len < 0 {
+            let error = io::Error::last_os_error();
+            return if error.kind() == io::ErrorKind::WouldBlock {
+                Ok(Vec::new())
+            } else {
+                Err(error)
+            };
+        }
+
+        Ok(parse_events(&buf[..len as usize]))
+    }
+}
+
+impl Drop for Inotify {
+    fn drop(&mut self) {
+        // SAFETY: `fd` was opened by inotify_init1 and is closed exactly once.
+        unsafe {
+            libc::close(self.fd);
+        }
+    }
+}
+
+/// Watch descriptor and mask of one `inotify_event` record.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+struct WatchEvent {
+    /// -1 for `IN_Q_OVERFLOW`, which belongs to no watch.
+    wd: i32,
+    mask: u32,
+}
+
+/// Extracts the watch descriptor and mask of each `inotify_event` record in `buf`.
+fn parse_events(buf: &[u8]) -> Vec<WatchEvent> {
+    const HEADER_LEN: usize = std::mem::size_of::<libc::inotify_event>();
+    let mut events = Vec::new();
+    let mut offset = 0;
+    while offset + HEADER_LEN <= buf.len() {
+        // SAFETY: the kernel writes whole records; read_unaligned tolerates any alignment.
+        let event: libc::inotify_event =
+            unsafe { std::ptr::read_unaligned(buf[offset..].as_ptr().cast()) };
+        events.push(WatchEvent {
+            wd: event.wd,
+            mask: event.mask,
+        });
+        offset += HEADER_LEN + event.len as usize;
+    }
+    events
+}
+
+struct TreeWatcher {
+    options: ScanOptions,
+    /// The watched directories and their last known totals; files stay in the
+    /// app's store only.
+    dirs: NodeStore,
+    inotify: Inotify,
+    watches: HashMap<i32, PathBuf>,
+    watched_paths: HashMap<PathBuf, i32>,
+    tx: Sender<ScanEvent>,
+    /// Paths the app deleted itself.
+    removed: Receiver<PathBuf>,
+    limit_reported: bool,
+}
+
+impl TreeWatcher {
+    fn new(
+        options: ScanOptions,
+        nodes: &NodeStore,
+        inotify: Inotify,
+        tx: Sender<ScanEvent>,
+        removed: Receiver<PathBuf>,
+    ) -> Self {
+        let mut watcher = Self {
+            options,
+            dirs: NodeStore::new(),
+            inotify,
+            watches: HashMap::new(),
+            watched_paths: HashMap::new(),
+            tx,
+            removed,
+            limit_reported: false,
+        };
+        for (path, node) in nodes.subtree(&watcher.options.root) {
+            if is_dir_node(&path, node, watcher.options.follow_symlinks) {
+                watcher.upsert(NodeSummary::from_parts(path, *node));
+            }
+        }
+        watcher
+    }
+
+    fn run(&mut self, stop: &AtomicBool) {
+        let mut buf = vec![0_u8; READ_BUFFER_LEN];
+        let mut dirty: HashSet<PathBuf> = HashSet::new();
+        let mut batch_started: Option<Instant> = None;
+        let mut last_event = Instant::now();
+
+        while !stop.load(Ordering::Relaxed) {
+            if self.take_removed(&mut dirty) {
+                last_event = Instant::now();
+                batch_started.get_or_insert(last_event);
+            }
+
+            match self.inotify.read_events(POLL_INTERVAL_MS, &mut buf) {
+                Ok(events) if !events.is_empty() => {
+                    self.mark_dirty(&events, &mut dirty);
+                    last_event = Instant::now();
+                    batch_started.get_or_insert(last_event);
+                }
+                Ok(_) => {}
+                Err(error) => {
+                    let _ = self.tx.send(ScanEvent::Warning {
+                        path: self.options.root.clone(),
+                        message: format!("watch stopped: {error}"),
+                    });
+                    return;
+                }
+            }
+
+            let Some(started) = batch_started else {
+                continue;
+            };
+            if last_event.elapsed() >= QUIET_PERIOD || started.elapsed() >= MAX_BATCH_DELAY {
+                self.refresh(dirty.drain().collect());
+                batch_started = None;
+            }
+        }
+    }
+
+    /// Drops the entries the app deleted from the index and adds their parents
+    /// to `dirty`; returns whether there were any.
+    fn take_removed(&mut self, dirty: &mut HashSet<PathBuf>) -> bool {
+        let removed: Vec<PathBuf> = self.removed.try_iter().collect();
+        for path in &removed {
+            self.forget(path);
+            if let Some(parent) = path
+                .parent()
+                .filter(|parent| parent.starts_with(&self.options.root))
+            {
+                dirty.insert(parent.to_path_buf());
+            }
+        }
+        !removed.is_empty()
+    }
+
+    /// Adds the directories `events` affect to `dirty` and drops watches the
+    /// kernel removed.
+    fn mark_dirty(&mut self, events: &[WatchEvent], dirty: &mut HashSet<PathBuf>) {
+        for event in events {
+            if event.mask & libc::IN_Q_OVERFLOW != 0 {
+                // Some events were dropped, so any watched directory may be stale.
+                let _ = self.tx.send(ScanEvent::Warning {
+                    path: self.options.root.clone(),
+                    message: "inotify event queue overflowed; refreshing every watched directory"
+                        .to_string(),
+                });
+                dirty.extend(self.watched_paths.keys().cloned());
+                continue;
+            }
+            let Some(path) = self.watches.get(&event.wd).cloned() else {
+                continue;
+            };
+            if event.mask & libc::IN_DELETE_SELF != 0 {
+                // Its parent lists it no more; the directory itself cannot be re-read.
+                if let Some(parent) = path
+                    .parent()
+                    .filter(|parent| parent.starts_with(&self.options.root))
+                {
+                    dirty.insert(parent.to_path_buf());
+                }
+            } else if event.mask & libc::IN_IGNORED == 0 {
+                dirty.insert(path.clone());
+            }
+            if event.mask & libc::IN_IGNORED != 0 {
+                // The kernel dropped the watch (directory deleted, filesystem
+                // unmounted); its descriptor may be handed out again.
+                self.watches.remove(&event.wd);
+                if self.watched_paths.get(&path) == Some(&event.wd) {
+                    self.watched_paths.remove(&path);
+                }
+            }
+        }
+    }
+
+    /// Refreshes the deepest directories first so their parents aggregate fresh totals.
+    fn refresh(&mut self, mut dirs: Vec<PathBuf>) {
+        dirs.sort_by_key(|dir| std::cmp::Reverse(dir.components().count()));
+        for dir in dirs {
+            if self.dirs.contains(&dir) {
+                self.refresh_one(&dir);
+            }
+        }
+    }
+
+    fn refresh_one(&mut self, dir: &Path) {
+        let (local_tx, local_rx) = unbounded();
+        let Some(summary) = refresh_dir(&self.options, dir, &self.dirs, &local_tx) else {
+            // The directory itself vanished; its parent's refresh reports the removal.
+            return;
+        };
+        drop(local_tx);
+
+        let mut children: Vec<OsString> = Vec::new();
+        for event in local_rx.try_iter() {
+            match event {
+                ScanEvent::NodeUpdated(node) => {
+                    if node.path.parent() == Some(dir)
+                        && let Some(name) = node.path.file_name()
+                    {
+                        children.push(name.to_os_string());
+                    }
+                    if node.path != dir {
+                        self.upsert(node.clone());
+                        let _ = self.tx.send(ScanEvent::NodeUpdated(node));
+                    }
+                }
+                ScanEvent::Warning { .. }
+                | ScanEvent::FileTypes { .. }
+                | ScanEvent::Owners { .. }
+                | ScanEvent::DuplicateCandidates { .. }
+                | ScanEvent::LargeFile(_) => {
+                    let _ = self.tx.send(event);
+                }
+                _ => {}
+            }
+        }
+
+        let listed: HashSet<&OsStr> = children.iter().map(OsString::as_os_str).collect();
+        let stale: Vec<PathBuf> = self
+            .dirs
+            .child_paths(dir)
+            .into_iter()
+            .filter(|path| path.file_name().is_none_or(|name| !listed.contains(name)))
+            .collect();
+        for path in stale {
+            self.forget(&path);
+        }
+        let _ = self.tx.send(ScanEvent::Relisted {
+            path: dir.to_path_buf(),
+            children,
+        });
+
+        let previous = self.dirs.get(dir).copied();
+        let current = summary.stats();
+        self.dirs.upsert(summary.clone());
+        let _ = self.tx.send(ScanEvent::NodeUpdated(summary));
+
+        if let Some(previous) = previous {
+            self.propagate(dir, &previous, &current);
+        }
+    }
+
+    /// Applies a directory's size change to each ancestor up to the scan root.
+    fn propagate(&mut self, dir: &Path, previous: &NodeStats, current: &NodeStats) {
+        for ancestor in dir.ancestors().skip(1) {
+            if !ancestor.starts_with(&self.options.root) {
+                break;
+            }
+            let Some(node) = self.dirs.get_mut(ancestor) else {
+                break;
+            };
+            node.subtract_totals(previous);
+            node.add_totals(current);
+            node.last_updated = current.last_updated;
+            let _ = self.tx.send(ScanEvent::NodeUpdated(NodeSummary::from_parts(
+                ancestor.to_path_buf(),
+                *node,
+            )));
+        }
+    }
+
+    /// Indexes and watches `node` if it is a directory.
+    fn upsert(&mut self, node: NodeSummary) {
+        if !is_dir_node(&node.path, &node.stats(), self.options.follow_symlinks) {
+            return;
+        }
+        if !self.watched_paths.contains_key(&node.path) {
+            self.watch(&node.path);
+        }
+        self.dirs.upsert(node);
+    }
+
+    fn forget(&mut self, path: &Path) {
+        let removed: Vec<PathBuf> = self.dirs.subtree(path).map(|(path, _)| path).collect();
+        for current in removed {
+            if let Some(wd) = self.watched_paths.remove(&current) {
+                self.watches.remove(&wd);
+                self.inotify.remove_watch(wd);
+            }
+        }
+        self.dirs.remove_subtree(path);
+    }
+
+    fn watch(&mut self, dir: &Path) {
+        match self.inotify.add_watch(dir) {
+            Ok(wd) => {
+                // Re-adding an existing inode returns its previous descriptor.
+                if let Some(old_path) = self.watches.insert(wd, dir.to_path_buf()) {
+                    self.watched_paths.remove(&old_path);
+                }
+                self.watched_paths.insert(dir.to_path_buf(), wd);
+            }
+            Err(error) if error.raw_os_error() == Some(libc::ENOSPC) => {
+                if !self.limit_reported {
+                    self.limit_reported = true;
+                    let _ = self.tx.send(ScanEvent::Warning {
+                        path: dir.to_path_buf(),
+                        message: "inotify watch limit reached; raise fs.inotify.max_user_watches to watch the whole tree".to_string(),
+                    });
+                }
+            }
+            Err(error) => {
+                let _ = self.tx.send(ScanEvent::Warning {
+                    path: dir.to_path_buf(),
+                    message: format!("cannot watch directory: {error}"),
+                });
+            }
+        }
+    }
+}
+
+/// Followed symlinked directories are reported with the `Symlink` kind.
+fn is_dir_node(path: &Path, node: &NodeStats, follow_symlinks: bool) -> bool {
+    match node.kind {
+        FsEntryKind::Dir => true,
+        FsEntryKind::Symlink => follow_symlinks && path.is_dir(),
+        FsEntryKind::File | FsEntryKind::Other => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use tempfile::TempDir;
+
+    use super::*;
+    use crate::model::DEFAULT_STALE_AFTER;
+    use crate::scanner::run_scan_blocking;
+
+    fn options(root: &Path) -> ScanOptions {
+        ScanOptions {
+            root: root.to_path_buf(),
+            one_file_system: true,
+            follow_symlinks: false,
+            show_hidden: true,
+            show_files: true,
+            max_depth: None,
+            threads: 1,
+            exclude_patterns: Vec::new(),
+            include_patterns: Vec::new(),
+            report_excluded: true,
+            stale_after: DEFAULT_STALE_AFTER,
+        }
+    }
+
+    fn scanned_nodes(options: &ScanOptions) -> NodeStore {
+        let mut nodes = NodeStore::new();
+        for event in run_scan_blocking(options.clone()) {
+            if let ScanEvent::NodeUpdated(node) = event {
+                nodes.upsert(node);
+            }
+        }
+        nodes
+    }
+
+    /// Collects watcher events until `done` holds for the merged tree or a timeout hits.
+    fn wait_for(
+        session: &WatchSession,
+        nodes: &mut NodeStore,
+        done: impl Fn(&NodeStore) -> bool,
+    ) -> bool {
+        let deadline = Instant::now() + Duration::from_secs(10);
+        while Instant::now() < deadline {
+            if let Ok(event) = session.receiver().recv_timeout(Duration::from_millis(100)) {
+                match event {
+                    ScanEvent::NodeUpdated(node) => {
+                        nodes.upsert(node);
+                    }
+                    ScanEvent::Relisted { path, children } => {
+                        for child in nodes.child_paths(&path) {
+                            if !children
+                                .iter()
+                                .any(|name| Some(name.as_os_str()) == child.file_name())
+                            {
+                                nodes.remove_subtree(&child);
+                            }
+                        }
+                    }
+                    _ => {}
+                }
+            }
+            if done(nodes) {
+                return true;
+            }
+        }
+        false
+    }
+
+    #[test]
+    fn reports_growth_new_entries_and_removals() {
+        let temp = TempDir::new().expect("temp dir");
+        let root = fs::canonicalize(temp.path()).expect("canonical root");
+        let build = root.join("build");
+        fs::create_dir(&build).expect("create build");
+        fs::write(build.join("old.o"), vec![0_u8; 100]).expect("write old");
+
+        let options = options(&root);
+        let mut nodes = scanned_nodes(&options);
+        let bytes = |nodes: &NodeStore, path: &Path| nodes.get(path).unwrap().apparent_bytes;
+        let root_before = bytes(&nodes, &root);
+        let session = start_watch(options, &nodes).expect("start watch");
+
+        fs::create_dir(build.join("out")).expect("create out");
+        fs::write(build.join("out").join("app"), vec![0_u8; 5000]).expect("write app");
+        fs::remove_file(build.join("old.o")).expect("remove old");
+
+        let app = build.join("out").join("app");
+        let old = build.join("old.o");
+        let updated = wait_for(&session, &mut nodes, |nodes| {
+            nodes.contains(&app)
+                && !nodes.contains(&old)
+                && bytes(nodes, &root) >= root_before + 4900
+        });
+        assert!(updated, "watcher never caught up: {nodes:#?}");
+        assert_eq!(bytes(&nodes, &app), 5000);
+        assert_eq!(nodes.get(&build).unwrap().children_count, 1);
+    }
+
+    #[test]
+    fn parses_packed_event_records() {
+        const HEADER_LEN: usize = std::mem::size_of::<libc::inotify_event>();
+        let mut buf = Vec::new();
+        for (wd, name_len) in [(3_i32, 16_u32), (7, 0)] {
+            let event = libc::inotify_event {
+                wd,
+                mask: libc::IN_CREATE,
+                cookie: 0,
+                len: name_len,
+            };
+            // SAFETY: inotify_event is plain old data.
+            let bytes: [u8; HEADER_LEN] = unsafe { std::mem::transmute(event) };
+            buf.extend_from_slice(&bytes);
+            buf.extend(std::iter::repeat_n(0_u8, name_len as usize));
+        }
+
+        assert_eq!(
+            parse_events(&buf),
+            vec![
+                WatchEvent {
+                    wd: 3,
+                    mask: libc::IN_CREATE
+                },
+                WatchEvent {
+                    wd: 7,
+                    mask: libc::IN_CREATE
+                }
+            ]
+        );
+    }
+
+    #[test]
+    fn overflow_refreshes_every_directory_and_ignored_watches_are_dropped() {
+        let temp = TempDir::new().expect("temp dir");
+        let root = fs::canonicalize(temp.path()).expect("canonical root");
+        fs::create_dir_all(root.join("build").join("out")).expect("create dirs");
+
+        let options = options(&root);
+        let nodes = scanned_nodes(&options);
+        let (tx, rx) = unbounded();
+        let (_removed_tx, removed_rx) = unbounded();
+        let mut watcher = TreeWatcher::new(
+            options,
+            &nodes,
+            Inotify::new().expect("inotify"),
+            tx,
+            removed_rx,
+        );
+        assert_eq!(watcher.watches.len(), 3);
+
+        let mut dirty = HashSet::new();
+        let overflow = WatchEvent {
+            wd: -1,
+            mask: libc::IN_Q_OVERFLOW,
+        };
+        watcher.mark_dirty(&[overflow], &mut dirty);
+        assert_eq!(dirty.len(), 3);
+        assert!(matches!(rx.try_recv(), Ok(ScanEvent::Warning { .. })));
+
+        let out = root.join("build").join("out");
+        let wd = watcher.watched_paths[&out];
+        dirty.clear();
+        watcher.mark_dirty(
+            &[
+                WatchEvent {
+                    wd,
+                    mask: libc::IN_DELETE_SELF,
+                },
+                WatchEvent {
+                    wd,
+                    mask: libc::IN_IGNORED,
+                },
+            ],
+            &mut dirty,
+        );
+        assert_eq!(dirty, HashSet::from([root.join("build")]));
+        assert!(!watcher.watches.contains_key(&wd));
+        assert!(!watcher.watched_paths.contains_key(&out));
+    }
+
+    #[test]
+    fn indexes_only_directories_and_follows_app_deletions() {
+        let temp = TempDir::new().expect("temp dir");
+        let root = fs::canonicalize(temp.path()).expect("canonical root");
+        let cache = root.join("cache");
+        fs::create_dir_all(cache.join("blobs")).expect("create dirs");
+        fs::write(cache.join("index"), vec![0_u8; 100]).expect("write index");
+
+        let options = options(&root);
+        let nodes = scanned_nodes(&options);
+        assert!(nodes.contains(&cache.join("index")));
+        let (tx, _rx) = unbounded();
+        let (removed_tx, removed_rx) = unbounded();
+        let mut watcher = TreeWatcher::new(
+            options,
+            &nodes,
+            Inotify::new().expect("inotify"),
+            tx,
+            removed_rx,
+        );
+        assert_eq!(watcher.dirs.len(), 3);
+        assert!(!watcher.dirs.contains(&cache.join("index")));
+
+        fs::remove_dir_all(&cache).expect("delete cache");
+        removed_tx.send(cache.clone()).expect("send removal");
+        let mut dirty = HashSet::new();
+        assert!(watcher.take_removed(&mut dirty));
+        assert_eq!(dirty, HashSet::from([root.clone()]));
+        assert!(!watcher.dirs.contains(&cache));
+        assert_eq!(watcher.watched_paths.len(), 1);
+    }
+}
@@ -2,8 +2,8 @@ use std::collections::HashMap;
 use std::fs;
 use std::path::Path;
 
-use storagescope::model::{NodeSummary, ScanEvent, ScanOptions};
-use storagescope::scanner::run_scan_blocking;
+use storagescope::model::{DEFAULT_STALE_AFTER, NodeSummary, ScanEvent, ScanOptions};
+use storagescope::scanner::{run_scan_blocking, start_scan};
 use tempfile::TempDir;
 
 fn collect_nodes(events: &[ScanEvent]) -> HashMap<String, NodeSummary> {
@@ -37,6 +37,11 @@ fn scan_completes_with_progress() {
         show_hidden: true,
         show_files: true,
         max_depth: None,
+        threads: 1,
+        exclude_patterns: Vec::new(),
+        include_patterns: Vec::new(),
+        report_excluded: true,
+        stale_after: DEFAULT_STALE_AFTER,
     });
 
     assert!(
@@ -61,6 +66,11 @@ fn hidden_files_obey_flag() {
         show_hidden: true,
         show_files: true,
         max_depth: None,
+        threads: 1,
+        exclude_patterns: Vec::new(),
+        include_patterns: Vec::new(),
+        report_excluded: true,
+        stale_after: DEFAULT_STALE_AFTER,
     });
     let nodes_with_hidden = collect_nodes(&with_hidden);
     assert!(nodes_with_hidden.contains_key(&path_key(&temp.path().join(".secret"))));
@@ -72,6 +82,11 @@ fn hidden_files_obey_flag() {
         show_hidden: false,
         show_files: true,
         max_depth: None,
+        threads: 1,
+        exclude_patterns: Vec::new(),
+        include_patterns: Vec::new(),
+        report_excluded: true,
+        stale_after: DEFAULT_STALE_AFTER,
     });
     let nodes_without_hidden = collect_nodes(&without_hidden);
     assert!(!nodes_without_hidden.contains_key(&path_key(&temp.path().join(".secret"))));
@@ -89,9 +104,489 @@ fn max_depth_zero_keeps_children_out() {
         show_hidden: true,
         show_files: true,
         max_depth: Some(0),
+        threads: 1,
+        exclude_patterns: Vec::new(),
+        include_patterns: Vec::new(),
+        report_excluded: true,
+        stale_after: DEFAULT_STALE_AFTER,
     });
 
     let nodes = collect_nodes(&events);
     assert!(nodes.contains_key(&path_key(temp.path())));
     assert!(!nodes.contains_key(&path_key(&temp.path().join("root-file.bin"))));
 }
+
+fn final_nodes(events: &[ScanEvent]) -> HashMap<String, (u64, u64, u64)> {
+    collect_nodes(events)
+        .into_iter()
+        .map(|(path, node)| {
+            assert!(node.is_complete, "{path} never completed");
+            (
+                path,
+                (
+                    node.apparent_bytes,
+                    node.allocated_bytes,
+                    node.children_count,
+                ),
+            )
+        })
+        .collect()
+}
+
+fn complete_progress(events: &[ScanEvent]) -> (u64, u64, u64) {
+    events
+        .iter()
+        .find_map(|event| match event {
+            ScanEvent::Complete(progress) => Some((
+                progress.visited_entries,
+                progress.apparent_bytes_seen,
+                progress.allocated_bytes_seen,
+            )),
+            _ => None,
+        })
+        .expect("scan completes")
+}
+
+fn generate_tree(root: &Path, depth: usize, fanout: usize) {
+    for file_idx in 0..fanout {
+        let size = (depth * 977 + file_idx * 131) % 5000;
+        fs::write(root.join(format!("file-{file_idx}.bin")), vec![7_u8; size]).expect("write");
+    }
+    if depth == 0 {
+        return;
+    }
+    for dir_idx in 0..fanout {
+        let child = root.join(format!("dir-{dir_idx}"));
+        fs::create_dir(&child).expect("create dir");
+        generate_tree(&child, depth - 1, fanout);
+    }
+}
+
+#[test]
+fn parallel_scan_matches_single_threaded_totals() {
+    let temp = TempDir::new().expect("temp dir");
+    generate_tree(temp.path(), 3, 4);
+
+    let scan_with = |threads: usize| {
+        run_scan_blocking(ScanOptions {
+            root: temp.path().to_path_buf(),
+            one_file_system: true,
+            follow_symlinks: false,
+            show_hidden: true,
+            show_files: true,
+            max_depth: None,
+            threads,
+            exclude_patterns: Vec::new(),
+            include_patterns: Vec::new(),
+            report_excluded: true,
+            stale_after: DEFAULT_STALE_AFTER,
+        })
+    };
+
+    let single = scan_with(1);
+    let parallel = scan_with(4);
+
+    assert_eq!(final_nodes(&single), final_nodes(&parallel));
+    assert_eq!(complete_progress(&single), complete_progress(&parallel));
+    assert_eq!(final_nodes(&parallel).len(), 1 + 4 + 16 + 64 + 4 * 85);
+}
+
+#[cfg(unix)]
+#[test]
+fn hardlinks_are_counted_once() {
+    use storagescope::model::SizeMetric;
+
+    let temp = TempDir::new().expect("temp dir");
+    let original = temp.path().join("original.bin");
+    fs::write(&original, vec![3_u8; 4096]).expect("write original");
+    fs::create_dir(temp.path().join("links")).expect("create links dir");
+    fs::hard_link(&original, temp.path().join("links").join("copy-a.bin")).expect("link a");
+    fs::hard_link(&original, temp.path().join("links").join("copy-b.bin")).expect("link b");
+
+    let events = run_scan_blocking(ScanOptions {
+        root: temp.path().to_path_buf(),
+        one_file_system: true,
+        follow_symlinks: false,
+        show_hidden: true,
+        show_files: true,
+        max_depth: None,
+        threads: 1,
+        exclude_patterns: Vec::new(),
+        include_patterns: Vec::new(),
+        report_excluded: true,
+        stale_after: DEFAULT_STALE_AFTER,
+    });
+    let nodes = collect_nodes(&events);
+    let root = nodes.get(&path_key(temp.path())).expect("root node");
+    let links = nodes
+        .get(&path_key(&temp.path().join("links")))
+        .expect("links node");
+
+    assert_eq!(root.naive_apparent_bytes - root.apparent_bytes, 2 * 4096);
+    assert_eq!(root.shared_bytes(SizeMetric::Apparent), 2 * 4096);
+    // The single-threaded walk claims root files before descending, so both links are shared.
+    assert_eq!(links.shared_bytes(SizeMetric::Apparent), 2 * 4096);
+}
+
+#[test]
+fn exclude_and_include_patterns_filter_entries() {
+    let temp = TempDir::new().expect("temp dir");
+    let app = temp.path().join("app");
+    fs::create_dir_all(app.join("node_modules").join("dep")).expect("create node_modules");
+    fs::write(
+        app.join("node_modules").join("dep").join("index.js"),
+        [0_u8; 64],
+    )
+    .expect("write dep");
+    fs::write(app.join("movie.mp4"), vec![1_u8; 512]).expect("write mp4");
+    fs::write(app.join("notes.txt"), vec![1_u8; 32]).expect("write txt");
+
+    let events = run_scan_blocking(ScanOptions {
+        root: temp.path().to_path_buf(),
+        one_file_system: true,
+        follow_symlinks: false,
+        show_hidden: true,
+        show_files: true,
+        max_depth: None,
+        threads: 1,
+        exclude_patterns: vec!["node_modules".to_string()],
+        include_patterns: vec!["*.mp4".to_string()],
+        report_excluded: true,
+        stale_after: DEFAULT_STALE_AFTER,
+    });
+    let nodes = collect_nodes(&events);
+
+    assert!(!nodes.contains_key(&path_key(&app.join("node_modules"))));
+    assert!(!nodes.contains_key(&path_key(&app.join("notes.txt"))));
+    assert!(nodes.contains_key(&path_key(&app.join("movie.mp4"))));
+
+    let app_node = nodes.get(&path_key(&app)).expect("app node");
+    assert_eq!(app_node.children_count, 1);
+    assert_eq!(app_node.excluded_entries, 2);
+}
+
+#[test]
+fn stale_bytes_roll_up_and_newest_mtime_wins() {
+    use std::fs::{File, FileTimes};
+    use std::time::{Duration, SystemTime};
+
+    let temp = TempDir::new().expect("temp dir");
+    let archive = temp.path().join("archive");
+    fs::create_dir(&archive).expect("create archive");
+    fs::write(archive.join("old.tar"), vec![1_u8; 4096]).expect("write old");
+    fs::write(archive.join("fresh.log"), vec![1_u8; 100]).expect("write fresh");
+    let long_ago = SystemTime::now() - Duration::from_secs(400 * 86_400);
+    File::options()
+        .write(true)
+        .open(archive.join("old.tar"))
+        .expect("open old")
+        .set_times(
+            FileTimes::new()
+                .set_accessed(long_ago)
+                .set_modified(long_ago),
+        )
+        .expect("age old file");
+
+    let events = run_scan_blocking(ScanOptions {
+        root: temp.path().to_path_buf(),
+        one_file_system: true,
+        follow_symlinks: false,
+        show_hidden: true,
+        show_files: true,
+        max_depth: None,
+        threads: 2,
+        exclude_patterns: Vec::new(),
+        include_patterns: Vec::new(),
+        report_excluded: true,
+        stale_after: DEFAULT_STALE_AFTER,
+    });
+    let nodes = collect_nodes(&events);
+
+    let old = &nodes[&path_key(&archive.join("old.tar"))];
+    assert_eq!(old.age.stale_apparent_bytes, 4096);
+    let root = &nodes[&path_key(temp.path())];
+    assert_eq!(root.age.stale_apparent_bytes, 4096);
+    assert!(root.age.modified > old.age.modified);
+}
+
+#[cfg(unix)]
+#[test]
+fn owner_tallies_cover_directory_and_its_leaves() {
+    use std::os::unix::fs::MetadataExt;
+
+    use storagescope::owners::{OwnerId, OwnerKind};
+
+    let temp = TempDir::new().expect("temp dir");
+    fs::create_dir(temp.path().join("logs")).expect("create logs");
+    fs::write(temp.path().join("logs").join("app.log"), vec![1_u8; 300]).expect("write log");
+    fs::write(temp.path().join("top.bin"), vec![1_u8; 700]).expect("write top");
+
+    let events = run_scan_blocking(ScanOptions {
+        root: temp.path().to_path_buf(),
+        one_file_system: true,
+        follow_symlinks: false,
+        show_hidden: true,
+        show_files: true,
+        max_depth: None,
+        threads: 2,
+        exclude_patterns: Vec::new(),
+        include_patterns: Vec::new(),
+        report_excluded: true,
+        stale_after: DEFAULT_STALE_AFTER,
+    });
+    let nodes = collect_nodes(&events);
+    let tallies: HashMap<String, _> = events
+        .iter()
+        .filter_map(|event| match event {
+            ScanEvent::Owners { path, tally } => Some((path_key(path), tally.clone())),
+            _ => None,
+        })
+        .collect();
+
+    let metadata = fs::metadata(temp.path()).expect("root metadata");
+    let user = OwnerId {
+        kind: OwnerKind::User,
+        id: metadata.uid(),
+    };
+    let logs = path_key(&temp.path().join("logs"));
+    let logs_dir_bytes = fs::metadata(temp.path().join("logs"))
+        .expect("logs metadata")
+        .len();
+    assert_eq!(tallies[&logs].stat(user).entries, 2);
+    assert_eq!(
+        tallies[&logs].stat(user).apparent_bytes,
+        logs_dir_bytes + 300
+    );
+    assert_eq!(tallies[&path_key(temp.path())].stat(user).entries, 2);
+    assert_eq!(
+        nodes[&path_key(&temp.path().join("top.bin"))]
+            .owner
+            .map(|owner| owner.uid),
+        Some(metadata.uid())
+    );
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn linux_walks_match_std_walk() {
+    use storagescope::platform::WalkBackend;
+    use storagescope::scanner::run_scan_blocking_with;
+
+    let temp = TempDir::new().expect("temp dir");
+    generate_tree(temp.path(), 2, 3);
+    fs::write(temp.path().join(".hidden"), vec![1_u8; 64]).expect("write hidden");
+    fs::hard_link(
+        temp.path().join("file-0.bin"),
+        temp.path().join("dir-0").join("linked.bin"),
+    )
+    .expect("hard link");
+    std::os::unix::fs::symlink("dir-1", temp.path().join("dir-link")).expect("dir symlink");
+    std::os::unix::fs::symlink("missing", temp.path().join("dangling")).expect("dangling");
+    // More entries than one stat batch.
+    let wide = temp.path().join("wide");
+    fs::create_dir(&wide).expect("create wide");
+    for index in 0..300 {
+        fs::write(wide.join(format!("{index}.bin")), vec![3_u8; index]).expect("write wide");
+    }
+
+    let backends = [
+        WalkBackend::Fd,
+        #[cfg(feature = "statx")]
+        WalkBackend::Statx,
+        #[cfg(feature = "statx")]
+        WalkBackend::StatxBatch,
+    ];
+
+    for follow_symlinks in [false, true] {
+        let scan_with = |backend: WalkBackend| {
+            run_scan_blocking_with(
+                ScanOptions {
+                    root: temp.path().to_path_buf(),
+                    one_file_system: true,
+                    follow_symlinks,
+                    show_hidden: true,
+                    show_files: true,
+                    max_depth: None,
+                    threads: 1,
+                    exclude_patterns: vec!["dir-2".to_string()],
+                    include_patterns: Vec::new(),
+                    report_excluded: true,
+                    stale_after: DEFAULT_STALE_AFTER,
+                },
+                backend,
+            )
+        };
+
+        let kinds = |events: &[ScanEvent]| {
+            collect_nodes(events)
+                .into_iter()
+                .map(|(path, node)| (path, (node.kind, node.owner, node.age)))
+                .collect::<HashMap<_, _>>()
+        };
+        let std_walk = scan_with(WalkBackend::Std);
+        for backend in backends {
+            let walk = scan_with(backend);
+            assert_eq!(final_nodes(&std_walk), final_nodes(&walk), "{backend:?}");
+            assert_eq!(complete_progress(&std_walk), complete_progress(&walk));
+            assert_eq!(kinds(&std_walk), kinds(&walk), "{backend:?}");
+        }
+    }
+}
+
+/// Builds a chain of `depth` nested `d` directories under `root`, letting
+/// `fill_leaf` populate the innermost one first. Each level is added at the
+/// top by wrapping the existing chain, so no path handed to the OS grows past
+/// a few components.
+fn generate_deep_chain(root: &Path, depth: usize, fill_leaf: impl FnOnce(&Path)) {
+    fs::create_dir(root.join("d")).expect("create leaf");
+    fill_leaf(&root.join("d"));
+    for _ in 1..depth {
+        fs::create_dir(root.join("wrap")).expect("create wrapper");
+        fs::rename(root.join("d"), root.join("wrap").join("d")).expect("wrap chain");
+        fs::rename(root.join("wrap"), root.join("d")).expect("rename wrapper");
+    }
+}
+
+/// Unwinds `generate_deep_chain` one level at a time from the top, since
+/// recursive removal would have to build the full-length paths.
+fn remove_deep_chain(root: &Path) {
+    loop {
+        match fs::rename(root.join("d").join("d"), root.join("inner")) {
+            Ok(()) => {
+                fs::remove_dir(root.join("d")).expect("remove level");
+                fs::rename(root.join("inner"), root.join("d")).expect("lift chain");
+            }
+            Err(_) => {
+                fs::remove_dir_all(root.join("d")).expect("remove leaf");
+                return;
+            }
+        }
+    }
+}
+
+#[test]
+fn deep_directory_chain_scans_without_recursion() {
+    const DEPTH: usize = 20_000;
+    let temp = TempDir::new().expect("temp dir");
+    generate_deep_chain(temp.path(), DEPTH, |_| {});
+
+    let root_len = temp.path().as_os_str().len();
+    for threads in [1, 4] {
+        let scan = start_scan(ScanOptions {
+            root: temp.path().to_path_buf(),
+            one_file_system: true,
+            follow_symlinks: false,
+            show_hidden: true,
+            show_files: true,
+            max_depth: None,
+            threads,
+            exclude_patterns: Vec::new(),
+            include_patterns: Vec::new(),
+            report_excluded: true,
+            stale_after: DEFAULT_STALE_AFTER,
+        });
+
+        // Events are consumed as they arrive and kept per level: collected, the
+        // full paths of a chain this deep would add up to gigabytes.
+        let mut by_level: Vec<Option<(bool, u64, u64, u64)>> = vec![None; DEPTH + 1];
+        let mut warnings = Vec::new();
+        let mut completed = false;
+        for event in scan.receiver().iter() {
+            match event {
+                ScanEvent::NodeUpdated(node) => {
+                    // Every level adds `/d`.
+                    let level = (node.path.as_os_str().len() - root_len) / 2;
+                    by_level[level] = Some((
+                        node.is_complete,
+                        node.apparent_bytes,
+                        node.allocated_bytes,
+                        node.children_count,
+                    ));
+                }
+                ScanEvent::Warning { message, .. } => warnings.push(message),
+                ScanEvent::Complete(_) => completed = true,
+                _ => {}
+            }
+        }
+        assert!(completed, "{threads} threads: scan did not complete");
+        assert!(warnings.is_empty(), "{threads} threads: {warnings:?}");
+
+        // Check every level completed and rolled up its child.
+        let mut levels = 0;
+        for pair in by_level.windows(2) {
+            let (complete, apparent, allocated, children) =
+                pair[0].expect("every level is reported");
+            assert!(complete, "{threads} threads: level {levels}");
+            let Some((_, child_apparent, child_allocated, _)) = pair[1] else {
+                break;
+            };
+            assert_eq!(children, 1);
+            assert!(apparent > child_apparent && allocated >= child_allocated);
+            levels += 1;
+        }
+        let (leaf_complete, _, _, leaf_children) = by_level[levels].expect("leaf is reported");
+        assert!(leaf_complete && leaf_children == 0);
+        assert_eq!(levels, DEPTH, "{threads} threads");
+    }
+
+    remove_deep_chain(temp.path());
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn followed_symlink_dirs_past_path_max_are_walked_once() {
+    // `/d` per level puts the leaf well past PATH_MAX (4096 bytes).
+    const DEPTH: usize = 3_000;
+    let temp = TempDir::new().expect("temp dir");
+    let files = temp.path().join("files");
+    fs::create_dir(&files).expect("create files dir");
+    fs::write(files.join("data.bin"), vec![1_u8; 8192]).expect("write data");
+    generate_deep_chain(temp.path(), DEPTH, |leaf| {
+        std::os::unix::fs::symlink(&files, leaf.join("target")).expect("target symlink");
+        std::os::unix::fs::symlink("..", leaf.join("loop")).expect("loop symlink");
+    });
+
+    for threads in [1, 4] {
+        let scan = start_scan(ScanOptions {
+            root: temp.path().to_path_buf(),
+            one_file_system: true,
+            follow_symlinks: true,
+            show_hidden: true,
+            show_files: false,
+            max_depth: None,
+            threads,
+            exclude_patterns: Vec::new(),
+            include_patterns: Vec::new(),
+            report_excluded: true,
+            stale_after: DEFAULT_STALE_AFTER,
+        });
+
+        let mut target_bytes = Vec::new();
+        let mut warnings = Vec::new();
+        let mut completed = false;
+        for event in scan.receiver().iter() {
+            match event {
+                ScanEvent::NodeUpdated(node)
+                    if node.is_complete && node.path.ends_with("d/target") =>
+                {
+                    target_bytes.push(node.apparent_bytes);
+                }
+                ScanEvent::Warning { message, .. } => warnings.push(message),
+                ScanEvent::Complete(_) => completed = true,
+                _ => {}
+            }
+        }
+        assert!(completed, "{threads} threads: scan did not complete");
+        // `target` and `loop` are each reached a second time through `loop/d`.
+        assert_eq!(
+            warnings, ["detected symlink cycle, skipping traversal"; 2],
+            "{threads} threads"
+        );
+        assert!(
+            target_bytes.iter().any(|bytes| *bytes >= 8192),
+            "{threads} threads: {target_bytes:?}"
+        );
+    }
+
+    remove_deep_chain(temp.path());
+}